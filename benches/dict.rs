@@ -27,6 +27,26 @@ where
     });
 }
 
+fn build_dict_from_iter_impl<K, V>(id: BenchmarkId, num_elements: usize, c: &mut Criterion)
+where
+    Standard: Distribution<K> + Distribution<V>,
+    K: Store + DictKey + Copy,
+    V: Store + Copy,
+{
+    let mut rng = rand_xorshift::XorShiftRng::from_seed([0u8; 16]);
+
+    let values = (0..num_elements)
+        .map(|_| (rng.gen::<K>(), rng.gen::<V>()))
+        .collect::<Vec<_>>();
+
+    c.bench_with_input(id, &values, |b, values| {
+        b.iter(|| {
+            let result = Dict::<K, V>::try_from_iter(values.iter().copied()).unwrap();
+            black_box(result);
+        });
+    });
+}
+
 fn build_dict_group(c: &mut Criterion) {
     macro_rules! decl_dict_benches {
         ($({ $n:literal, $k:ty, $v:ident }),*$(,)?) => {
@@ -39,6 +59,15 @@ fn build_dict_group(c: &mut Criterion) {
                     )
                 );
                 build_dict_impl::<$k, $v>(id, $n, c);
+
+                let id = BenchmarkId::new(
+                    "build_dict_from_iter",
+                    format!(
+                        "size={}; key={}; value={}",
+                        $n, stringify!($k), stringify!($v)
+                    )
+                );
+                build_dict_from_iter_impl::<$k, $v>(id, $n, c);
             });*
         };
     }
@@ -64,5 +93,51 @@ fn build_dict_group(c: &mut Criterion) {
     ];
 }
 
+fn iter_dict_impl<K, V>(id: BenchmarkId, num_elements: usize, c: &mut Criterion)
+where
+    Standard: Distribution<K> + Distribution<V>,
+    K: Store + DictKey,
+    V: Store + for<'a> Load<'a>,
+{
+    let mut rng = rand_xorshift::XorShiftRng::from_seed([0u8; 16]);
+
+    let mut dict = Dict::<K, V>::new();
+    for _ in 0..num_elements {
+        dict.set(rng.gen::<K>(), rng.gen::<V>()).unwrap();
+    }
+
+    c.bench_with_input(id, &dict, |b, dict| {
+        b.iter(|| {
+            for entry in dict.iter() {
+                black_box(entry.unwrap());
+            }
+        });
+    });
+}
+
+fn iter_dict_group(c: &mut Criterion) {
+    macro_rules! decl_dict_benches {
+        ($({ $n:literal, $k:ty, $v:ident }),*$(,)?) => {
+            $({
+                let id = BenchmarkId::new(
+                    "iter_dict",
+                    format!(
+                        "size={}; key={}; value={}",
+                        $n, stringify!($k), stringify!($v)
+                    )
+                );
+                iter_dict_impl::<$k, $v>(id, $n, c);
+            });*
+        };
+    }
+
+    decl_dict_benches![
+        { 100, u32, u64 },
+        { 10000, u32, u64 },
+        { 10000, u64, u64 },
+    ];
+}
+
 criterion_group!(build_dict, build_dict_group);
-criterion_main!(build_dict);
+criterion_group!(iter_dict, iter_dict_group);
+criterion_main!(build_dict, iter_dict);
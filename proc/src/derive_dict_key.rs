@@ -0,0 +1,122 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+use crate::internals::{ast, attr, ctxt};
+use crate::{bound, Derive};
+
+pub fn impl_derive(input: syn::DeriveInput) -> Result<TokenStream, Vec<syn::Error>> {
+    let cx = ctxt::Ctxt::new();
+    let container = match ast::Container::from_ast(&cx, &input, Derive::Debug) {
+        Some(container) => container,
+        None => return Err(cx.check().unwrap_err()),
+    };
+
+    if let ast::Data::Enum(_) = &container.data {
+        cx.error_spanned_by(
+            &container.original.ident,
+            "DictKey can only be derived for structs",
+        );
+    }
+    cx.check()?;
+
+    let ident = &container.ident;
+    let generics = bound::without_default(container.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let (style, fields) = match &container.data {
+        ast::Data::Struct(style, fields) => (*style, fields),
+        ast::Data::Enum(_) => unreachable!(),
+    };
+
+    let tag = container.attrs.tlb_tag.filter(|tag| tag.bits > 0);
+
+    let tag_bits = tag.map(|tag| tag.bits as u16).unwrap_or_default();
+    let field_bits = fields.iter().map(|field| {
+        let ty = field.ty;
+        quote!(<#ty as ::everscale_types::dict::DictKey>::BITS)
+    });
+
+    let condition = tag.map(load_tag_op);
+
+    let values = fields.iter().enumerate().map(|(i, field)| {
+        let ty = field.ty;
+        let value = format_ident!("__field{}", i);
+        let shift = if i + 1 < fields.len() {
+            quote! {
+                ::everscale_types::dict::shift_raw_key_data(
+                    &mut __raw_data,
+                    <#ty as ::everscale_types::dict::DictKey>::BITS,
+                );
+            }
+        } else {
+            quote!()
+        };
+
+        quote! {
+            let #value = match <#ty as ::everscale_types::dict::DictKey>::from_raw_data(&__raw_data) {
+                ::core::option::Option::Some(value) => value,
+                ::core::option::Option::None => return ::core::option::Option::None,
+            };
+            #shift
+        }
+    });
+
+    let members = fields.iter().enumerate().map(|(i, field)| {
+        let member = &field.member;
+        let value = format_ident!("__field{}", i);
+        quote!(#member: #value)
+    });
+
+    let result = match style {
+        ast::Style::Unit => quote!(Self),
+        _ => quote! {
+            Self {
+                #(#members),*
+            }
+        },
+    };
+
+    let mutability = if condition.is_some() || fields.len() > 1 {
+        quote!(mut)
+    } else {
+        quote!()
+    };
+
+    let result = quote! {
+        #[automatically_derived]
+        impl #impl_generics ::everscale_types::dict::DictKey for #ident #ty_generics #where_clause {
+            const BITS: u16 = #tag_bits #(+ #field_bits)*;
+
+            #[allow(unused_variables)]
+            fn from_raw_data(
+                __raw_data: &[u8; 128]
+            ) -> ::core::option::Option<Self> {
+                let #mutability __raw_data = *__raw_data;
+                #condition
+                #(#values)*
+                ::core::option::Option::Some(#result)
+            }
+        }
+    };
+
+    Ok(result)
+}
+
+fn load_tag_op(tag: attr::TlbTag) -> TokenStream {
+    let bits = tag.bits as u16;
+    let shift = 32 - bits as u32;
+    let value = tag.value;
+
+    quote! {
+        let __tag = u32::from_be_bytes([
+            __raw_data[0],
+            __raw_data[1],
+            __raw_data[2],
+            __raw_data[3],
+        ]) >> #shift;
+        if __tag != #value {
+            return ::core::option::Option::None;
+        }
+        ::everscale_types::dict::shift_raw_key_data(&mut __raw_data, #bits);
+    }
+}
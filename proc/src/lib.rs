@@ -2,6 +2,7 @@ use proc_macro::TokenStream;
 use quote::quote;
 
 mod bound;
+mod derive_dict_key;
 mod derive_load;
 mod derive_store;
 mod internals;
@@ -29,6 +30,19 @@ pub fn derive_store(input: TokenStream) -> TokenStream {
         .into()
 }
 
+/// Implements `DictKey` for the struct.
+///
+/// The key is a concatenation of the struct fields (preceded by the
+/// optional `#[tlb(tag = "...")]`), so the `Store` implementation
+/// must serialize them in the same order (e.g. `#[derive(Store)]`).
+#[proc_macro_derive(DictKey, attributes(bounds, tlb))]
+pub fn derive_dict_key(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as syn::DeriveInput);
+    derive_dict_key::impl_derive(input)
+        .unwrap_or_else(to_compile_errors)
+        .into()
+}
+
 fn to_compile_errors(errors: Vec<syn::Error>) -> proc_macro2::TokenStream {
     let compile_errors = errors.iter().map(syn::Error::to_compile_error);
     quote!(#(#compile_errors)*)
@@ -409,6 +409,13 @@ impl CellBuilder {
         impl_store_uint!(self, value, bytes: 16, bits: 128)
     }
 
+    /// Tries to store `i128` in the cell,
+    /// returning `false` if there is not enough remaining capacity.
+    #[inline]
+    pub fn store_i128(&mut self, value: i128) -> Result<(), Error> {
+        self.store_u128(value as u128)
+    }
+
     /// Tries to store 32 bytes in the cell,
     /// returning `false` if there is not enough remaining capacity.
     pub fn store_u256(&mut self, value: &HashBytes) -> Result<(), Error> {
@@ -559,6 +566,44 @@ impl CellBuilder {
         }
     }
 
+    /// Tries to store a two's complement `i64` in the cell (but only the specified
+    /// number of bits), returning `false` if there is not enough remaining capacity.
+    ///
+    /// Returns [`Error::IntOverflow`] if the value does not fit into `bits`.
+    ///
+    /// NOTE: if `bits` is greater than **64**, pads the value with its sign bit (as high bits).
+    pub fn store_int(&mut self, value: i64, bits: u16) -> Result<(), Error> {
+        if bits == 0 {
+            return if value == 0 {
+                Ok(())
+            } else {
+                Err(Error::IntOverflow)
+            };
+        }
+
+        if bits < 64 {
+            let shift = 64 - bits;
+            if (value << shift) >> shift != value {
+                return Err(Error::IntOverflow);
+            }
+        } else if value < 0 {
+            if self.bit_len + bits > MAX_BIT_LEN {
+                return Err(Error::CellOverflow);
+            }
+
+            // Store ones if bits is greater than 64
+            let mut offset = bits - 64;
+            while offset > 0 {
+                let chunk = std::cmp::min(offset, 64);
+                ok!(self.store_uint(u64::MAX, chunk));
+                offset -= chunk;
+            }
+            return self.store_u64(value as u64);
+        }
+
+        self.store_uint(value as u64, bits)
+    }
+
     /// Tries to store bytes in the cell (but only the specified number of bits),
     /// returning `false` if there is not enough remaining capacity.
     ///
@@ -567,6 +612,87 @@ impl CellBuilder {
         store_raw(&mut self.data, &mut self.bit_len, value, bits)
     }
 
+    /// Tries to store bytes in the "snake" layout: as many bytes as fit
+    /// into the current cell, and the rest in a chain of child cells
+    /// (each continuation is stored as the first and only reference).
+    ///
+    /// The snake data owns the references of the cell, so the builder must
+    /// not contain any references before, and nothing must be stored after it.
+    /// Returns [`Error::InvalidData`] if the builder already has references.
+    ///
+    /// Use [`store_snake_bytes`] if you want to use the default finalizer.
+    ///
+    /// [`store_snake_bytes`]: Self::store_snake_bytes
+    pub fn store_snake_bytes_ext(
+        &mut self,
+        value: &[u8],
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        const CHUNK_LEN: usize = (MAX_BIT_LEN / 8) as usize;
+
+        if !self.references.is_empty() {
+            return Err(Error::InvalidData);
+        }
+
+        let head_len = std::cmp::min((self.spare_bits_capacity() / 8) as usize, value.len());
+        let (head, tail) = value.split_at(head_len);
+
+        if tail.is_empty() {
+            return self.store_raw(head, head_len as u16 * 8);
+        }
+
+        // Build continuation cells starting from the last one
+        let mut child = None;
+        for chunk in tail.chunks(CHUNK_LEN).rev() {
+            let mut builder = CellBuilder::new();
+            ok!(builder.store_raw(chunk, chunk.len() as u16 * 8));
+            if let Some(child) = child {
+                ok!(builder.store_reference(child));
+            }
+            child = Some(ok!(builder.build_ext(finalizer)));
+        }
+
+        ok!(self.store_raw(head, head_len as u16 * 8));
+        match child {
+            Some(child) => self.store_reference(child),
+            None => Ok(()),
+        }
+    }
+
+    /// Tries to store bytes in the "snake" layout using the default finalizer.
+    ///
+    /// See [`store_snake_bytes_ext`] for the layout description.
+    ///
+    /// [`store_snake_bytes_ext`]: Self::store_snake_bytes_ext
+    #[inline]
+    pub fn store_snake_bytes(&mut self, value: &[u8]) -> Result<(), Error> {
+        self.store_snake_bytes_ext(value, &mut Cell::default_finalizer())
+    }
+
+    /// Tries to store a string in the "snake" layout.
+    ///
+    /// See [`store_snake_bytes_ext`] for the layout description.
+    ///
+    /// [`store_snake_bytes_ext`]: Self::store_snake_bytes_ext
+    #[inline]
+    pub fn store_snake_str_ext(
+        &mut self,
+        value: &str,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        self.store_snake_bytes_ext(value.as_bytes(), finalizer)
+    }
+
+    /// Tries to store a string in the "snake" layout using the default finalizer.
+    ///
+    /// See [`store_snake_bytes_ext`] for the layout description.
+    ///
+    /// [`store_snake_bytes_ext`]: Self::store_snake_bytes_ext
+    #[inline]
+    pub fn store_snake_str(&mut self, value: &str) -> Result<(), Error> {
+        self.store_snake_bytes(value.as_bytes())
+    }
+
     /// Tries to store all data bits of the specified cell in the current cell,
     /// returning `false` if there is not enough remaining capacity.
     #[inline]
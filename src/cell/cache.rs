@@ -0,0 +1,190 @@
+use std::collections::VecDeque;
+
+use super::{Cell, CellParts, DefaultFinalizer, Finalizer, HashBytes};
+use crate::error::Error;
+
+/// Shared size-bounded cache of cells for deduplicating identical subtrees.
+///
+/// Cells are looked up by their representation hash. When the cache is full,
+/// the oldest entries are evicted first.
+///
+/// With the `sync` feature the cache is protected by a mutex and can be
+/// shared between threads. Otherwise it uses a `RefCell` and, like the
+/// cells themselves, can only be used from a single thread.
+///
+/// # Example
+///
+/// ```
+/// # use everscale_types::prelude::*;
+/// # use everscale_types::cell::CellCache;
+/// let cache = CellCache::with_capacity(1000);
+///
+/// let first = CellBuilder::build_from(0xdeadbeafu32).unwrap();
+/// let first = cache.intern(first);
+///
+/// let mut builder = CellBuilder::new();
+/// builder.store_u32(0xdeadbeaf).unwrap();
+/// let second = builder.build_ext(&mut cache.finalizer()).unwrap();
+///
+/// // Both cells point to the same allocation
+/// assert!(std::ptr::eq(
+///     first.as_ref() as *const DynCell as *const u8,
+///     second.as_ref() as *const DynCell as *const u8,
+/// ));
+/// ```
+pub struct CellCache {
+    inner: SharedInner,
+    capacity: usize,
+}
+
+#[cfg(not(feature = "sync"))]
+type SharedInner = std::cell::RefCell<CellCacheInner>;
+
+#[cfg(feature = "sync")]
+type SharedInner = std::sync::Mutex<CellCacheInner>;
+
+#[derive(Default)]
+struct CellCacheInner {
+    cells: ahash::HashMap<HashBytes, Cell>,
+    order: VecDeque<HashBytes>,
+}
+
+impl CellCache {
+    /// Creates an empty cache which can hold at most `capacity` cells.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Default::default(),
+            capacity,
+        }
+    }
+
+    /// Returns the maximum number of cells in the cache.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of cells in the cache.
+    pub fn len(&self) -> usize {
+        self.with_inner(|inner| inner.cells.len())
+    }
+
+    /// Returns `true` if the cache contains no cells.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Removes all cells from the cache.
+    pub fn clear(&self) {
+        self.with_inner(|inner| {
+            inner.cells.clear();
+            inner.order.clear();
+        })
+    }
+
+    /// Returns a cached cell with the specified representation hash.
+    pub fn get(&self, repr_hash: &HashBytes) -> Option<Cell> {
+        self.with_inner(|inner| inner.cells.get(repr_hash).cloned())
+    }
+
+    /// Returns a cached cell with the same representation hash
+    /// or inserts the specified one.
+    pub fn intern(&self, cell: Cell) -> Cell {
+        if self.capacity == 0 {
+            return cell;
+        }
+
+        let capacity = self.capacity;
+        self.with_inner(|inner| {
+            if let Some(cached) = inner.cells.get(cell.repr_hash()) {
+                return cached.clone();
+            }
+
+            if inner.cells.len() >= capacity {
+                if let Some(oldest) = inner.order.pop_front() {
+                    inner.cells.remove(&oldest);
+                }
+            }
+
+            let repr_hash = *cell.repr_hash();
+            inner.order.push_back(repr_hash);
+            inner.cells.insert(repr_hash, cell.clone());
+            cell
+        })
+    }
+
+    #[inline]
+    fn with_inner<R>(&self, f: impl FnOnce(&mut CellCacheInner) -> R) -> R {
+        #[cfg(not(feature = "sync"))]
+        let mut inner = self.inner.borrow_mut();
+
+        #[cfg(feature = "sync")]
+        let mut inner = self.inner.lock().expect("lock failed");
+
+        f(&mut inner)
+    }
+
+    /// Creates a finalizer which deduplicates new cells using this cache.
+    pub fn finalizer(&self) -> CellCacheFinalizer<'_> {
+        CellCacheFinalizer {
+            cache: self,
+            inner: Cell::default_finalizer(),
+        }
+    }
+}
+
+/// Finalizer which looks up each new cell in the [`CellCache`].
+pub struct CellCacheFinalizer<'a> {
+    cache: &'a CellCache,
+    inner: <Cell as DefaultFinalizer>::Finalizer,
+}
+
+impl Finalizer for CellCacheFinalizer<'_> {
+    fn finalize_cell(&mut self, cell: CellParts<'_>) -> Result<Cell, Error> {
+        match self.inner.finalize_cell(cell) {
+            Ok(cell) => Ok(self.cache.intern(cell)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boc::Boc;
+    use crate::cell::CellBuilder;
+
+    #[test]
+    fn cache_dedup() -> anyhow::Result<()> {
+        let leaf = CellBuilder::build_from(0xdeadbeafu32)?;
+        let mut builder = CellBuilder::new();
+        builder.store_u8(123)?;
+        builder.store_reference(leaf.clone())?;
+        builder.store_reference(leaf)?;
+        let root = builder.build()?;
+        let boc = Boc::encode(root.as_ref());
+
+        let cache = CellCache::with_capacity(100);
+        let first = Boc::decode_ext(&boc, &mut cache.finalizer())?;
+        assert_eq!(first.as_ref(), root.as_ref());
+        assert_eq!(cache.len(), 2);
+
+        let second = Boc::decode_ext(&boc, &mut cache.finalizer())?;
+        assert_eq!(cache.len(), 2);
+        assert!(std::ptr::eq(
+            first.as_ref() as *const _ as *const u8,
+            second.as_ref() as *const _ as *const u8
+        ));
+
+        // Eviction
+        let cache = CellCache::with_capacity(1);
+        Boc::decode_ext(&boc, &mut cache.finalizer())?;
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get(root.repr_hash()).is_some());
+
+        cache.clear();
+        assert!(cache.is_empty());
+
+        Ok(())
+    }
+}
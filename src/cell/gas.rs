@@ -0,0 +1,280 @@
+use super::{Cell, CellParts, DefaultFinalizer, Finalizer, UsageTree, UsageTreeMode};
+use crate::error::Error;
+
+#[cfg(feature = "models")]
+use crate::models::config::GasLimitsPrices;
+
+/// Gas prices for cell operations.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CellGasPrices {
+    /// Gas price for each created cell.
+    pub cell_create: u64,
+    /// Gas price for each cell loaded for the first time.
+    pub cell_load: u64,
+}
+
+impl CellGasPrices {
+    /// Default TVM gas prices.
+    pub const TVM: Self = Self {
+        cell_create: 500,
+        cell_load: 100,
+    };
+}
+
+impl Default for CellGasPrices {
+    #[inline]
+    fn default() -> Self {
+        Self::TVM
+    }
+}
+
+/// Finalizer which charges gas for each created cell.
+///
+/// Fails with [`Error::OutOfGas`] as soon as the gas limit is reached.
+pub struct GasTrackingFinalizer {
+    inner: <Cell as DefaultFinalizer>::Finalizer,
+    prices: CellGasPrices,
+    gas_limit: u64,
+    gas_consumed: u64,
+}
+
+impl GasTrackingFinalizer {
+    /// Creates a new finalizer with the default TVM prices.
+    pub fn new(gas_limit: u64) -> Self {
+        Self::with_prices(CellGasPrices::TVM, gas_limit)
+    }
+
+    /// Creates a new finalizer with the default TVM prices and
+    /// the compute phase gas limit from the config.
+    #[cfg(feature = "models")]
+    pub fn from_gas_limits(gas_limits: &GasLimitsPrices) -> Self {
+        Self::new(gas_limits.gas_limit)
+    }
+
+    /// Creates a new finalizer with the specified prices.
+    pub fn with_prices(prices: CellGasPrices, gas_limit: u64) -> Self {
+        Self {
+            inner: Cell::default_finalizer(),
+            prices,
+            gas_limit,
+            gas_consumed: 0,
+        }
+    }
+
+    /// Returns the gas prices used by this finalizer.
+    #[inline]
+    pub fn prices(&self) -> &CellGasPrices {
+        &self.prices
+    }
+
+    /// Returns the gas limit.
+    #[inline]
+    pub fn gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    /// Returns the amount of gas consumed so far.
+    #[inline]
+    pub fn gas_consumed(&self) -> u64 {
+        self.gas_consumed
+    }
+
+    /// Returns the amount of gas left until the limit is reached.
+    #[inline]
+    pub fn gas_remaining(&self) -> u64 {
+        self.gas_limit.saturating_sub(self.gas_consumed)
+    }
+
+    /// Charges the specified amount of gas (e.g. from [`GasLoadTracker`]).
+    pub fn consume_gas(&mut self, amount: u64) -> Result<(), Error> {
+        match self.gas_consumed.checked_add(amount) {
+            Some(consumed) if consumed <= self.gas_limit => {
+                self.gas_consumed = consumed;
+                Ok(())
+            }
+            _ => {
+                self.gas_consumed = self.gas_limit;
+                Err(Error::OutOfGas)
+            }
+        }
+    }
+}
+
+impl Finalizer for GasTrackingFinalizer {
+    fn finalize_cell(&mut self, cell: CellParts<'_>) -> Result<Cell, Error> {
+        ok!(self.consume_gas(self.prices.cell_create));
+        self.inner.finalize_cell(cell)
+    }
+}
+
+/// Tracker which charges gas for each cell loaded for the first time.
+///
+/// Cells are wrapped the same way as in [`UsageTree`], and a cell is charged
+/// only when its data is accessed (like `CTOS` in TVM), so cells which are
+/// only moved between builders are free.
+///
+/// NOTE: cell access can't fail, so the tracker doesn't interrupt loads
+/// when the limit is reached. It only reports the consumed gas after the fact,
+/// use [`check`] to find out whether the limit was exceeded.
+///
+/// [`check`]: GasLoadTracker::check
+pub struct GasLoadTracker {
+    usage_tree: UsageTree,
+    prices: CellGasPrices,
+    gas_limit: u64,
+}
+
+impl GasLoadTracker {
+    /// Creates a new tracker with the default TVM prices.
+    pub fn new(gas_limit: u64) -> Self {
+        Self::with_prices(CellGasPrices::TVM, gas_limit)
+    }
+
+    /// Creates a new tracker with the default TVM prices and
+    /// the compute phase gas limit from the config.
+    #[cfg(feature = "models")]
+    pub fn from_gas_limits(gas_limits: &GasLimitsPrices) -> Self {
+        Self::new(gas_limits.gas_limit)
+    }
+
+    /// Creates a new tracker with the specified prices.
+    pub fn with_prices(prices: CellGasPrices, gas_limit: u64) -> Self {
+        Self {
+            usage_tree: UsageTree::new(UsageTreeMode::OnDataAccess),
+            prices,
+            gas_limit,
+        }
+    }
+
+    /// Wraps the specified cell to charge for the loaded cells.
+    pub fn track(&self, cell: &Cell) -> Cell {
+        self.usage_tree.track(cell)
+    }
+
+    /// Returns the number of distinct cells with accessed data.
+    pub fn loaded_cells(&self) -> u64 {
+        self.usage_tree.visited_count() as u64
+    }
+
+    /// Returns the amount of gas consumed so far.
+    pub fn gas_consumed(&self) -> u64 {
+        self.loaded_cells().saturating_mul(self.prices.cell_load)
+    }
+
+    /// Returns the gas limit.
+    #[inline]
+    pub fn gas_limit(&self) -> u64 {
+        self.gas_limit
+    }
+
+    /// Checks whether the gas limit is reached and returns the consumed gas.
+    pub fn check(&self) -> Result<u64, Error> {
+        let consumed = self.gas_consumed();
+        if consumed <= self.gas_limit {
+            Ok(consumed)
+        } else {
+            Err(Error::OutOfGas)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::CellBuilder;
+
+    #[test]
+    fn gas_tracking_finalizer() -> anyhow::Result<()> {
+        let mut finalizer = GasTrackingFinalizer::new(1200);
+
+        let mut builder = CellBuilder::new();
+        builder.store_u32(123)?;
+        let child = builder.build_ext(&mut finalizer)?;
+        assert_eq!(finalizer.gas_consumed(), 500);
+
+        let mut builder = CellBuilder::new();
+        builder.store_reference(child.clone())?;
+        builder.build_ext(&mut finalizer)?;
+        assert_eq!(finalizer.gas_consumed(), 1000);
+        assert_eq!(finalizer.gas_remaining(), 200);
+
+        let mut builder = CellBuilder::new();
+        builder.store_reference(child)?;
+        assert_eq!(builder.build_ext(&mut finalizer), Err(Error::OutOfGas));
+        assert_eq!(finalizer.consume_gas(1), Err(Error::OutOfGas));
+
+        // Custom prices
+        let prices = CellGasPrices {
+            cell_create: 10,
+            cell_load: 0,
+        };
+        let mut finalizer = GasTrackingFinalizer::with_prices(prices, 100);
+        for _ in 0..10 {
+            CellBuilder::new().build_ext(&mut finalizer)?;
+        }
+        assert_eq!(finalizer.gas_remaining(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn gas_load_tracker() -> anyhow::Result<()> {
+        let leaf = CellBuilder::build_from(0xdeadbeafu32)?;
+        let mut builder = CellBuilder::new();
+        builder.store_u8(123)?;
+        builder.store_reference(leaf.clone())?;
+        builder.store_reference(leaf)?;
+        let root = builder.build()?;
+
+        let tracker = GasLoadTracker::new(250);
+        let tracked = tracker.track(&root);
+        assert_eq!(tracker.check()?, 0);
+
+        let mut slice = tracked.as_slice()?;
+        assert_eq!(slice.load_u8()?, 123);
+        assert_eq!(tracker.check()?, 100);
+
+        // Cells which are only moved into a builder are free
+        let child = slice.load_reference_cloned()?;
+        let mut builder = CellBuilder::new();
+        builder.store_reference(child.clone())?;
+        builder.build()?;
+        assert_eq!(tracker.loaded_cells(), 1);
+
+        // Identical cells are charged only once
+        assert_eq!(child.as_slice()?.load_u32()?, 0xdeadbeaf);
+        assert_eq!(slice.load_reference()?.as_slice()?.load_u32()?, 0xdeadbeaf);
+        assert_eq!(tracker.loaded_cells(), 2);
+        assert_eq!(tracker.check()?, 200);
+
+        // Limit is only checked after the fact
+        let other = tracker.track(&CellBuilder::build_from(123u8)?);
+        assert_eq!(other.as_slice()?.load_u8()?, 123);
+        assert_eq!(tracker.check(), Err(Error::OutOfGas));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "models")]
+    #[test]
+    fn gas_from_config() {
+        let gas_limits = GasLimitsPrices {
+            gas_price: 65536000,
+            gas_limit: 1000000,
+            special_gas_limit: 100000000,
+            gas_credit: 10000,
+            block_gas_limit: 11000000,
+            freeze_due_limit: 100000000,
+            delete_due_limit: 1000000000,
+            flat_gas_limit: 100,
+            flat_gas_price: 100000,
+        };
+
+        let finalizer = GasTrackingFinalizer::from_gas_limits(&gas_limits);
+        assert_eq!(finalizer.gas_limit(), gas_limits.gas_limit);
+        assert_eq!(finalizer.prices(), &CellGasPrices::TVM);
+
+        let tracker = GasLoadTracker::from_gas_limits(&gas_limits);
+        assert_eq!(tracker.gas_limit(), gas_limits.gas_limit);
+    }
+}
@@ -5,9 +5,12 @@ use std::ops::{BitOr, BitOrAssign};
 use crate::error::Error;
 
 pub use self::builder::{CellBuilder, CellRefsBuilder, Store};
+pub use self::cache::{CellCache, CellCacheFinalizer};
 pub use self::cell_impl::StaticCell;
 pub use self::finalizer::{CellParts, DefaultFinalizer, Finalizer};
-pub use self::slice::{CellSlice, Load};
+pub use self::gas::{CellGasPrices, GasLoadTracker, GasTrackingFinalizer};
+pub use self::slice::{CellSlice, CellSliceRange, Load, OwnedCellSlice};
+pub use self::snake::{SnakeBytes, SnakeString, DEFAULT_SNAKE_MAX_LEN};
 pub use self::usage_tree::{UsageTree, UsageTreeMode, UsageTreeWithSubtrees};
 
 #[cfg(not(feature = "sync"))]
@@ -24,12 +27,21 @@ mod cell_impl;
 /// Cell finalization primitives.
 mod finalizer;
 
+/// Gas accounting for cell operations.
+mod gas;
+
 /// Cell view utils.
 mod slice;
 
 /// Cell creation utils.
 mod builder;
 
+/// Cell deduplication utils.
+mod cache;
+
+/// Snake data layout utils.
+mod snake;
+
 mod usage_tree;
 
 #[cfg(feature = "sync")]
@@ -40,6 +52,7 @@ mod __checks {
     assert_impl_all!(Cell: Send);
     assert_impl_all!(CellSlice: Send);
     assert_impl_all!(CellBuilder: Send);
+    assert_impl_all!(CellCache: Send, Sync);
 }
 
 /// Cell implementation family.
@@ -62,6 +75,11 @@ pub trait CellFamily: Sized {
     fn virtualize(cell: Cell) -> Cell;
 }
 
+/// A cell with a data and refs windows of its slice.
+///
+/// Used to return owned slices, e.g. values removed from a dictionary.
+pub type CellSliceParts = (Cell, CellSliceRange);
+
 /// Dyn trait type alias.
 #[cfg(not(feature = "sync"))]
 pub type DynCell = dyn CellImpl;
@@ -201,6 +219,30 @@ impl DynCell {
         }
     }
 
+    /// Computes the number of unique cells and bits in the cell tree.
+    ///
+    /// Returns `None` if the number of unique cells or bits exceeds the `limit`.
+    /// See [`StorageStat`] for the incremental version.
+    pub fn compute_unique_stats(&self, limit: CellTreeStats) -> Option<CellTreeStats> {
+        StorageStat::compute_for_cell(self, limit)
+    }
+
+    /// Creates a depth-first (pre-order) iterator through all cells in the tree.
+    ///
+    /// See [`CellTreeIter`] for the traversal options.
+    #[inline]
+    pub fn iter_dfs(&self) -> CellTreeIter<'_> {
+        CellTreeIter::new(self, TraversalOrder::DepthFirst)
+    }
+
+    /// Creates a breadth-first iterator through all cells in the tree.
+    ///
+    /// See [`CellTreeIter`] for the traversal options.
+    #[inline]
+    pub fn iter_bfs(&self) -> CellTreeIter<'_> {
+        CellTreeIter::new(self, TraversalOrder::BreadthFirst)
+    }
+
     /// Returns this cell as a cell slice.
     /// Returns an error if the cell is pruned.
     #[inline]
@@ -467,6 +509,120 @@ impl ExactSizeIterator for ClonedRefsIter<'_> {
     }
 }
 
+/// Cell tree traversal order.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TraversalOrder {
+    /// Depth-first (pre-order) traversal.
+    DepthFirst,
+    /// Breadth-first traversal.
+    BreadthFirst,
+}
+
+/// An iterator through all cells in the cell tree.
+///
+/// Yields pairs of the cell depth relative to the root and the cell itself.
+/// Uses an explicit queue, so it can be used for very deep trees.
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct CellTreeIter<'a> {
+    queue: std::collections::VecDeque<(u16, &'a DynCell)>,
+    order: TraversalOrder,
+    visited: Option<ahash::HashSet<&'a HashBytes>>,
+    skip_pruned_branches: bool,
+}
+
+impl<'a> CellTreeIter<'a> {
+    /// Creates an iterator through the tree of the specified root cell.
+    pub fn new(root: &'a DynCell, order: TraversalOrder) -> Self {
+        let mut queue = std::collections::VecDeque::new();
+        queue.push_back((0, root));
+        Self {
+            queue,
+            order,
+            visited: None,
+            skip_pruned_branches: false,
+        }
+    }
+
+    /// Yields each unique cell (by its representation hash) only once.
+    ///
+    /// For the depth-first order a cell is yielded at its first position
+    /// in the pre-order traversal, for the breadth-first order at its
+    /// smallest depth.
+    pub fn unique(mut self) -> Self {
+        if self.visited.is_none() {
+            let mut visited = ahash::HashSet::default();
+            // Depth-first traversal marks cells when they are popped
+            if self.order == TraversalOrder::BreadthFirst {
+                for (_, cell) in &self.queue {
+                    visited.insert(cell.repr_hash());
+                }
+            }
+            self.visited = Some(visited);
+        }
+        self
+    }
+
+    /// Doesn't yield pruned branch cells.
+    ///
+    /// NOTE: pruned branches never have children, so the traversal
+    /// always stops at them.
+    pub fn skip_pruned_branches(mut self) -> Self {
+        self.skip_pruned_branches = true;
+        self
+    }
+}
+
+impl<'a> Iterator for CellTreeIter<'a> {
+    type Item = (u16, &'a DynCell);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (depth, cell) = match self.order {
+                TraversalOrder::DepthFirst => self.queue.pop_back(),
+                TraversalOrder::BreadthFirst => self.queue.pop_front(),
+            }?;
+
+            if self.order == TraversalOrder::DepthFirst {
+                if let Some(visited) = &mut self.visited {
+                    if !visited.insert(cell.repr_hash()) {
+                        continue;
+                    }
+                }
+            }
+
+            let descriptor = cell.descriptor();
+            if descriptor.is_pruned_branch() {
+                if self.skip_pruned_branches {
+                    continue;
+                }
+                return Some((depth, cell));
+            }
+
+            let order = self.order;
+            let mut push_child = |child: &'a DynCell| {
+                if let Some(visited) = &mut self.visited {
+                    let is_new = match order {
+                        TraversalOrder::DepthFirst => !visited.contains(child.repr_hash()),
+                        TraversalOrder::BreadthFirst => visited.insert(child.repr_hash()),
+                    };
+                    if !is_new {
+                        return;
+                    }
+                }
+                self.queue.push_back((depth + 1, child));
+            };
+
+            match self.order {
+                // Push children in reverse order to pop them in the direct one
+                TraversalOrder::DepthFirst => cell.references().rev().for_each(&mut push_child),
+                TraversalOrder::BreadthFirst => cell.references().for_each(&mut push_child),
+            }
+
+            return Some((depth, cell));
+        }
+    }
+}
+
 /// Type alias for a cell hash.
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 #[repr(transparent)]
@@ -917,9 +1073,9 @@ impl std::fmt::Debug for LevelMask {
 
 /// Cell tree storage stats.
 ///
-/// NOTE: identical cells are counted each time they occur in the tree.
+/// NOTE: identical cells are counted each time they occur in the tree
+/// (see [`StorageStat`] for the unique stats).
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
-#[cfg(feature = "stats")]
 pub struct CellTreeStats {
     /// Total number of bits in tree.
     pub bit_count: u64,
@@ -927,7 +1083,21 @@ pub struct CellTreeStats {
     pub cell_count: u64,
 }
 
-#[cfg(feature = "stats")]
+impl CellTreeStats {
+    /// The additive identity for this type, i.e. `0`.
+    pub const ZERO: Self = Self {
+        bit_count: 0,
+        cell_count: 0,
+    };
+
+    /// The largest value that can be represented by this type
+    /// (can be used as an unbounded limit).
+    pub const MAX: Self = Self {
+        bit_count: u64::MAX,
+        cell_count: u64::MAX,
+    };
+}
+
 impl std::ops::Add for CellTreeStats {
     type Output = Self;
 
@@ -940,7 +1110,6 @@ impl std::ops::Add for CellTreeStats {
     }
 }
 
-#[cfg(feature = "stats")]
 impl std::ops::AddAssign for CellTreeStats {
     #[inline]
     fn add_assign(&mut self, rhs: Self) {
@@ -949,6 +1118,96 @@ impl std::ops::AddAssign for CellTreeStats {
     }
 }
 
+/// A helper to track the size of the unique data in multiple cell trees.
+///
+/// Cells are deduplicated by their representation hash and the traversal
+/// uses an explicit stack, so it is safe to use for very deep trees.
+pub struct StorageStat<'a> {
+    visited: ahash::HashSet<&'a HashBytes>,
+    stack: Vec<RefsIter<'a>>,
+    stats: CellTreeStats,
+    limit: CellTreeStats,
+}
+
+impl<'a> StorageStat<'a> {
+    /// Computes the number of unique cells and bits in the cell tree,
+    /// visiting each distinct cell only once.
+    ///
+    /// Returns `None` if the number of unique cells or bits exceeds the `limit`.
+    pub fn compute_for_cell(cell: &'a DynCell, limit: CellTreeStats) -> Option<CellTreeStats> {
+        let mut this = Self::with_limit(limit);
+        if this.add_cell(cell) {
+            Some(this.stats)
+        } else {
+            None
+        }
+    }
+
+    /// Creates a new storage stat state with an explicit limit
+    /// on the number of unique cells and bits.
+    pub fn with_limit(limit: CellTreeStats) -> Self {
+        Self {
+            visited: Default::default(),
+            stack: Vec::new(),
+            stats: CellTreeStats::default(),
+            limit,
+        }
+    }
+
+    /// Creates a new storage stat state without a limit.
+    pub fn unlimited() -> Self {
+        Self::with_limit(CellTreeStats::MAX)
+    }
+
+    /// Returns the stats for all unique cells added so far.
+    pub fn stats(&self) -> CellTreeStats {
+        self.stats
+    }
+
+    /// Merges the stats of the cell tree into the current state.
+    ///
+    /// Returns `false` if the limit was reached. The state is left
+    /// partially updated in that case.
+    pub fn add_cell(&mut self, cell: &'a DynCell) -> bool {
+        if !self.visit(cell) {
+            return false;
+        }
+
+        while let Some(iter) = self.stack.last_mut() {
+            match iter.next() {
+                Some(child) => {
+                    if !self.visit(child) {
+                        self.stack.clear();
+                        return false;
+                    }
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+
+        true
+    }
+
+    fn visit(&mut self, cell: &'a DynCell) -> bool {
+        if !self.visited.insert(cell.repr_hash()) {
+            return true;
+        }
+
+        self.stats.bit_count += cell.bit_len() as u64;
+        self.stats.cell_count += 1;
+        if self.stats.cell_count > self.limit.cell_count
+            || self.stats.bit_count > self.limit.bit_count
+        {
+            return false;
+        }
+
+        self.stack.push(cell.references());
+        true
+    }
+}
+
 /// Helper struct to debug print the root cell.
 #[derive(Clone, Copy)]
 pub struct DebugCell<'a>(&'a DynCell);
@@ -1026,6 +1285,121 @@ pub const MAX_REF_COUNT: usize = 4;
 mod tests {
     use super::*;
 
+    #[test]
+    fn cell_tree_iter() -> anyhow::Result<()> {
+        fn build(value: u8, refs: &[&Cell]) -> Cell {
+            let mut builder = CellBuilder::new();
+            builder.store_u8(value).unwrap();
+            for cell in refs {
+                builder.store_reference((*cell).clone()).unwrap();
+            }
+            builder.build().unwrap()
+        }
+
+        fn collect(iter: CellTreeIter<'_>) -> Vec<(u16, u8)> {
+            iter.map(|(depth, cell)| (depth, cell.data()[0])).collect()
+        }
+
+        let c = build(3, &[]);
+        let a = build(1, &[&c]);
+        let b = build(2, &[&c]);
+        let root = build(0, &[&a, &b]);
+
+        let dfs = collect(root.iter_dfs());
+        assert_eq!(dfs, [(0, 0), (1, 1), (2, 3), (1, 2), (2, 3)]);
+        let dfs = collect(root.iter_dfs().unique());
+        assert_eq!(dfs, [(0, 0), (1, 1), (2, 3), (1, 2)]);
+
+        let bfs = collect(root.iter_bfs());
+        assert_eq!(bfs, [(0, 0), (1, 1), (1, 2), (2, 3), (2, 3)]);
+        let bfs = collect(root.iter_bfs().unique());
+        assert_eq!(bfs, [(0, 0), (1, 1), (1, 2), (2, 3)]);
+
+        // Shared cell at different depths
+        let a = build(1, &[&c]);
+        let root = build(0, &[&a, &c]);
+
+        let dfs = collect(root.iter_dfs().unique());
+        assert_eq!(dfs, [(0, 0), (1, 1), (2, 3)]);
+        let bfs = collect(root.iter_bfs().unique());
+        assert_eq!(bfs, [(0, 0), (1, 1), (1, 3)]);
+
+        let b = build(2, &[&c]);
+        let root = build(0, &[&build(4, &[&b]), &a, &c]);
+        let dfs = collect(root.iter_dfs().unique());
+        assert_eq!(dfs, [(0, 0), (1, 4), (2, 2), (3, 3), (1, 1)]);
+
+        // Pruned branches
+        let proof =
+            crate::merkle::MerkleProof::create_for_cell(root.as_ref(), a.repr_hash()).build()?;
+        let all = proof.cell.iter_dfs().count();
+        let pruned = proof
+            .cell
+            .iter_dfs()
+            .filter(|(_, cell)| cell.descriptor().is_pruned_branch())
+            .count();
+        assert!(pruned > 0);
+        let skipped = proof.cell.iter_dfs().skip_pruned_branches();
+        assert_eq!(skipped.count(), all - pruned);
+
+        // Very deep tree
+        let mut cell = Cell::empty_cell();
+        for _ in 0..1000 {
+            cell = build(0, &[&cell]);
+        }
+        assert_eq!(cell.iter_dfs().count(), 1001);
+        assert_eq!(cell.iter_bfs().last().map(|(depth, _)| depth), Some(1000));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unique_stats() -> anyhow::Result<()> {
+        let leaf = CellBuilder::build_from(0xdeadbeafu32)?;
+        let mut builder = CellBuilder::new();
+        builder.store_bit_one()?;
+        builder.store_reference(leaf.clone())?;
+        builder.store_reference(leaf.clone())?;
+        let root = builder.build()?;
+
+        let stats = root.compute_unique_stats(CellTreeStats::MAX).unwrap();
+        assert_eq!(stats.cell_count, 2);
+        assert_eq!(stats.bit_count, 33);
+
+        assert_eq!(root.compute_unique_stats(stats), Some(stats));
+
+        // Cell limit
+        let limit = CellTreeStats {
+            cell_count: 1,
+            ..CellTreeStats::MAX
+        };
+        assert_eq!(root.compute_unique_stats(limit), None);
+
+        // Bit limit
+        let limit = CellTreeStats {
+            bit_count: 32,
+            ..CellTreeStats::MAX
+        };
+        assert_eq!(root.compute_unique_stats(limit), None);
+
+        // Multiple trees
+        let mut stat = StorageStat::unlimited();
+        assert!(stat.add_cell(leaf.as_ref()));
+        assert!(stat.add_cell(root.as_ref()));
+        assert_eq!(stat.stats(), stats);
+
+        // Very deep tree
+        let mut cell = Cell::empty_cell();
+        for i in 0..2000u32 {
+            cell = CellBuilder::build_from((i % 1000, cell))?;
+        }
+        let stats = cell.compute_unique_stats(CellTreeStats::MAX).unwrap();
+        assert_eq!(stats.cell_count, 2001);
+        assert_eq!(stats.bit_count, 2000 * 32);
+
+        Ok(())
+    }
+
     #[test]
     fn correct_level() {
         const LEVEL: [u8; 8] = [0, 1, 1, 2, 1, 2, 2, 3];
@@ -2,7 +2,10 @@ use std::num::{NonZeroU16, NonZeroU32, NonZeroU8};
 use std::rc::Rc;
 use std::sync::Arc;
 
-use crate::cell::{Cell, CellType, DynCell, HashBytes, LevelMask, RefsIter};
+use crate::cell::{
+    Cell, CellBuilder, CellSliceParts, CellType, DynCell, Finalizer, HashBytes, LevelMask,
+    RefsIter, Store,
+};
 use crate::error::Error;
 use crate::util::unlikely;
 
@@ -181,6 +184,189 @@ impl<'a> Clone for CellSlice<'a> {
 
 impl<'a> Copy for CellSlice<'a> {}
 
+/// Indices of the slice data and refs windows.
+///
+/// Can be used to store a slice position without borrowing the cell.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct CellSliceRange {
+    bits_start: u16,
+    bits_end: u16,
+    refs_start: u8,
+    refs_end: u8,
+}
+
+impl CellSliceRange {
+    /// Returns an empty slice range.
+    pub const fn empty() -> Self {
+        Self {
+            bits_start: 0,
+            bits_end: 0,
+            refs_start: 0,
+            refs_end: 0,
+        }
+    }
+
+    /// Returns a full range for the specified cell.
+    pub fn full(cell: &DynCell) -> Self {
+        Self {
+            bits_start: 0,
+            bits_end: cell.bit_len(),
+            refs_start: 0,
+            refs_end: cell.reference_count(),
+        }
+    }
+
+    /// Constructs a new cell slice from the specified cell using the current range.
+    /// Returns an error if the cell is pruned or the range is out of cell bounds.
+    pub fn apply<'a>(self, cell: &'a DynCell) -> Result<CellSlice<'a>, Error> {
+        if unlikely(cell.descriptor().is_pruned_branch()) {
+            return Err(Error::PrunedBranchAccess);
+        }
+        if self.bits_start > self.bits_end
+            || self.refs_start > self.refs_end
+            || self.bits_end > cell.bit_len()
+            || self.refs_end > cell.reference_count()
+        {
+            return Err(Error::CellUnderflow);
+        }
+        Ok(CellSlice {
+            cell,
+            bits_window_start: self.bits_start,
+            bits_window_end: self.bits_end,
+            refs_window_start: self.refs_start,
+            refs_window_end: self.refs_end,
+        })
+    }
+
+    /// Returns the number of remaining bits of data in the range.
+    pub const fn remaining_bits(&self) -> u16 {
+        self.bits_end.saturating_sub(self.bits_start)
+    }
+
+    /// Returns the number of remaining references in the range.
+    pub const fn remaining_refs(&self) -> u8 {
+        self.refs_end.saturating_sub(self.refs_start)
+    }
+
+    /// Returns the start of the data window.
+    #[inline]
+    pub const fn bits_offset(&self) -> u16 {
+        self.bits_start
+    }
+
+    /// Returns the start of the references window.
+    #[inline]
+    pub const fn refs_offset(&self) -> u8 {
+        self.refs_start
+    }
+}
+
+/// An owned version of [`CellSlice`]: a cell with its data and refs windows.
+///
+/// Unlike [`CellSlice`] it doesn't borrow the cell, so it can be stored
+/// in long-lived structures or sent across threads.
+#[derive(Debug, Clone)]
+pub struct OwnedCellSlice {
+    cell: Cell,
+    range: CellSliceRange,
+}
+
+impl OwnedCellSlice {
+    /// Constructs a new owned slice for the whole cell.
+    /// Returns an error if the cell is pruned.
+    pub fn new(cell: Cell) -> Result<Self, Error> {
+        if unlikely(cell.descriptor().is_pruned_branch()) {
+            return Err(Error::PrunedBranchAccess);
+        }
+        let range = CellSliceRange::full(cell.as_ref());
+        Ok(Self { cell, range })
+    }
+
+    /// Constructs a new owned slice from the cell and its range.
+    /// Returns an error if the cell is pruned or the range is out of cell bounds.
+    pub fn from_parts(cell: Cell, range: CellSliceRange) -> Result<Self, Error> {
+        ok!(range.apply(cell.as_ref()));
+        Ok(Self { cell, range })
+    }
+
+    /// Returns a reference to the underlying cell.
+    #[inline]
+    pub fn cell(&self) -> &Cell {
+        &self.cell
+    }
+
+    /// Returns the data and refs windows of this slice.
+    #[inline]
+    pub const fn range(&self) -> CellSliceRange {
+        self.range
+    }
+
+    /// Returns a borrowed slice for the same windows.
+    #[inline]
+    pub fn as_slice(&self) -> CellSlice<'_> {
+        CellSlice {
+            cell: self.cell.as_ref(),
+            bits_window_start: self.range.bits_start,
+            bits_window_end: self.range.bits_end,
+            refs_window_start: self.range.refs_start,
+            refs_window_end: self.range.refs_end,
+        }
+    }
+
+    /// Updates the windows of this slice (e.g. to the [`range`] of a slice
+    /// returned by [`as_slice`] after some data was read from it).
+    /// Returns an error if the range is out of cell bounds.
+    ///
+    /// [`range`]: CellSlice::range
+    /// [`as_slice`]: Self::as_slice
+    pub fn set_range(&mut self, range: CellSliceRange) -> Result<(), Error> {
+        ok!(range.apply(self.cell.as_ref()));
+        self.range = range;
+        Ok(())
+    }
+
+    /// Splits this slice into its cell and range.
+    #[inline]
+    pub fn into_parts(self) -> CellSliceParts {
+        (self.cell, self.range)
+    }
+}
+
+impl TryFrom<CellSliceParts> for OwnedCellSlice {
+    type Error = Error;
+
+    #[inline]
+    fn try_from((cell, range): CellSliceParts) -> Result<Self, Self::Error> {
+        Self::from_parts(cell, range)
+    }
+}
+
+impl From<OwnedCellSlice> for CellSliceParts {
+    #[inline]
+    fn from(value: OwnedCellSlice) -> Self {
+        value.into_parts()
+    }
+}
+
+impl Store for OwnedCellSlice {
+    #[inline]
+    fn store_into(&self, builder: &mut CellBuilder, _: &mut dyn Finalizer) -> Result<(), Error> {
+        builder.store_slice(self.as_slice())
+    }
+}
+
+impl<'a> Load<'a> for OwnedCellSlice {
+    /// Copies all remaining bits and refs into a new cell.
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        let mut builder = CellBuilder::new();
+        ok!(builder.store_slice(slice.load_remaining()));
+        match builder.build() {
+            Ok(cell) => Self::new(cell),
+            Err(e) => Err(e),
+        }
+    }
+}
+
 impl<'a> CellSlice<'a> {
     /// Constructs a new cell slice from the specified cell.
     /// Returns an error if the cell is pruned.
@@ -221,6 +407,17 @@ impl<'a> CellSlice<'a> {
         self.cell
     }
 
+    /// Returns the data and refs windows of this slice.
+    #[inline]
+    pub const fn range(&self) -> CellSliceRange {
+        CellSliceRange {
+            bits_start: self.bits_window_start,
+            bits_end: self.bits_window_end,
+            refs_start: self.refs_window_start,
+            refs_end: self.refs_window_end,
+        }
+    }
+
     /// Computes cell type from descriptor bytes.
     #[inline]
     pub fn cell_type(&self) -> CellType {
@@ -481,7 +678,86 @@ impl<'a> CellSlice<'a> {
         self.get_prefix(prefix_len, 0)
     }
 
-    fn longest_common_data_prefix_impl(&self, other: &Self, max_hint: u16) -> u16 {
+    /// Returns a subslice with the data suffix removed.
+    ///
+    /// If the slice ends with `suffix`, returns the subslice before the suffix, wrapped in `Some`.
+    /// If `suffix` is empty, simply returns the original slice.
+    ///
+    /// If the slice does not end with `suffix`, returns `None`.
+    pub fn strip_data_suffix(&self, suffix: &CellSlice<'_>) -> Option<CellSlice<'a>> {
+        if suffix.is_data_suffix_of(self) {
+            let mut result = *self;
+            result.bits_window_end -= suffix.remaining_bits();
+            Some(result)
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if the data of this slice is a prefix of the `other` slice data.
+    pub fn is_data_prefix_of(&self, other: &CellSlice<'_>) -> bool {
+        let bits = self.remaining_bits();
+        bits <= other.remaining_bits() && self.longest_common_data_prefix_impl(other, bits) >= bits
+    }
+
+    /// Returns `true` if the data of this slice is a suffix of the `other` slice data.
+    pub fn is_data_suffix_of(&self, other: &CellSlice<'_>) -> bool {
+        let bits = self.remaining_bits();
+        let Some(offset) = other.remaining_bits().checked_sub(bits) else {
+            return false;
+        };
+
+        let mut other = *other;
+        other.bits_window_start += offset;
+        self.longest_common_data_prefix_impl(&other, bits) >= bits
+    }
+
+    /// Returns `true` if both slices contain the same data bits
+    /// (regardless of their offsets in cells).
+    pub fn data_eq(&self, other: &CellSlice<'_>) -> bool {
+        let bits = self.remaining_bits();
+        bits == other.remaining_bits() && self.longest_common_data_prefix_impl(other, bits) >= bits
+    }
+
+    /// Lexicographically compares the data bits of two slices.
+    ///
+    /// A slice which is a prefix of the other slice is considered less.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use everscale_types::prelude::CellBuilder;
+    /// # use std::cmp::Ordering;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let cell = {
+    ///     let mut builder = CellBuilder::new();
+    ///     builder.store_u16(0xdead)?;
+    ///     builder.build()?
+    /// };
+    /// let slice = cell.as_slice()?;
+    ///
+    /// let mut other = slice;
+    /// other.try_advance(4, 0); // 0xead
+    /// assert_eq!(slice.lex_cmp(&other), Ordering::Less);
+    /// assert_eq!(slice.get_prefix(4, 0).lex_cmp(&slice), Ordering::Less);
+    /// # Ok(()) }
+    /// ```
+    pub fn lex_cmp(&self, other: &CellSlice<'_>) -> std::cmp::Ordering {
+        let self_bits = self.remaining_bits();
+        let other_bits = other.remaining_bits();
+
+        let prefix_len = self.longest_common_data_prefix_impl(other, u16::MAX);
+        if prefix_len >= std::cmp::min(self_bits, other_bits) {
+            return self_bits.cmp(&other_bits);
+        }
+
+        // Compare the first different bit
+        let self_bit = self.get_bit(prefix_len).unwrap_or_default();
+        let other_bit = other.get_bit(prefix_len).unwrap_or_default();
+        self_bit.cmp(&other_bit)
+    }
+
+    fn longest_common_data_prefix_impl(&self, other: &CellSlice<'_>, max_hint: u16) -> u16 {
         if self.bits_window_start >= self.bits_window_end
             || other.bits_window_start >= other.bits_window_end
         {
@@ -539,7 +815,8 @@ impl<'a> CellSlice<'a> {
                     // Some bits are not equal
                     x => {
                         // Number of leading zeros is the number of equal bits
-                        return prefix_len + x.leading_zeros() as u16;
+                        // (the last aligned byte can contain bits outside the window)
+                        return std::cmp::min(prefix_len + x.leading_zeros() as u16, max_bit_len);
                     }
                 }
             }
@@ -876,6 +1153,23 @@ impl<'a> CellSlice<'a> {
         res
     }
 
+    /// Reads `i128` starting from the `offset`.
+    #[inline]
+    pub fn get_i128(&self, offset: u16) -> Result<i128, Error> {
+        match self.get_u128(offset) {
+            Ok(value) => Ok(value as i128),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Tries to read the next `i128`, incrementing the bits window start.
+    #[inline]
+    pub fn load_i128(&mut self) -> Result<i128, Error> {
+        let res = self.get_i128(0);
+        self.bits_window_start += 128 * res.is_ok() as u16;
+        res
+    }
+
     /// Reads 32 bytes starting from the `offset`.
     pub fn get_u256(&self, offset: u16) -> Result<HashBytes, Error> {
         if self.bits_window_start + offset + 256 <= self.bits_window_end {
@@ -1053,6 +1347,33 @@ impl<'a> CellSlice<'a> {
         res
     }
 
+    /// Reads a two's complement `i64` from the cell (but only the specified
+    /// number of bits) starting from the `offset`. The value is sign-extended.
+    ///
+    /// NOTE: Reading zero bits always succeeds,
+    /// and reading more than 64 bits always fails.
+    pub fn get_int(&self, offset: u16, bits: u16) -> Result<i64, Error> {
+        match self.get_uint(offset, bits) {
+            Ok(value) if bits > 0 => {
+                let shift = 64 - bits;
+                Ok(((value << shift) as i64) >> shift)
+            }
+            Ok(_) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Tries to read the next two's complement `i64` (but only the specified
+    /// number of bits), incrementing the bits window start.
+    ///
+    /// NOTE: Reading zero bits always succeeds,
+    /// and reading more than 64 bits always fails.
+    pub fn load_int(&mut self, bits: u16) -> Result<i64, Error> {
+        let res = self.get_int(0, bits);
+        self.bits_window_start += bits * res.is_ok() as u16;
+        res
+    }
+
     /// Reads the specified number of bits to the target starting from the `offset`.
     pub fn get_raw<'b>(
         &'_ self,
@@ -1130,6 +1451,59 @@ impl<'a> CellSlice<'a> {
         res
     }
 
+    /// Reads bytes stored in the "snake" layout: all remaining bytes of the
+    /// current slice, continued in a chain of child cells (the first and only
+    /// reference of each cell). Returns an error if there are more than
+    /// `max_len` bytes or if any cell in the chain has more than one reference.
+    ///
+    /// See [`CellBuilder::store_snake_bytes_ext`] for the layout description.
+    ///
+    /// [`CellBuilder::store_snake_bytes_ext`]: crate::cell::CellBuilder::store_snake_bytes_ext
+    pub fn load_snake_bytes(&mut self, max_len: usize) -> Result<Vec<u8>, Error> {
+        let mut result = Vec::new();
+
+        let mut slice = *self;
+        loop {
+            let bits = slice.remaining_bits();
+            if bits % 8 != 0 {
+                return Err(Error::InvalidData);
+            }
+
+            let len = (bits / 8) as usize;
+            if result.len() + len > max_len {
+                return Err(Error::InvalidData);
+            }
+
+            let offset = result.len();
+            result.resize(offset + len, 0);
+            ok!(slice.load_raw(&mut result[offset..], bits));
+
+            match slice.remaining_refs() {
+                0 => break,
+                1 => slice = ok!(slice.load_reference_as_slice()),
+                _ => return Err(Error::InvalidData),
+            }
+        }
+
+        self.bits_window_start = self.bits_window_end;
+        self.refs_window_start += !self.is_refs_empty() as u8;
+        Ok(result)
+    }
+
+    /// Reads an UTF-8 string stored in the "snake" layout.
+    /// Returns an error if there are more than `max_len` bytes.
+    ///
+    /// See [`load_snake_bytes`] for the layout description.
+    ///
+    /// [`load_snake_bytes`]: Self::load_snake_bytes
+    pub fn load_snake_string(&mut self, max_len: usize) -> Result<String, Error> {
+        let bytes = ok!(self.load_snake_bytes(max_len));
+        match String::from_utf8(bytes) {
+            Ok(string) => Ok(string),
+            Err(_) => Err(Error::InvalidData),
+        }
+    }
+
     /// Reads all remaining bits and refs into the new slice.
     pub fn load_remaining(&mut self) -> CellSlice<'a> {
         let result = *self;
@@ -1245,6 +1619,7 @@ impl<'a> CellSlice<'a> {
 
 #[cfg(test)]
 mod tests {
+    use super::OwnedCellSlice;
     use crate::error::Error;
     use crate::prelude::*;
 
@@ -1364,6 +1739,20 @@ mod tests {
             .longest_common_data_prefix(&slice2.get_prefix(5, 0));
         assert_eq!(prefix.remaining_bits(), 5);
 
+        // Bits outside the window must not affect the prefix
+        let cell1 = build_cell(|b| b.store_u16(0));
+        let mut slice1 = cell1.as_slice()?;
+        slice1.try_advance(3, 0);
+
+        let cell2 = build_cell(|b| b.store_u16(0x003f));
+        let mut slice2 = cell2.as_slice()?;
+        slice2.try_advance(3, 0);
+
+        let prefix = slice1
+            .get_prefix(10, 0)
+            .longest_common_data_prefix(&slice2.get_prefix(6, 0));
+        assert_eq!(prefix.remaining_bits(), 6);
+
         Ok(())
     }
 
@@ -1381,6 +1770,145 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn lex_cmp_and_suffixes() -> anyhow::Result<()> {
+        use std::cmp::Ordering;
+
+        let cell = build_cell(|b| b.store_u64(0xabcdef0123456789));
+        let slice = cell.as_slice()?;
+
+        // Same data with a different alignment
+        let shifted = build_cell(|b| {
+            b.store_small_uint(0b101, 3)?;
+            b.store_u64(0xabcdef0123456789)
+        });
+        let mut shifted = shifted.as_slice()?;
+        shifted.try_advance(3, 0);
+
+        assert!(slice.data_eq(&shifted));
+        assert_eq!(slice.lex_cmp(&shifted), Ordering::Equal);
+        assert!(!slice.data_eq(&shifted.get_prefix(63, 0)));
+
+        for bits in [0, 1, 5, 8, 13, 40, 64] {
+            let prefix = shifted.get_prefix(bits, 0);
+            assert!(prefix.is_data_prefix_of(&slice));
+            assert_eq!(prefix.lex_cmp(&slice), bits.cmp(&64));
+            assert_eq!(slice.lex_cmp(&prefix), 64.cmp(&bits));
+
+            let mut suffix = shifted;
+            suffix.try_advance(64 - bits, 0);
+            assert!(suffix.is_data_suffix_of(&slice));
+
+            let stripped = slice.strip_data_suffix(&suffix).unwrap();
+            assert_eq!(stripped.remaining_bits(), 64 - bits);
+            assert!(stripped.is_data_prefix_of(&slice));
+        }
+
+        let mut other = slice;
+        other.try_advance(8, 0); // 0xcdef...
+        assert!(!other.is_data_prefix_of(&slice));
+        assert!(other.is_data_suffix_of(&slice));
+        assert!(!slice.is_data_suffix_of(&other));
+        assert!(slice.strip_data_suffix(&slice.get_prefix(8, 0)).is_none());
+
+        // 0xab... < 0xcd...
+        assert_eq!(slice.lex_cmp(&other), Ordering::Less);
+        assert_eq!(other.lex_cmp(&slice), Ordering::Greater);
+
+        // 0b101_0101_1110 vs 0b101_0101_1111
+        let bits = build_cell(|b| b.store_uint(0b101_0101_1111, 11));
+        let bits = bits.as_slice()?;
+        assert_eq!(shifted.get_prefix(11, 0).lex_cmp(&bits), Ordering::Less);
+        assert_eq!(bits.lex_cmp(&shifted), Ordering::Greater);
+
+        Ok(())
+    }
+
+    #[test]
+    fn owned_cell_slice() -> anyhow::Result<()> {
+        let cell = build_cell(|b| {
+            b.store_u32(0xdeafbeaf)?;
+            b.store_reference(Cell::empty_cell())
+        });
+
+        let mut owned = OwnedCellSlice::new(cell.clone())?;
+        let mut slice = owned.as_slice();
+        assert_eq!(slice.load_u16(), Ok(0xdeaf));
+        let range = slice.range();
+        owned.set_range(range)?;
+
+        let (cell, range) = owned.clone().into_parts();
+        assert_eq!(range.remaining_bits(), 16);
+        assert_eq!(OwnedCellSlice::from_parts(cell, range)?.range(), range);
+
+        let copied = CellBuilder::build_from(&owned)?;
+        let copied = copied.parse::<OwnedCellSlice>()?;
+        assert_eq!(copied.as_slice().load_u16(), Ok(0xbeaf));
+        assert_eq!(copied.range().remaining_refs(), 1);
+
+        // Ranges out of cell bounds are rejected
+        assert_eq!(
+            OwnedCellSlice::from_parts(Cell::empty_cell(), range).unwrap_err(),
+            Error::CellUnderflow
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn load_store_int() -> anyhow::Result<()> {
+        let values = [
+            (0i64, 0u16),
+            (0, 1),
+            (-1, 1),
+            (-4, 3),
+            (3, 3),
+            (-1000, 11),
+            (1023, 11),
+            (i64::MIN, 64),
+            (i64::MAX, 64),
+            (-5, 100),
+            (5, 100),
+        ];
+
+        for (value, bits) in values {
+            let cell = build_cell(|b| b.store_int(value, bits));
+            assert_eq!(cell.bit_len(), bits);
+
+            let mut slice = cell.as_slice()?;
+            if bits <= 64 {
+                assert_eq!(slice.load_int(bits), Ok(value));
+            } else {
+                let sign = slice.load_uint(bits - 64)?;
+                assert_eq!(
+                    sign,
+                    if value < 0 {
+                        u64::MAX >> (128 - bits)
+                    } else {
+                        0
+                    }
+                );
+                assert_eq!(slice.load_int(64), Ok(value));
+            }
+            assert!(slice.is_data_empty());
+        }
+
+        let mut builder = CellBuilder::new();
+        assert_eq!(builder.store_int(1, 0), Err(Error::IntOverflow));
+        assert_eq!(builder.store_int(-1, 0), Err(Error::IntOverflow));
+        assert_eq!(builder.store_int(1, 1), Err(Error::IntOverflow));
+        assert_eq!(builder.store_int(-5, 3), Err(Error::IntOverflow));
+        assert_eq!(builder.store_int(4, 3), Err(Error::IntOverflow));
+        assert_eq!(builder.bit_len(), 0);
+
+        let cell = build_cell(|b| b.store_i128(-123456789012345678901234567890));
+        let mut slice = cell.as_slice()?;
+        assert_eq!(slice.get_int(0, 3), Ok(-1));
+        assert_eq!(slice.load_i128(), Ok(-123456789012345678901234567890));
+
+        Ok(())
+    }
+
     #[test]
     fn test_uniform() -> anyhow::Result<()> {
         let cell = build_cell(|b| b.store_zeros(10));
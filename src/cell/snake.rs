@@ -0,0 +1,180 @@
+use crate::cell::{CellBuilder, CellSlice, Finalizer, Load, Store};
+use crate::error::Error;
+
+/// Default limit for the length of the snake data in bytes.
+pub const DEFAULT_SNAKE_MAX_LEN: usize = 0x10000;
+
+/// Bytes stored in the "snake" layout.
+///
+/// Consumes all remaining data bits of the slice and the continuation reference.
+/// Loading fails if there are more than `MAX_LEN` bytes.
+///
+/// See [`CellBuilder::store_snake_bytes_ext`] for the layout description.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SnakeBytes<const MAX_LEN: usize = DEFAULT_SNAKE_MAX_LEN>(pub Vec<u8>);
+
+impl<const MAX_LEN: usize> Store for SnakeBytes<MAX_LEN> {
+    #[inline]
+    fn store_into(
+        &self,
+        builder: &mut CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        builder.store_snake_bytes_ext(&self.0, finalizer)
+    }
+}
+
+impl<'a, const MAX_LEN: usize> Load<'a> for SnakeBytes<MAX_LEN> {
+    #[inline]
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match slice.load_snake_bytes(MAX_LEN) {
+            Ok(bytes) => Ok(Self(bytes)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<const MAX_LEN: usize> From<Vec<u8>> for SnakeBytes<MAX_LEN> {
+    #[inline]
+    fn from(value: Vec<u8>) -> Self {
+        Self(value)
+    }
+}
+
+/// UTF-8 string stored in the "snake" layout.
+///
+/// Consumes all remaining data bits of the slice and the continuation reference.
+/// Loading fails if there are more than `MAX_LEN` bytes.
+///
+/// See [`CellBuilder::store_snake_bytes_ext`] for the layout description.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct SnakeString<const MAX_LEN: usize = DEFAULT_SNAKE_MAX_LEN>(pub String);
+
+impl<const MAX_LEN: usize> Store for SnakeString<MAX_LEN> {
+    #[inline]
+    fn store_into(
+        &self,
+        builder: &mut CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        builder.store_snake_str_ext(&self.0, finalizer)
+    }
+}
+
+impl<'a, const MAX_LEN: usize> Load<'a> for SnakeString<MAX_LEN> {
+    #[inline]
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match slice.load_snake_string(MAX_LEN) {
+            Ok(string) => Ok(Self(string)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<const MAX_LEN: usize> From<String> for SnakeString<MAX_LEN> {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl<const MAX_LEN: usize> From<&str> for SnakeString<MAX_LEN> {
+    #[inline]
+    fn from(value: &str) -> Self {
+        Self(value.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{Cell, CellFamily};
+
+    #[test]
+    fn snake_bytes() -> anyhow::Result<()> {
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<u8>>();
+
+        for len in [0, 1, 127, 128, 200, 254, 255, 1000] {
+            // Store after some prefix to check unaligned heads
+            let mut builder = CellBuilder::new();
+            builder.store_u32(0xdeadbeaf)?;
+            builder.store_bit_one()?;
+            builder.store_snake_bytes(&data[..len])?;
+            let cell = builder.build()?;
+
+            let mut slice = cell.as_slice()?;
+            slice.try_advance(33, 0);
+            assert_eq!(slice.load_snake_bytes(len)?, &data[..len]);
+            assert!(slice.is_data_empty() && slice.is_refs_empty());
+
+            if len > 0 {
+                let mut slice = cell.as_slice()?;
+                slice.try_advance(33, 0);
+                assert_eq!(slice.load_snake_bytes(len - 1), Err(Error::InvalidData));
+            }
+        }
+
+        // Unaligned data
+        let cell = CellBuilder::build_from((0xdeu8, true))?;
+        assert_eq!(
+            cell.as_slice()?.load_snake_bytes(DEFAULT_SNAKE_MAX_LEN),
+            Err(Error::InvalidData)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn snake_bytes_own_refs() -> anyhow::Result<()> {
+        let data = vec![0xaa; 300];
+
+        // Preceding reference
+        let mut builder = CellBuilder::new();
+        builder.store_reference(Cell::empty_cell())?;
+        assert_eq!(builder.store_snake_bytes(&data), Err(Error::InvalidData));
+        assert_eq!(builder.store_snake_bytes(&[]), Err(Error::InvalidData));
+
+        // Reference after the snake data
+        let mut builder = CellBuilder::new();
+        builder.store_snake_bytes(&data)?;
+        builder.store_reference(Cell::empty_cell())?;
+        let cell = builder.build()?;
+        assert_eq!(
+            cell.as_slice()?.load_snake_bytes(DEFAULT_SNAKE_MAX_LEN),
+            Err(Error::InvalidData)
+        );
+
+        // Continuation is the first reference
+        let cell = CellBuilder::build_from(SnakeBytes::<1000>(data.clone()))?;
+        let mut slice = cell.as_slice()?;
+        assert_eq!(slice.remaining_refs(), 1);
+        let child = slice.load_reference()?;
+        assert_eq!(child.bit_len(), 127 * 8);
+        assert_eq!(child.reference_count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn snake_wrappers() -> anyhow::Result<()> {
+        let comment = SnakeString::<1000>::from("hello ".repeat(100));
+        let cell = CellBuilder::build_from(&comment)?;
+        assert_eq!(cell.parse::<SnakeString<1000>>()?, comment);
+        assert_eq!(
+            cell.parse::<SnakeString<100>>().unwrap_err(),
+            Error::InvalidData
+        );
+
+        let bytes = SnakeBytes::<DEFAULT_SNAKE_MAX_LEN>::from(vec![0xff; 300]);
+        let cell = CellBuilder::build_from((0u32, &bytes))?;
+        assert_eq!(cell.parse::<(u32, SnakeBytes)>()?, (0, bytes));
+
+        // Invalid UTF-8
+        let cell = CellBuilder::build_from(SnakeBytes::<10>(vec![0xff, 0xfe]))?;
+        assert_eq!(cell.parse::<SnakeString>().unwrap_err(), Error::InvalidData);
+
+        Ok(())
+    }
+}
@@ -40,6 +40,11 @@ impl UsageTree {
         self.state.contains(repr_hash)
     }
 
+    /// Returns the number of distinct cells included into this usage tree.
+    pub(crate) fn visited_count(&self) -> usize {
+        self.state.visited_count()
+    }
+
     /// Extends the usage tree with subtree tracker.
     pub fn with_subtrees(self) -> UsageTreeWithSubtrees {
         UsageTreeWithSubtrees {
@@ -174,6 +179,7 @@ mod rc {
     pub struct UsageTreeState {
         mode: UsageTreeMode,
         visited: VisitedCells,
+        included: std::cell::Cell<usize>,
     }
 
     impl UsageTreeState {
@@ -181,6 +187,7 @@ mod rc {
             Rc::new(Self {
                 mode,
                 visited: Default::default(),
+                included: Default::default(),
             })
         }
 
@@ -199,8 +206,10 @@ mod rc {
 
             let mut visited = self.visited.borrow_mut();
 
-            if let Some(visited) = visited.get_mut(repr_hash) {
+            let newly_included = if let Some(visited) = visited.get_mut(repr_hash) {
+                let newly_included = include && !visited.include;
                 visited.include |= include;
+                newly_included
             } else {
                 visited.insert(
                     *repr_hash,
@@ -209,9 +218,19 @@ mod rc {
                         _cell: cell.clone(),
                     },
                 );
+                include
+            };
+
+            if newly_included {
+                self.included.set(self.included.get() + 1);
             }
         }
 
+        #[inline]
+        pub fn visited_count(&self) -> usize {
+            self.included.get()
+        }
+
         #[inline]
         pub fn contains(&self, repr_hash: &HashBytes) -> bool {
             if let Some(cell) = self.visited.borrow().get(repr_hash) {
@@ -256,6 +275,7 @@ mod rc {
 
 #[cfg(feature = "sync")]
 mod sync {
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex};
 
     use super::{UsageTreeMode, VisitedCell};
@@ -268,6 +288,7 @@ mod sync {
     pub struct UsageTreeState {
         mode: UsageTreeMode,
         visited: VisitedCells,
+        included: AtomicUsize,
     }
 
     impl UsageTreeState {
@@ -275,6 +296,7 @@ mod sync {
             Arc::new(Self {
                 mode,
                 visited: Default::default(),
+                included: Default::default(),
             })
         }
 
@@ -293,8 +315,10 @@ mod sync {
 
             let mut visited = self.visited.lock().expect("lock failed");
 
-            if let Some(visited) = visited.get_mut(repr_hash) {
+            let newly_included = if let Some(visited) = visited.get_mut(repr_hash) {
+                let newly_included = include && !visited.include;
                 visited.include |= include;
+                newly_included
             } else {
                 visited.insert(
                     *repr_hash,
@@ -303,9 +327,19 @@ mod sync {
                         _cell: cell.clone(),
                     },
                 );
+                include
+            };
+
+            if newly_included {
+                self.included.fetch_add(1, Ordering::Relaxed);
             }
         }
 
+        #[inline]
+        pub fn visited_count(&self) -> usize {
+            self.included.load(Ordering::Relaxed)
+        }
+
         #[inline]
         pub fn contains(&self, repr_hash: &HashBytes) -> bool {
             let visited = self.visited.lock().expect("lock failed");
@@ -5,9 +5,13 @@ use crate::cell::*;
 use crate::error::*;
 use crate::util::*;
 
+use super::diff::*;
 use super::raw::*;
 use super::typed::*;
-use super::{read_label, DictKey};
+use super::{
+    aug_dict_insert, aug_dict_merge, aug_dict_remove, aug_dict_validate, load_exact,
+    read_aug_extra, read_label, serialize_entry, DictKey, SetMode,
+};
 
 pub(crate) trait AugDictSkipValue<'a> {
     fn skip_value(slice: &mut CellSlice<'a>) -> bool;
@@ -24,6 +28,29 @@ impl<'a> AugDictSkipValue<'a> for crate::num::Tokens {
     }
 }
 
+/// A trait for values used as dictionary augmentation.
+pub trait AugDictExtra: Default {
+    /// Computes the augmented value of a fork from the augmented
+    /// values of its children, storing the result into the builder.
+    fn comp_add(
+        left: &mut CellSlice,
+        right: &mut CellSlice,
+        b: &mut CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error>;
+}
+
+/// The next step of the [`AugDict::find_by_extra`] search.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AugDictSearchStep {
+    /// Continue the search in the left subtree.
+    Left,
+    /// Continue the search in the right subtree.
+    Right,
+    /// Stop the search without a result.
+    Stop,
+}
+
 /// Typed augmented dictionary with fixed length keys.
 ///
 /// # TLB scheme
@@ -134,6 +161,23 @@ impl<K: DictKey, A, V> AugDict<K, A, V> {
             _value: PhantomData,
         })
     }
+
+    /// Checks the structure of the dictionary cells and that each
+    /// augmented value and value is fully loaded without any remaining data.
+    ///
+    /// Returns the key prefix of the first invalid node on failure.
+    pub fn validate(&self) -> Result<(), DictValidationError>
+    where
+        for<'a> A: Load<'a>,
+        for<'a> V: Load<'a>,
+    {
+        aug_dict_validate(
+            self.dict.root(),
+            K::BITS,
+            &mut load_exact::<A>,
+            &mut load_exact::<(A, V)>,
+        )
+    }
 }
 
 fn load_from_root<'a, A, V>(
@@ -220,58 +264,167 @@ where
     }
 }
 
-// TODO: add support for `extra` in edges
-
-// impl<K, A, V> AugDict<K, A, V>
-// where
-//     K: Store + DictKey,
-//     A: Store,
-//     V: Store,
-// {
-//     /// Sets the augmented value associated with the key in the dictionary.
-//     ///
-//     /// Use [`set_ext`] if you need to use a custom finalizer.
-//     ///
-//     /// [`set_ext`]: AugDict::set_ext
-//     pub fn set<Q, E, T>(&mut self, key: Q, aug: E, value: T) -> Result<(), Error>
-//     where
-//         Q: Borrow<K>,
-//         E: Borrow<A>,
-//         T: Borrow<V>,
-//     {
-//         self.set_ext(key, aug, value, &mut Cell::default_finalizer())
-//     }
-
-//     /// Sets the augmented value associated with the key in the dictionary
-//     /// only if the key was already present in it.
-//     ///
-//     /// Use [`replace_ext`] if you need to use a custom finalizer.
-//     ///
-//     /// [`replace_ext`]: AugDict::replace_ext
-//     pub fn replace<Q, E, T>(&mut self, key: Q, aug: E, value: T) -> Result<(), Error>
-//     where
-//         Q: Borrow<K>,
-//         E: Borrow<A>,
-//         T: Borrow<V>,
-//     {
-//         self.replace_ext(key, aug, value, &mut Cell::default_finalizer())
-//     }
-
-//     /// Sets the value associated with key in dictionary,
-//     /// but only if it is not already present.
-//     ///
-//     /// Use [`add_ext`] if you need to use a custom finalizer.
-//     ///
-//     /// [`add_ext`]: AugDict::add_ext
-//     pub fn add<Q, E, T>(&mut self, key: Q, aug: E, value: T) -> Result<(), Error>
-//     where
-//         Q: Borrow<K>,
-//         E: Borrow<A>,
-//         T: Borrow<V>,
-//     {
-//         self.add_ext(key, aug, value, &mut Cell::default_finalizer())
-//     }
-// }
+impl<K, A, V> AugDict<K, A, V>
+where
+    K: DictKey,
+{
+    /// Searches for an entry using the augmented values of forks.
+    ///
+    /// Starting from the root, calls `f` with the augmented values of the
+    /// left and right subtrees of each fork to choose the next branch.
+    /// Returns the entry of the reached leaf, or `None` if the dictionary
+    /// is empty or the search was stopped.
+    ///
+    /// Visits only a single path, so the search costs `O(depth)`.
+    pub fn find_by_extra<'a, F>(&'a self, mut f: F) -> Result<Option<(K, A, V)>, Error>
+    where
+        F: FnMut(&A, &A) -> AugDictSearchStep,
+        A: Load<'a>,
+        V: Load<'a>,
+    {
+        let mut data = match self.dict.root() {
+            Some(root) => root.as_ref(),
+            None => return Ok(None),
+        };
+
+        let mut key = CellBuilder::new();
+        let mut remaining_bit_len = K::BITS;
+        loop {
+            let mut slice = ok!(data.as_slice());
+
+            // Read the next key part
+            let prefix = ok!(read_label(&mut slice, remaining_bit_len));
+            ok!(key.store_slice_data(prefix));
+
+            remaining_bit_len = match remaining_bit_len.checked_sub(prefix.remaining_bits()) {
+                // Return the leaf entry if there are no remaining bits to read
+                Some(0) => {
+                    let Some(key) = K::from_raw_data(key.raw_data()) else {
+                        return Err(Error::CellUnderflow);
+                    };
+                    let extra = ok!(A::load_from(&mut slice));
+                    let value = ok!(V::load_from(&mut slice));
+                    return Ok(Some((key, extra, value)));
+                }
+                Some(remaining) => remaining - 1,
+                // Well-formed `Dict` should have the required number of bits
+                // for each value
+                None => return Err(Error::CellUnderflow),
+            };
+
+            let (left, right) = match (data.reference(0), data.reference(1)) {
+                (Some(left), Some(right)) => (left, right),
+                _ => return Err(Error::CellUnderflow),
+            };
+
+            // Select the next branch using extras of both subtrees
+            let left_extra = ok!(A::load_from(&mut ok!(read_aug_extra(
+                left,
+                remaining_bit_len
+            ))));
+            let right_extra = ok!(A::load_from(&mut ok!(read_aug_extra(
+                right,
+                remaining_bit_len
+            ))));
+
+            data = match f(&left_extra, &right_extra) {
+                AugDictSearchStep::Left => {
+                    ok!(key.store_bit_zero());
+                    left
+                }
+                AugDictSearchStep::Right => {
+                    ok!(key.store_bit_one());
+                    right
+                }
+                AugDictSearchStep::Stop => return Ok(None),
+            };
+        }
+    }
+}
+
+impl<K, A, V> AugDict<K, A, V>
+where
+    K: Store + DictKey,
+    for<'a> A: AugDictExtra + Store + Load<'a>,
+    V: Store,
+{
+    /// Sets the augmented value associated with the key in the dictionary.
+    ///
+    /// Use [`set_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`set_ext`]: AugDict::set_ext
+    pub fn set<Q, E, T>(&mut self, key: Q, aug: E, value: T) -> Result<(), Error>
+    where
+        Q: Borrow<K>,
+        E: Borrow<A>,
+        T: Borrow<V>,
+    {
+        self.set_ext(key, aug, value, &mut Cell::default_finalizer())
+    }
+
+    /// Sets the augmented value associated with the key in the dictionary
+    /// only if the key was already present in it.
+    ///
+    /// Use [`replace_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`replace_ext`]: AugDict::replace_ext
+    pub fn replace<Q, E, T>(&mut self, key: Q, aug: E, value: T) -> Result<(), Error>
+    where
+        Q: Borrow<K>,
+        E: Borrow<A>,
+        T: Borrow<V>,
+    {
+        self.replace_ext(key, aug, value, &mut Cell::default_finalizer())
+    }
+
+    /// Sets the value associated with key in dictionary,
+    /// but only if it is not already present.
+    ///
+    /// Use [`add_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`add_ext`]: AugDict::add_ext
+    pub fn add<Q, E, T>(&mut self, key: Q, aug: E, value: T) -> Result<(), Error>
+    where
+        Q: Borrow<K>,
+        E: Borrow<A>,
+        T: Borrow<V>,
+    {
+        self.add_ext(key, aug, value, &mut Cell::default_finalizer())
+    }
+}
+
+impl<K, A, V> AugDict<K, A, V>
+where
+    K: Store + DictKey,
+    for<'a> A: AugDictExtra + Load<'a>,
+{
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value with its augmentation.
+    ///
+    /// Use [`remove_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`remove_ext`]: AugDict::remove_ext
+    pub fn remove<Q>(&mut self, key: Q) -> Result<Option<(A, V)>, Error>
+    where
+        Q: Borrow<K>,
+        for<'a> V: Load<'a> + 'static,
+    {
+        self.remove_ext(key, &mut Cell::default_finalizer())
+    }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value (with its augmentation) as cell slice parts.
+    ///
+    /// Use [`remove_raw_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`remove_raw_ext`]: AugDict::remove_raw_ext
+    pub fn remove_raw<Q>(&mut self, key: Q) -> Result<Option<CellSliceParts>, Error>
+    where
+        Q: Borrow<K>,
+    {
+        self.remove_raw_ext(key, &mut Cell::default_finalizer())
+    }
+}
 
 impl<K, A, V> AugDict<K, A, V>
 where
@@ -297,6 +450,24 @@ where
         AugIter::new(self.dict.root())
     }
 
+    /// Gets an iterator over the differences between this dictionary
+    /// and the `other` one, sorted by key. This dictionary is treated
+    /// as the old version and the `other` one as the new version.
+    /// The iterator element type is `Result<DictDiffItem<K, (A, V)>>`.
+    ///
+    /// Identical subtrees are skipped by comparing their hashes,
+    /// so the cost is proportional to the size of the change.
+    ///
+    /// If any dictionary is invalid, finishes after the first invalid element,
+    /// returning an error.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> DictDiff<'a, K, (A, V)>
+    where
+        A: Load<'a>,
+        V: Load<'a>,
+    {
+        DictDiff::new(self.dict.root(), other.dict.root())
+    }
+
     /// Gets an iterator over the keys of the dictionary, in sorted order.
     /// The iterator element type is `Result<K>`.
     ///
@@ -400,106 +571,238 @@ where
     }
 }
 
-// impl<K, A, V> AugDict<K, A, V>
-// where
-//     K: Store + DictKey,
-//     A: Store,
-//     V: Store,
-// {
-//     /// Sets the value associated with the key in the dictionary.
-//     pub fn set_ext<Q, E, T>(
-//         &mut self,
-//         key: Q,
-//         aug: E,
-//         value: T,
-//         finalizer: &mut dyn Finalizer,
-//     ) -> Result<(), Error>
-//     where
-//         Q: Borrow<K>,
-//         E: Borrow<A>,
-//         T: Borrow<V>,
-//     {
-//         self.insert_impl(
-//             key.borrow(),
-//             aug.borrow(),
-//             value.borrow(),
-//             SetMode::Set,
-//             finalizer,
-//         )
-//     }
-
-//     /// Sets the value associated with the key in the dictionary
-//     /// only if the key was already present in it.
-//     pub fn replace_ext<Q, E, T>(
-//         &mut self,
-//         key: Q,
-//         aug: E,
-//         value: T,
-//         finalizer: &mut dyn Finalizer,
-//     ) -> Result<(), Error>
-//     where
-//         Q: Borrow<K>,
-//         E: Borrow<A>,
-//         T: Borrow<V>,
-//     {
-//         self.insert_impl(
-//             key.borrow(),
-//             aug.borrow(),
-//             value.borrow(),
-//             SetMode::Replace,
-//             finalizer,
-//         )
-//     }
-
-//     /// Sets the value associated with key in dictionary,
-//     /// but only if it is not already present.
-//     pub fn add_ext<Q, E, T>(
-//         &mut self,
-//         key: Q,
-//         aug: E,
-//         value: T,
-//         finalizer: &mut dyn Finalizer,
-//     ) -> Result<(), Error>
-//     where
-//         Q: Borrow<K>,
-//         E: Borrow<A>,
-//         T: Borrow<V>,
-//     {
-//         self.insert_impl(
-//             key.borrow(),
-//             aug.borrow(),
-//             value.borrow(),
-//             SetMode::Add,
-//             finalizer,
-//         )
-//     }
-
-//     fn insert_impl(
-//         &mut self,
-//         key: &K,
-//         aug: &A,
-//         value: &V,
-//         mode: SetMode,
-//         finalizer: &mut dyn Finalizer,
-//     ) -> Result<(), Error>
-//     where
-//         K: Store + DictKey,
-//         A: Store,
-//         V: Store,
-//     {
-//         let key = ok!(serialize_entry(key, finalizer));
-//         let value = ok!(serialize_aug_entry(aug, value, finalizer));
-//         self.dict.root = ok!(dict_insert(
-//             &self.dict.root,
-//             &mut key.as_ref().as_slice(),
-//             K::BITS,
-//             &value.as_ref().as_slice(),
-//             mode,
-//             finalizer
-//         ));
-//         Ok(())
-//     }
-// }
+impl<K, A, V> AugDict<K, A, V>
+where
+    K: Store + DictKey,
+    for<'a> A: AugDictExtra + Store + Load<'a>,
+    V: Store,
+{
+    /// Sets the value associated with the key in the dictionary.
+    pub fn set_ext<Q, E, T>(
+        &mut self,
+        key: Q,
+        aug: E,
+        value: T,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error>
+    where
+        Q: Borrow<K>,
+        E: Borrow<A>,
+        T: Borrow<V>,
+    {
+        self.insert_impl(
+            key.borrow(),
+            aug.borrow(),
+            value.borrow(),
+            SetMode::Set,
+            finalizer,
+        )
+    }
+
+    /// Sets the value associated with the key in the dictionary
+    /// only if the key was already present in it.
+    pub fn replace_ext<Q, E, T>(
+        &mut self,
+        key: Q,
+        aug: E,
+        value: T,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error>
+    where
+        Q: Borrow<K>,
+        E: Borrow<A>,
+        T: Borrow<V>,
+    {
+        self.insert_impl(
+            key.borrow(),
+            aug.borrow(),
+            value.borrow(),
+            SetMode::Replace,
+            finalizer,
+        )
+    }
+
+    /// Sets the value associated with key in dictionary,
+    /// but only if it is not already present.
+    pub fn add_ext<Q, E, T>(
+        &mut self,
+        key: Q,
+        aug: E,
+        value: T,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error>
+    where
+        Q: Borrow<K>,
+        E: Borrow<A>,
+        T: Borrow<V>,
+    {
+        self.insert_impl(
+            key.borrow(),
+            aug.borrow(),
+            value.borrow(),
+            SetMode::Add,
+            finalizer,
+        )
+    }
+
+    fn insert_impl(
+        &mut self,
+        key: &K,
+        aug: &A,
+        value: &V,
+        mode: SetMode,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        let key = ok!(serialize_entry(key, finalizer));
+        let value = ok!(serialize_aug_entry(aug, value, finalizer));
+        self.dict.root = ok!(aug_dict_insert(
+            &self.dict.root,
+            &mut ok!(key.as_ref().as_slice()),
+            K::BITS,
+            &ok!(value.as_ref().as_slice()),
+            mode,
+            A::comp_add,
+            finalizer,
+        ));
+        self.update_root_extra()
+    }
+}
+
+impl<K, A, V> AugDict<K, A, V>
+where
+    K: Store + DictKey,
+    for<'a> A: AugDictExtra + Load<'a>,
+{
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value with its augmentation.
+    pub fn remove_ext<Q>(
+        &mut self,
+        key: Q,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Option<(A, V)>, Error>
+    where
+        Q: Borrow<K>,
+        for<'a> V: Load<'a> + 'static,
+    {
+        match ok!(self.remove_raw_ext(key, finalizer)) {
+            Some((cell, range)) => {
+                let mut slice = ok!(range.apply(cell.as_ref()));
+                let aug = ok!(A::load_from(&mut slice));
+                match V::load_from(&mut slice) {
+                    Ok(value) => Ok(Some((aug, value))),
+                    Err(e) => Err(e),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value (with its augmentation) as cell slice parts.
+    pub fn remove_raw_ext<Q>(
+        &mut self,
+        key: Q,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Option<CellSliceParts>, Error>
+    where
+        Q: Borrow<K>,
+    {
+        let key = ok!(serialize_entry(key.borrow(), finalizer));
+        let (root, removed) = ok!(aug_dict_remove(
+            &self.dict.root,
+            &mut ok!(key.as_ref().as_slice()),
+            K::BITS,
+            A::comp_add,
+            finalizer,
+        ));
+        self.dict.root = root;
+        ok!(self.update_root_extra());
+        Ok(removed)
+    }
+}
+
+impl<K, A, V> AugDict<K, A, V>
+where
+    K: DictKey,
+    for<'a> A: AugDictExtra + Load<'a>,
+{
+    /// Splits the dictionary by the next bit after the key prefix.
+    /// Returns dictionaries with keys starting with `prefix + 0`
+    /// and `prefix + 1` respectively.
+    ///
+    /// All keys must start with the `prefix`, otherwise an error is returned.
+    pub fn split_by_prefix_ext(
+        &self,
+        prefix: &CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(Self, Self), Error> {
+        let (left, right) = ok!(self.dict.split_by_prefix_ext(prefix, finalizer));
+
+        let mut left = Self {
+            dict: left,
+            extra: A::default(),
+            _key: PhantomData,
+            _value: PhantomData,
+        };
+        ok!(left.update_root_extra());
+
+        let mut right = Self {
+            dict: right,
+            extra: A::default(),
+            _key: PhantomData,
+            _value: PhantomData,
+        };
+        ok!(right.update_root_extra());
+
+        Ok((left, right))
+    }
+
+    /// Merges the other dictionary into this one, reusing disjoint subtrees.
+    /// Values from the `other` dictionary replace values with the same keys.
+    pub fn merge_ext(&mut self, other: &Self, finalizer: &mut dyn Finalizer) -> Result<(), Error> {
+        self.dict.root = ok!(aug_dict_merge(
+            &self.dict.root,
+            &other.dict.root,
+            K::BITS,
+            A::comp_add,
+            finalizer
+        ));
+        self.update_root_extra()
+    }
+
+    /// Splits the dictionary by the next bit after the key prefix.
+    /// Returns dictionaries with keys starting with `prefix + 0`
+    /// and `prefix + 1` respectively.
+    ///
+    /// Use [`split_by_prefix_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`split_by_prefix_ext`]: AugDict::split_by_prefix_ext
+    pub fn split_by_prefix(&self, prefix: &CellSlice<'_>) -> Result<(Self, Self), Error> {
+        self.split_by_prefix_ext(prefix, &mut Cell::default_finalizer())
+    }
+
+    /// Merges the other dictionary into this one, reusing disjoint subtrees.
+    /// Values from the `other` dictionary replace values with the same keys.
+    ///
+    /// Use [`merge_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`merge_ext`]: AugDict::merge_ext
+    pub fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        self.merge_ext(other, &mut Cell::default_finalizer())
+    }
+
+    /// Loads the root augmented value from the root cell.
+    fn update_root_extra(&mut self) -> Result<(), Error> {
+        self.extra = match &self.dict.root {
+            Some(root) => {
+                let mut slice = ok!(read_aug_extra(root.as_ref(), K::BITS));
+                ok!(A::load_from(&mut slice))
+            }
+            None => A::default(),
+        };
+        Ok(())
+    }
+}
 
 /// An iterator over the entries of an [`AugDict`].
 ///
@@ -545,65 +848,262 @@ where
     }
 }
 
-// fn serialize_aug_entry<A: Store, V: Store>(
-//     aug: &A,
-//     entry: &V,
-//     finalizer: &mut dyn Finalizer,
-// ) -> Result<CellContainer, Error> {
-//     let mut builder = CellBuilder::new();
-//     if aug.store_into(&mut builder, finalizer) && entry.store_into(&mut builder, finalizer) {
-//         if let Some(key) = builder.build_ext(finalizer) {
-//             return Ok(key);
-//         }
-//     }
-//     Err(Error::CellOverflow)
-// }
+impl<'a, K, A, V> DoubleEndedIterator for AugIter<'a, K, A, V>
+where
+    K: DictKey,
+    (A, V): Load<'a>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self.inner.next_back()? {
+            Ok((key, (aug, value))) => Some(Ok((key, aug, value))),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+fn serialize_aug_entry<A: Store, V: Store>(
+    aug: &A,
+    entry: &V,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Cell, Error> {
+    let mut builder = CellBuilder::new();
+    ok!(aug.store_into(&mut builder, finalizer));
+    ok!(entry.store_into(&mut builder, finalizer));
+    builder.build_ext(finalizer)
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::prelude::Boc;
 
-    // #[test]
-    // fn dict_set() {
-    //     let mut dict = AugDict::<RcCellFamily, u32, bool, u16>::new();
-    //     dict.set(123, false, 0xffff).unwrap();
-    //     assert_eq!(dict.get(123).unwrap(), Some((false, 0xffff)));
-
-    //     dict.set(123, true, 0xcafe).unwrap();
-    //     assert_eq!(dict.get(123).unwrap(), Some((true, 0xcafe)));
-    // }
-
-    // #[test]
-    // fn dict_set_complex() {
-    //     let mut dict = AugDict::<RcCellFamily, u32, bool, u32>::new();
-    //     for i in 0..520 {
-    //         dict.set(i, true, 123).unwrap();
-    //     }
-    // }
-
-    // #[test]
-    // fn dict_replace() {
-    //     let mut dict = AugDict::<RcCellFamily, u32, bool, u16>::new();
-    //     dict.replace(123, false, 0xff).unwrap();
-    //     assert!(!dict.contains_key(123).unwrap());
-
-    //     dict.set(123, false, 0xff).unwrap();
-    //     assert_eq!(dict.get(123).unwrap(), Some((false, 0xff)));
-    //     dict.replace(123, true, 0xaa).unwrap();
-    //     assert_eq!(dict.get(123).unwrap(), Some((true, 0xaa)));
-    // }
-
-    // #[test]
-    // fn dict_add() {
-    //     let mut dict = AugDict::<RcCellFamily, u32, bool, u16>::new();
-
-    //     dict.add(123, false, 0x12).unwrap();
-    //     assert_eq!(dict.get(123).unwrap(), Some((false, 0x12)));
-
-    //     dict.add(123, true, 0x11).unwrap();
-    //     assert_eq!(dict.get(123).unwrap(), Some((false, 0x12)));
-    // }
+    #[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Store, Load)]
+    struct OrCmp(bool);
+
+    impl AugDictExtra for OrCmp {
+        fn comp_add(
+            left: &mut CellSlice,
+            right: &mut CellSlice,
+            b: &mut CellBuilder,
+            _: &mut dyn Finalizer,
+        ) -> Result<(), Error> {
+            let left = ok!(left.load_bit());
+            let right = ok!(right.load_bit());
+            b.store_bit(left | right)
+        }
+    }
+
+    #[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Store, Load)]
+    struct SumExtra(u32);
+
+    impl AugDictExtra for SumExtra {
+        fn comp_add(
+            left: &mut CellSlice,
+            right: &mut CellSlice,
+            b: &mut CellBuilder,
+            _: &mut dyn Finalizer,
+        ) -> Result<(), Error> {
+            let left = ok!(left.load_u32());
+            let right = ok!(right.load_u32());
+            match left.checked_add(right) {
+                Some(sum) => b.store_u32(sum),
+                None => Err(Error::IntOverflow),
+            }
+        }
+    }
+
+    #[test]
+    fn dict_set() {
+        let mut dict = AugDict::<u32, OrCmp, u16>::new();
+        assert_eq!(*dict.root_extra(), OrCmp(false));
+
+        dict.set(123, OrCmp(false), 0xffff).unwrap();
+        assert_eq!(dict.get(123).unwrap(), Some((OrCmp(false), 0xffff)));
+        assert_eq!(*dict.root_extra(), OrCmp(false));
+
+        dict.set(123, OrCmp(true), 0xcafe).unwrap();
+        assert_eq!(dict.get(123).unwrap(), Some((OrCmp(true), 0xcafe)));
+        assert_eq!(*dict.root_extra(), OrCmp(true));
+    }
+
+    #[test]
+    fn dict_set_complex() {
+        let mut dict = AugDict::<u32, OrCmp, u32>::new();
+        for i in 0..520 {
+            dict.set(i, OrCmp(i == 333), 123).unwrap();
+        }
+        assert_eq!(*dict.root_extra(), OrCmp(true));
+        assert_eq!(dict.iter().count(), 520);
+
+        dict.set(333, OrCmp(false), 321).unwrap();
+        assert_eq!(*dict.root_extra(), OrCmp(false));
+    }
+
+    #[test]
+    fn dict_replace() {
+        let mut dict = AugDict::<u32, OrCmp, u16>::new();
+        dict.replace(123, OrCmp(false), 0xff).unwrap();
+        assert!(!dict.contains_key(123).unwrap());
+
+        dict.set(123, OrCmp(false), 0xff).unwrap();
+        assert_eq!(dict.get(123).unwrap(), Some((OrCmp(false), 0xff)));
+        dict.replace(123, OrCmp(true), 0xaa).unwrap();
+        assert_eq!(dict.get(123).unwrap(), Some((OrCmp(true), 0xaa)));
+        assert_eq!(*dict.root_extra(), OrCmp(true));
+    }
+
+    #[test]
+    fn dict_add() {
+        let mut dict = AugDict::<u32, OrCmp, u16>::new();
+
+        dict.add(123, OrCmp(false), 0x12).unwrap();
+        assert_eq!(dict.get(123).unwrap(), Some((OrCmp(false), 0x12)));
+
+        dict.add(123, OrCmp(true), 0x11).unwrap();
+        assert_eq!(dict.get(123).unwrap(), Some((OrCmp(false), 0x12)));
+        assert_eq!(*dict.root_extra(), OrCmp(false));
+    }
+
+    #[test]
+    fn dict_remove() {
+        let mut dict = AugDict::<u32, SumExtra, u32>::new();
+        for i in 0..10 {
+            dict.set(i, SumExtra(i), i * 10).unwrap();
+        }
+        assert_eq!(*dict.root_extra(), SumExtra(45));
+
+        assert_eq!(dict.remove(100).unwrap(), None);
+        assert_eq!(dict.remove(5).unwrap(), Some((SumExtra(5), 50)));
+        assert_eq!(*dict.root_extra(), SumExtra(40));
+        assert_eq!(dict.remove(5).unwrap(), None);
+
+        for i in [0, 9, 3, 1, 2, 8, 7, 4] {
+            assert_eq!(dict.remove(i).unwrap(), Some((SumExtra(i), i * 10)));
+        }
+        assert_eq!(*dict.root_extra(), SumExtra(6));
+
+        assert_eq!(dict.remove(6).unwrap(), Some((SumExtra(6), 60)));
+        assert!(dict.is_empty());
+        assert_eq!(*dict.root_extra(), SumExtra(0));
+    }
+
+    #[test]
+    fn dict_extra_is_independent_of_history() {
+        let mut forward = AugDict::<u32, SumExtra, u32>::new();
+        for i in 0..100 {
+            forward.set(i * 7, SumExtra(i), i).unwrap();
+        }
+
+        let mut backward = AugDict::<u32, SumExtra, u32>::new();
+        for i in (0..150).rev() {
+            backward.set(i * 7, SumExtra(i), i).unwrap();
+        }
+        for i in 100..150 {
+            backward.remove(i * 7).unwrap();
+        }
+
+        assert_eq!(*forward.root_extra(), SumExtra((0..100).sum()));
+        assert_eq!(forward, backward);
+
+        // Check the extra of each fork
+        fn check_node(cell: &DynCell, key_bit_len: u16) -> u32 {
+            let mut slice = cell.as_slice().unwrap();
+            let label = read_label(&mut slice, key_bit_len).unwrap();
+            let remaining = key_bit_len - label.remaining_bits();
+            if remaining == 0 {
+                return slice.load_u32().unwrap();
+            }
+
+            let left = check_node(cell.reference(0).unwrap(), remaining - 1);
+            let right = check_node(cell.reference(1).unwrap(), remaining - 1);
+            slice.try_advance(0, 2);
+            assert_eq!(slice.load_u32().unwrap(), left + right);
+            left + right
+        }
+        let root = forward.dict().root().as_ref().unwrap();
+        assert_eq!(check_node(root.as_ref(), 32), forward.root_extra().0);
+    }
+
+    #[test]
+    fn dict_split_merge() {
+        let mut dict = AugDict::<u32, SumExtra, u32>::new();
+        for i in 0..100 {
+            dict.set(i * 0x1500000, SumExtra(i), i).unwrap();
+        }
+
+        let prefix = Cell::empty_cell_ref().as_slice().unwrap();
+        let (left, right) = dict.split_by_prefix(&prefix).unwrap();
+
+        let expected_left = (0..100).filter(|i| i * 0x1500000 < 0x80000000).sum();
+        assert_eq!(left.root_extra(), &SumExtra(expected_left));
+        assert_eq!(right.root_extra().0, dict.root_extra().0 - expected_left);
+
+        let mut merged = left.clone();
+        merged.merge(&right).unwrap();
+        assert_eq!(merged, dict);
+
+        // Overlapping entries are replaced by the other dictionary
+        let mut other = AugDict::<u32, SumExtra, u32>::new();
+        for i in 50..150 {
+            other.set(i * 0x1500000, SumExtra(1), i).unwrap();
+            dict.set(i * 0x1500000, SumExtra(1), i).unwrap();
+        }
+        merged.merge(&other).unwrap();
+        assert_eq!(merged.root_extra(), &SumExtra((0..50).sum::<u32>() + 100));
+        assert_eq!(merged, dict);
+    }
+
+    #[test]
+    fn dict_find_by_extra() {
+        let mut dict = AugDict::<u32, SumExtra, u32>::new();
+        assert_eq!(
+            dict.find_by_extra(|_, _| AugDictSearchStep::Left).unwrap(),
+            None
+        );
+
+        for i in 0..100 {
+            dict.set(i, SumExtra(i % 7), i).unwrap();
+        }
+
+        // Find an entry at the cumulative position
+        let total = dict.root_extra().0;
+        for position in [0, 1, 5, 100, total - 1] {
+            let mut offset = position;
+            let (key, extra, value) = dict
+                .find_by_extra(|left, _| {
+                    if offset < left.0 {
+                        AugDictSearchStep::Left
+                    } else {
+                        offset -= left.0;
+                        AugDictSearchStep::Right
+                    }
+                })
+                .unwrap()
+                .unwrap();
+            assert_eq!(key, value);
+
+            let before = (0..key).map(|i| i % 7).sum::<u32>();
+            assert!(before <= position && position < before + extra.0);
+        }
+
+        // Stop the search
+        let entry = dict.find_by_extra(|_, _| AugDictSearchStep::Stop).unwrap();
+        assert_eq!(entry, None);
+
+        // Find the only marked entry
+        let mut dict = AugDict::<u32, OrCmp, u32>::new();
+        for i in 0..100 {
+            dict.set(i * 3, OrCmp(i == 42), i).unwrap();
+        }
+        let entry = dict
+            .find_by_extra(|left, right| match (left.0, right.0) {
+                (true, _) => AugDictSearchStep::Left,
+                (_, true) => AugDictSearchStep::Right,
+                _ => AugDictSearchStep::Stop,
+            })
+            .unwrap();
+        assert_eq!(entry, Some((126, OrCmp(true), 42)));
+    }
 
     #[test]
     fn dict_iter() {
@@ -622,4 +1122,58 @@ mod tests {
             assert_eq!(value, 9 - i as u32);
         }
     }
+
+    #[test]
+    fn dict_validate() -> anyhow::Result<()> {
+        fn cast<A: Default, V>(dict: &AugDict<u32, SumExtra, u32>) -> AugDict<u32, A, V> {
+            AugDict {
+                dict: Dict::from(dict.dict().root().clone()),
+                extra: A::default(),
+                _key: PhantomData,
+                _value: PhantomData,
+            }
+        }
+
+        let mut dict = AugDict::<u32, SumExtra, u32>::new();
+        for i in 0..100u32 {
+            dict.set(i * 1234567, SumExtra(i), i)?;
+        }
+        dict.validate()?;
+
+        // Fork extras which do not match the augmentation type
+        let e = cast::<u64, ()>(&dict).validate().unwrap_err();
+        assert_eq!(e.error, Error::CellUnderflow);
+        assert!(e.key_prefix_bit_len < 32);
+
+        // Leaves with remaining data
+        let e = cast::<SumExtra, u16>(&dict).validate().unwrap_err();
+        assert_eq!(e.error, Error::InvalidData);
+        assert_eq!(e.key_prefix_bit_len, 32);
+
+        Ok(())
+    }
+
+    #[cfg(feature = "models")]
+    #[test]
+    fn dict_validate_extra_with_refs() -> anyhow::Result<()> {
+        use crate::models::{CurrencyCollection, ExtraCurrencyCollection};
+        use crate::num::{Tokens, VarUint248};
+
+        let mut other = Dict::<HashBytes, VarUint248>::new();
+        other.set(HashBytes([0x11; 32]), VarUint248::new(100))?;
+        let other = CellBuilder::build_from(&other)?.parse::<ExtraCurrencyCollection>()?;
+
+        let value = CurrencyCollection {
+            tokens: Tokens::new(1000),
+            other,
+        };
+
+        let mut dict = AugDict::<u32, CurrencyCollection, u32>::new();
+        dict.set(1, value.clone(), 1)?;
+        dict.set(2, value.clone(), 2)?;
+        assert_eq!(dict.get(2)?, Some((value, 2)));
+        dict.validate()?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,637 @@
+use std::borrow::Borrow;
+use std::marker::PhantomData;
+
+use crate::cell::*;
+use crate::error::Error;
+
+use super::raw::*;
+use super::typed::*;
+use super::{dict_get, read_label, write_label, DictKey};
+
+/// Mutable builder for a [`RawDict`].
+///
+/// Keeps unchanged subtrees as cells and modified nodes in memory,
+/// so that the resulting dictionary is finalized only once in [`build_ext`].
+///
+/// [`build_ext`]: RawDictBuilder::build_ext
+pub struct RawDictBuilder<const N: u16>(DictTree);
+
+impl<const N: u16> Default for RawDictBuilder<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: u16> From<RawDict<N>> for RawDictBuilder<N> {
+    #[inline]
+    fn from(dict: RawDict<N>) -> Self {
+        Self(DictTree::new(dict.root().clone(), N))
+    }
+}
+
+impl<const N: u16> RawDictBuilder<N> {
+    /// Creates an empty dictionary builder.
+    pub const fn new() -> Self {
+        Self(DictTree {
+            root: None,
+            key_bit_len: N,
+        })
+    }
+
+    /// Returns `true` if the dictionary contains no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.0.root.is_none()
+    }
+
+    /// Sets the value associated with the key in the dictionary.
+    pub fn set(&mut self, key: CellSlice<'_>, value: CellSlice<'_>) -> Result<(), Error> {
+        let key = ok!(key_to_cell(&key, N));
+        let mut builder = CellBuilder::new();
+        ok!(builder.store_slice(value));
+        self.0.set(key, builder)
+    }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns `true` if the key was present in the dictionary.
+    pub fn remove(&mut self, key: CellSlice<'_>) -> Result<bool, Error> {
+        if key.remaining_bits() != N {
+            return Err(Error::CellUnderflow);
+        }
+        self.0.remove(&key)
+    }
+
+    /// Builds the dictionary, finalizing each modified cell once.
+    pub fn build_ext(self, finalizer: &mut dyn Finalizer) -> Result<RawDict<N>, Error> {
+        match self.0.build(finalizer) {
+            Ok(root) => Ok(RawDict::from(root)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Builds the dictionary, finalizing each modified cell once.
+    ///
+    /// Use [`build_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`build_ext`]: RawDictBuilder::build_ext
+    pub fn build(self) -> Result<RawDict<N>, Error> {
+        self.build_ext(&mut Cell::default_finalizer())
+    }
+}
+
+/// Mutable builder for a [`Dict`].
+///
+/// Keeps unchanged subtrees as cells and modified nodes in memory,
+/// so that the resulting dictionary is finalized only once in [`build_ext`].
+///
+/// Only dictionary nodes go through the finalizer passed to [`build_ext`].
+/// Cells created while serializing values (e.g. child cells) are finalized
+/// immediately with the finalizer passed to [`set_ext`]. Keys are copied into
+/// temporary cells which never become a part of the dictionary.
+///
+/// [`build_ext`]: DictBuilder::build_ext
+/// [`set_ext`]: DictBuilder::set_ext
+pub struct DictBuilder<K, V> {
+    tree: DictTree,
+    _key: PhantomData<K>,
+    _value: PhantomData<V>,
+}
+
+impl<K: DictKey, V> Default for DictBuilder<K, V> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: DictKey, V> From<Dict<K, V>> for DictBuilder<K, V> {
+    #[inline]
+    fn from(dict: Dict<K, V>) -> Self {
+        Self {
+            tree: DictTree::new(dict.root().clone(), K::BITS),
+            _key: PhantomData,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<K: DictKey, V> DictBuilder<K, V> {
+    /// Creates an empty dictionary builder.
+    pub const fn new() -> Self {
+        Self {
+            tree: DictTree {
+                root: None,
+                key_bit_len: K::BITS,
+            },
+            _key: PhantomData,
+            _value: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the dictionary contains no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.tree.root.is_none()
+    }
+
+    /// Builds the dictionary, finalizing each modified cell once.
+    pub fn build_ext(self, finalizer: &mut dyn Finalizer) -> Result<Dict<K, V>, Error> {
+        match self.tree.build(finalizer) {
+            Ok(root) => Ok(Dict::from(root)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Builds the dictionary, finalizing each modified cell once.
+    ///
+    /// Use [`build_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`build_ext`]: DictBuilder::build_ext
+    pub fn build(self) -> Result<Dict<K, V>, Error> {
+        self.build_ext(&mut Cell::default_finalizer())
+    }
+}
+
+impl<K, V> DictBuilder<K, V>
+where
+    K: Store + DictKey,
+    V: Store,
+{
+    /// Sets the value associated with the key in the dictionary.
+    ///
+    /// Use [`set_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`set_ext`]: DictBuilder::set_ext
+    pub fn set<Q, T>(&mut self, key: Q, value: T) -> Result<(), Error>
+    where
+        Q: Borrow<K>,
+        T: Borrow<V>,
+    {
+        self.set_ext(key, value, &mut Cell::default_finalizer())
+    }
+
+    /// Sets the value associated with the key in the dictionary.
+    ///
+    /// Cells created while serializing the value are finalized
+    /// with the provided finalizer.
+    pub fn set_ext<Q, T>(
+        &mut self,
+        key: Q,
+        value: T,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error>
+    where
+        Q: Borrow<K>,
+        T: Borrow<V>,
+    {
+        let key = ok!(serialize_key(key.borrow(), K::BITS));
+
+        let mut builder = CellBuilder::new();
+        ok!(value.borrow().store_into(&mut builder, finalizer));
+        self.tree.set(key, builder)
+    }
+}
+
+impl<K, V> DictBuilder<K, V>
+where
+    K: Store + DictKey,
+{
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns `true` if the key was present in the dictionary.
+    pub fn remove<Q>(&mut self, key: Q) -> Result<bool, Error>
+    where
+        Q: Borrow<K>,
+    {
+        let key = ok!(serialize_key(key.borrow(), K::BITS));
+        self.tree.remove(&ok!(key.as_slice()))
+    }
+}
+
+/// Serializes the key into a temporary cell and checks its length.
+///
+/// NOTE: key cells are never a part of the dictionary,
+/// so they are always built with the default finalizer.
+fn serialize_key<K: Store>(key: &K, key_bit_len: u16) -> Result<Cell, Error> {
+    let finalizer = &mut Cell::default_finalizer();
+
+    let mut builder = CellBuilder::new();
+    ok!(key.store_into(&mut builder, finalizer));
+    if builder.bit_len() != key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+    builder.build_ext(finalizer)
+}
+
+/// Checks the key length and copies its data into a separate cell.
+fn key_to_cell(key: &CellSlice<'_>, key_bit_len: u16) -> Result<Cell, Error> {
+    if key.remaining_bits() != key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+    let mut builder = CellBuilder::new();
+    ok!(builder.store_slice_data(key));
+    builder.build_ext(&mut Cell::default_finalizer())
+}
+
+/// Returns the key bits in the range `start..split`.
+fn key_label(key: &Cell, start: u16, split: u16) -> Result<CellSlice<'_>, Error> {
+    let mut label = ok!(key.as_slice()).get_prefix(split, 0);
+    if !label.try_advance(start, 0) {
+        return Err(Error::CellUnderflow);
+    }
+    Ok(label)
+}
+
+/// Dictionary tree with modified nodes kept in memory.
+struct DictTree {
+    root: Option<Node>,
+    key_bit_len: u16,
+}
+
+/// Dictionary tree node.
+///
+/// Modified nodes store some full key from their subtree, so their labels
+/// can be computed for any depth. Unchanged subtrees are always kept
+/// at their original depth, because their labels are already serialized.
+enum Node {
+    /// Unchanged subtree.
+    Cell(Cell),
+    /// Modified leaf.
+    Leaf { key: Cell, value: CellBuilder },
+    /// Modified fork at the `split` bit of the key.
+    Fork {
+        key: Cell,
+        split: u16,
+        children: Box<[Node; 2]>,
+    },
+}
+
+impl DictTree {
+    fn new(root: Option<Cell>, key_bit_len: u16) -> Self {
+        Self {
+            root: root.map(Node::Cell),
+            key_bit_len,
+        }
+    }
+
+    fn set(&mut self, key: Cell, value: CellBuilder) -> Result<(), Error> {
+        let key_bit_len = self.key_bit_len;
+        let path = ok!(key.as_slice());
+
+        let mut node = match &mut self.root {
+            Some(root) => root,
+            None => {
+                self.root = Some(Node::Leaf { key, value });
+                return Ok(());
+            }
+        };
+
+        let mut start = 0;
+        loop {
+            ok!(node.expand(path.get_prefix(start, 0), key_bit_len));
+
+            let split = node.split(key_bit_len);
+            let lcp_len = {
+                let label = ok!(node.label(start, key_bit_len));
+                let mut remaining = path;
+                remaining.try_advance(start, 0);
+                label
+                    .longest_common_data_prefix(&remaining)
+                    .remaining_bits()
+            };
+            let pos = start + lcp_len;
+
+            if pos < split {
+                // Key diverges inside the label, so insert a new fork
+                let is_right = ok!(path.get_bit(pos));
+                let leaf = Node::Leaf {
+                    key: key.clone(),
+                    value,
+                };
+                let old = std::mem::replace(node, Node::Cell(Cell::empty_cell()));
+                *node = Node::Fork {
+                    key,
+                    split: pos,
+                    children: Box::new(if is_right { [old, leaf] } else { [leaf, old] }),
+                };
+                return Ok(());
+            }
+
+            node = match node {
+                Node::Leaf { value: old, .. } => {
+                    *old = value;
+                    return Ok(());
+                }
+                Node::Fork { children, .. } => {
+                    let branch = ok!(path.get_bit(split)) as usize;
+                    &mut children[branch]
+                }
+                Node::Cell(_) => return Err(Error::InvalidData),
+            };
+            start = split + 1;
+        }
+    }
+
+    fn remove(&mut self, key: &CellSlice<'_>) -> Result<bool, Error> {
+        enum Removed {
+            None,
+            Child,
+            Node,
+        }
+
+        fn remove_impl(
+            node: &mut Node,
+            start: u16,
+            key: &CellSlice<'_>,
+            key_bit_len: u16,
+        ) -> Result<Removed, Error> {
+            if let Node::Cell(cell) = node {
+                // Don't expand subtrees without the key
+                let mut remaining = *key;
+                remaining.try_advance(start, 0);
+                if ok!(dict_get(
+                    &Some(cell.clone()),
+                    key_bit_len - start,
+                    remaining
+                ))
+                .is_none()
+                {
+                    return Ok(Removed::None);
+                }
+            }
+            ok!(node.expand(key.get_prefix(start, 0), key_bit_len));
+
+            let split = node.split(key_bit_len);
+            let lcp_len = {
+                let label = ok!(node.label(start, key_bit_len));
+                let mut remaining = *key;
+                remaining.try_advance(start, 0);
+                label
+                    .longest_common_data_prefix(&remaining)
+                    .remaining_bits()
+            };
+            if start + lcp_len < split {
+                return Ok(Removed::None);
+            } else if split == key_bit_len {
+                return Ok(Removed::Node);
+            }
+
+            let branch = ok!(key.get_bit(split)) as usize;
+            let Node::Fork { children, .. } = node else {
+                return Err(Error::InvalidData);
+            };
+            match ok!(remove_impl(
+                &mut children[branch],
+                split + 1,
+                key,
+                key_bit_len
+            )) {
+                Removed::Node => {}
+                other => return Ok(other),
+            }
+
+            // Replace the fork with the remaining child
+            let mut sibling =
+                std::mem::replace(&mut children[1 - branch], Node::Cell(Cell::empty_cell()));
+
+            let mut prefix = CellBuilder::new();
+            ok!(prefix.store_slice_data(key.get_prefix(split, 0)));
+            ok!(prefix.store_bit(branch == 0));
+            let prefix = ok!(prefix.build_ext(&mut Cell::default_finalizer()));
+            ok!(sibling.expand(ok!(prefix.as_slice()), key_bit_len));
+
+            *node = sibling;
+            Ok(Removed::Child)
+        }
+
+        let Some(root) = &mut self.root else {
+            return Ok(false);
+        };
+        match ok!(remove_impl(root, 0, key, self.key_bit_len)) {
+            Removed::None => Ok(false),
+            Removed::Child => Ok(true),
+            Removed::Node => {
+                self.root = None;
+                Ok(true)
+            }
+        }
+    }
+
+    fn build(self, finalizer: &mut dyn Finalizer) -> Result<Option<Cell>, Error> {
+        match self.root {
+            Some(root) => match root.build(0, self.key_bit_len, finalizer) {
+                Ok(root) => Ok(Some(root)),
+                Err(e) => Err(e),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+impl Node {
+    /// Converts an unchanged subtree into a modified node.
+    ///
+    /// `prefix` must contain the key bits before the node label.
+    fn expand(&mut self, prefix: CellSlice<'_>, key_bit_len: u16) -> Result<(), Error> {
+        let Node::Cell(cell) = self else {
+            return Ok(());
+        };
+
+        let start = prefix.remaining_bits();
+        let mut data = ok!(cell.as_slice());
+        let label = ok!(read_label(&mut data, key_bit_len - start));
+        let split = start + label.remaining_bits();
+
+        let mut key = CellBuilder::new();
+        ok!(key.store_slice_data(prefix));
+        ok!(key.store_slice_data(label));
+        ok!(key.store_zeros(key_bit_len - split));
+        let key = ok!(key.build_ext(&mut Cell::default_finalizer()));
+
+        *self = if split == key_bit_len {
+            let mut value = CellBuilder::new();
+            ok!(value.store_slice(data));
+            Node::Leaf { key, value }
+        } else {
+            let (Some(left), Some(right)) = (
+                cell.as_ref().reference_cloned(0),
+                cell.as_ref().reference_cloned(1),
+            ) else {
+                return Err(Error::CellUnderflow);
+            };
+            Node::Fork {
+                key,
+                split,
+                children: Box::new([Node::Cell(left), Node::Cell(right)]),
+            }
+        };
+        Ok(())
+    }
+
+    /// Returns the length of the common prefix of all keys in the subtree.
+    fn split(&self, key_bit_len: u16) -> u16 {
+        match self {
+            Node::Fork { split, .. } => *split,
+            _ => key_bit_len,
+        }
+    }
+
+    /// Returns the label of the modified node which starts at the `start` bit.
+    fn label(&self, start: u16, key_bit_len: u16) -> Result<CellSlice<'_>, Error> {
+        let (key, split) = match self {
+            Node::Leaf { key, .. } => (key, key_bit_len),
+            Node::Fork { key, split, .. } => (key, *split),
+            Node::Cell(_) => return Err(Error::InvalidData),
+        };
+
+        key_label(key, start, split)
+    }
+
+    fn build(
+        self,
+        start: u16,
+        key_bit_len: u16,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Cell, Error> {
+        let mut builder = CellBuilder::new();
+        match self {
+            Node::Cell(cell) => return Ok(cell),
+            Node::Leaf { key, value } => {
+                let label = ok!(key_label(&key, start, key_bit_len));
+                ok!(write_label(&label, key_bit_len - start, &mut builder));
+                ok!(builder.store_builder(&value));
+            }
+            Node::Fork {
+                key,
+                split,
+                children,
+            } => {
+                let label = ok!(key_label(&key, start, split));
+                ok!(write_label(&label, key_bit_len - start, &mut builder));
+
+                let [left, right] = *children;
+                ok!(builder.store_reference(ok!(left.build(split + 1, key_bit_len, finalizer))));
+                ok!(builder.store_reference(ok!(right.build(split + 1, key_bit_len, finalizer))));
+            }
+        }
+        builder.build_ext(finalizer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dict_builder_set_remove() -> anyhow::Result<()> {
+        let mut expected = Dict::<u32, u16>::new();
+        for i in 0..200u32 {
+            expected.set(i.wrapping_mul(0x9e3779b9), i as u16)?;
+        }
+
+        let mut builder = DictBuilder::from(expected.clone());
+        for i in 0..400u32 {
+            let key = i.wrapping_mul(0x9e3779b9) ^ (i % 3);
+            if i % 5 == 0 {
+                assert_eq!(builder.remove(key)?, expected.remove(key)?.is_some());
+            } else {
+                builder.set(key, i as u16)?;
+                expected.set(key, i as u16)?;
+            }
+        }
+
+        // Remove some keys in a different order
+        for i in (0..400u32).step_by(7).rev() {
+            let key = i.wrapping_mul(0x9e3779b9);
+            assert_eq!(builder.remove(key)?, expected.remove(key)?.is_some());
+        }
+
+        assert_eq!(builder.build()?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn dict_builder_single_finalization() -> anyhow::Result<()> {
+        let mut dict = RawDict::<32>::new();
+        for i in 0..100u32 {
+            let key = CellBuilder::build_from(i * 1000)?;
+            let value = CellBuilder::build_from(i)?;
+            dict.set(key.as_slice()?, value.as_slice()?)?;
+        }
+
+        // Only cells on the modified path are finalized
+        let key = CellBuilder::build_from(1000u32)?;
+        let value = CellBuilder::build_from(123u32)?;
+
+        let mut builder = RawDictBuilder::from(dict.clone());
+        builder.set(key.as_slice()?, value.as_slice()?)?;
+        builder.set(key.as_slice()?, value.as_slice()?)?;
+
+        let mut finalized = 0;
+        let mut finalizer = |parts: CellParts| {
+            finalized += 1;
+            Cell::default_finalizer().finalize_cell(parts)
+        };
+        let result = builder.build_ext(&mut finalizer)?;
+
+        dict.set(key.as_slice()?, value.as_slice()?)?;
+        assert_eq!(result, dict);
+
+        // Count nodes on the path to the modified key
+        let mut path_len = 0;
+        let mut node = result.root().clone().unwrap();
+        let mut key_bits = key.as_slice()?;
+        let mut remaining = 32;
+        loop {
+            path_len += 1;
+            let mut data = node.as_slice()?;
+            let label = read_label(&mut data, remaining)?;
+            key_bits.try_advance(label.remaining_bits(), 0);
+            remaining -= label.remaining_bits();
+            if remaining == 0 {
+                break;
+            }
+            let branch = key_bits.load_bit()? as u8;
+            remaining -= 1;
+            node = node.as_ref().reference_cloned(branch).unwrap();
+        }
+        assert!(path_len > 1);
+        assert_eq!(finalized, path_len);
+
+        // Removing all entries produces an empty dictionary
+        let mut builder = RawDictBuilder::from(dict);
+        for i in 0..100u32 {
+            let key = CellBuilder::build_from(i * 1000)?;
+            assert!(builder.remove(key.as_slice()?)?);
+            assert!(!builder.remove(key.as_slice()?)?);
+        }
+        assert!(builder.is_empty());
+        assert!(builder.build()?.is_empty());
+
+        // Value cells are finalized with the provided finalizer
+        struct Boxed(u32);
+
+        impl Store for Boxed {
+            fn store_into(
+                &self,
+                builder: &mut CellBuilder,
+                finalizer: &mut dyn Finalizer,
+            ) -> Result<(), Error> {
+                let mut child = CellBuilder::new();
+                child.store_u32(self.0)?;
+                builder.store_reference(child.build_ext(finalizer)?)
+            }
+        }
+
+        let mut finalized = 0;
+        let mut finalizer = |parts: CellParts| {
+            finalized += 1;
+            Cell::default_finalizer().finalize_cell(parts)
+        };
+        let mut builder = DictBuilder::<u32, Boxed>::new();
+        builder.set_ext(1, Boxed(0xaa), &mut finalizer)?;
+        builder.set_ext(2, Boxed(0xbb), &mut finalizer)?;
+        builder.set(3, Boxed(0xcc))?;
+        assert_eq!(finalized, 2);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,506 @@
+use std::marker::PhantomData;
+
+use crate::cell::*;
+use crate::error::Error;
+use crate::util::{unlikely, IterStatus};
+
+use super::{read_label, DictKey};
+
+/// A difference between two dictionaries for a single key.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum DictDiffItem<K, V> {
+    /// The entry is present only in the new dictionary.
+    Added(K, V),
+    /// The entry is present only in the old dictionary.
+    Removed(K, V),
+    /// The entry is present in both dictionaries with different values.
+    /// Contains the old value followed by the new one.
+    Changed(K, V, V),
+}
+
+impl<K, V> DictDiffItem<K, V> {
+    /// Returns the key of the entry.
+    pub fn key(&self) -> &K {
+        match self {
+            Self::Added(key, _) | Self::Removed(key, _) | Self::Changed(key, _, _) => key,
+        }
+    }
+}
+
+/// An iterator over the differences between two [`RawDict`]s, sorted by key.
+///
+/// Identical subtrees are skipped by comparing their representation hashes,
+/// so the cost is proportional to the size of the change.
+///
+/// This struct is created by the [`diff`] method on [`RawDict`]
+/// or the [`raw_diff`] method on [`Dict`]. See their documentation for more.
+///
+/// [`RawDict`]: crate::dict::RawDict
+/// [`Dict`]: crate::dict::Dict
+/// [`diff`]: crate::dict::RawDict::diff
+/// [`raw_diff`]: crate::dict::Dict::raw_diff
+#[derive(Clone)]
+pub struct RawDictDiff<'a> {
+    segments: Vec<DiffSegment<'a>>,
+    status: IterStatus,
+}
+
+impl<'a> RawDictDiff<'a> {
+    /// Creates an iterator over the differences between two dictionaries.
+    pub fn new(old: &'a Option<Cell>, new: &'a Option<Cell>, bit_len: u16) -> Self {
+        let mut status = IterStatus::Valid;
+        let mut make_node = |root: &'a Option<Cell>| {
+            let data = root.as_ref()?.as_ref();
+            if unlikely(data.descriptor().is_pruned_branch()) {
+                status = IterStatus::Pruned;
+            }
+            Some(DiffNode {
+                data,
+                key_bit_len: bit_len,
+                skip: 0,
+            })
+        };
+
+        let segment = DiffSegment {
+            key: CellBuilder::new(),
+            old: make_node(old),
+            new: make_node(new),
+        };
+
+        Self {
+            segments: if status.is_valid() {
+                vec![segment]
+            } else {
+                Vec::new()
+            },
+            status,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn finish(&mut self, err: Error) -> Error {
+        self.status = IterStatus::Broken;
+        err
+    }
+}
+
+impl<'a> Iterator for RawDictDiff<'a> {
+    type Item = Result<DictDiffItem<CellBuilder, CellSlice<'a>>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if unlikely(!self.status.is_valid()) {
+            return if self.status.is_pruned() {
+                self.status = IterStatus::Broken;
+                Some(Err(Error::PrunedBranchAccess))
+            } else {
+                None
+            };
+        }
+
+        match next_diff(&mut self.segments) {
+            Ok(Some(item)) => Some(Ok(item)),
+            Ok(None) => {
+                self.status = IterStatus::Broken;
+                None
+            }
+            Err(e) => Some(Err(self.finish(e))),
+        }
+    }
+}
+
+/// An iterator over the differences between two [`Dict`]s, sorted by key.
+///
+/// This struct is created by the [`diff`] method on [`Dict`]. See its documentation for more.
+///
+/// [`Dict`]: crate::dict::Dict
+/// [`diff`]: crate::dict::Dict::diff
+pub struct DictDiff<'a, K, V> {
+    inner: RawDictDiff<'a>,
+    _key: PhantomData<K>,
+    _value: PhantomData<V>,
+}
+
+impl<K, V> Clone for DictDiff<'_, K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            _key: PhantomData,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> DictDiff<'a, K, V>
+where
+    K: DictKey,
+{
+    /// Creates an iterator over the differences between two dictionaries.
+    pub fn new(old: &'a Option<Cell>, new: &'a Option<Cell>) -> Self {
+        Self {
+            inner: RawDictDiff::new(old, new, K::BITS),
+            _key: PhantomData,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for DictDiff<'a, K, V>
+where
+    K: DictKey,
+    V: Load<'a>,
+{
+    type Item = Result<DictDiffItem<K, V>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        fn load_item<'a, K, V>(
+            item: DictDiffItem<CellBuilder, CellSlice<'a>>,
+        ) -> Result<DictDiffItem<K, V>, Error>
+        where
+            K: DictKey,
+            V: Load<'a>,
+        {
+            let key = match K::from_raw_data(item.key().raw_data()) {
+                Some(key) => key,
+                None => return Err(Error::CellUnderflow),
+            };
+            Ok(match item {
+                DictDiffItem::Added(_, mut value) => {
+                    DictDiffItem::Added(key, ok!(V::load_from(&mut value)))
+                }
+                DictDiffItem::Removed(_, mut value) => {
+                    DictDiffItem::Removed(key, ok!(V::load_from(&mut value)))
+                }
+                DictDiffItem::Changed(_, mut old, mut new) => DictDiffItem::Changed(
+                    key,
+                    ok!(V::load_from(&mut old)),
+                    ok!(V::load_from(&mut new)),
+                ),
+            })
+        }
+
+        Some(match self.inner.next()? {
+            Ok(item) => match load_item(item) {
+                Ok(item) => Ok(item),
+                Err(e) => Err(self.inner.finish(e)),
+            },
+            Err(e) => Err(e),
+        })
+    }
+}
+
+/// A pair of subtrees with the same key prefix.
+#[derive(Clone)]
+struct DiffSegment<'a> {
+    /// Key bits before the labels of both nodes.
+    key: CellBuilder,
+    old: Option<DiffNode<'a>>,
+    new: Option<DiffNode<'a>>,
+}
+
+/// A subtree with a partially consumed label.
+#[derive(Clone, Copy)]
+struct DiffNode<'a> {
+    data: &'a DynCell,
+    /// Remaining key length at the start of the node label.
+    key_bit_len: u16,
+    /// Number of the label bits which are already in the segment key.
+    skip: u16,
+}
+
+impl<'a> DiffNode<'a> {
+    /// Returns the rest of the label and the node data after it.
+    fn read(&self) -> Result<(CellSlice<'a>, CellSlice<'a>), Error> {
+        let mut data = ok!(self.data.as_slice());
+        let mut label = ok!(read_label(&mut data, self.key_bit_len));
+        if !label.try_advance(self.skip, 0) {
+            return Err(Error::CellUnderflow);
+        }
+        Ok((label, data))
+    }
+
+    fn remaining_bit_len(&self) -> u16 {
+        self.key_bit_len - self.skip
+    }
+
+    /// Returns the same node without the next `bits` of the label.
+    fn advanced(self, bits: u16) -> Self {
+        Self {
+            skip: self.skip + bits,
+            ..self
+        }
+    }
+
+    fn child(data: &CellSlice<'a>, bit: bool, key_bit_len: u16) -> Result<Self, Error> {
+        Ok(Self {
+            data: ok!(data.get_reference(bit as u8)),
+            key_bit_len,
+            skip: 0,
+        })
+    }
+}
+
+/// Pops segments until the next difference is found.
+///
+/// Segments are pushed in the reverse order, so that the subtree
+/// with the smallest keys is always at the top of the stack.
+fn next_diff<'a>(
+    segments: &mut Vec<DiffSegment<'a>>,
+) -> Result<Option<DictDiffItem<CellBuilder, CellSlice<'a>>>, Error> {
+    while let Some(DiffSegment { key, old, new }) = segments.pop() {
+        let (old, new) = match (old, new) {
+            (Some(old), Some(new)) => (old, new),
+            (Some(old), None) => {
+                if let Some((key, value)) = ok!(expand_single(segments, key, old, true)) {
+                    return Ok(Some(DictDiffItem::Removed(key, value)));
+                }
+                continue;
+            }
+            (None, Some(new)) => {
+                if let Some((key, value)) = ok!(expand_single(segments, key, new, false)) {
+                    return Ok(Some(DictDiffItem::Added(key, value)));
+                }
+                continue;
+            }
+            (None, None) => continue,
+        };
+
+        // Skip identical subtrees
+        if old.key_bit_len == new.key_bit_len
+            && old.skip == new.skip
+            && old.data.repr_hash() == new.data.repr_hash()
+        {
+            continue;
+        }
+
+        let remaining = old.remaining_bit_len();
+        if remaining != new.remaining_bit_len() {
+            return Err(Error::CellUnderflow);
+        }
+
+        let (old_label, old_data) = ok!(old.read());
+        let (new_label, new_data) = ok!(new.read());
+
+        let lcp = old_label.longest_common_data_prefix(&new_label);
+        let lcp_len = lcp.remaining_bits();
+        let old_len = old_label.remaining_bits();
+        let new_len = new_label.remaining_bits();
+
+        let mut prefix = key;
+        ok!(prefix.store_slice_data(lcp));
+
+        if lcp_len == remaining {
+            // Both nodes are leaves with the same key
+            if slices_eq(&old_data, &new_data) {
+                continue;
+            }
+            return Ok(Some(DictDiffItem::Changed(prefix, old_data, new_data)));
+        }
+
+        let child_key_bit_len = remaining - lcp_len - 1;
+        for bit in [true, false] {
+            let mut key = prefix.clone();
+            ok!(key.store_bit(bit));
+
+            let segment = if lcp_len == old_len && lcp_len == new_len {
+                // Both nodes are forks at the same position
+                DiffSegment {
+                    key,
+                    old: Some(ok!(DiffNode::child(&old_data, bit, child_key_bit_len))),
+                    new: Some(ok!(DiffNode::child(&new_data, bit, child_key_bit_len))),
+                }
+            } else if lcp_len == old_len {
+                // The new node is inside one of the old fork subtrees
+                let new_bit = ok!(new_label.get_bit(lcp_len));
+                DiffSegment {
+                    key,
+                    old: Some(ok!(DiffNode::child(&old_data, bit, child_key_bit_len))),
+                    new: (bit == new_bit).then(|| new.advanced(lcp_len + 1)),
+                }
+            } else if lcp_len == new_len {
+                // The old node is inside one of the new fork subtrees
+                let old_bit = ok!(old_label.get_bit(lcp_len));
+                DiffSegment {
+                    key,
+                    old: (bit == old_bit).then(|| old.advanced(lcp_len + 1)),
+                    new: Some(ok!(DiffNode::child(&new_data, bit, child_key_bit_len))),
+                }
+            } else {
+                // Labels diverge, so subtrees have no common keys
+                let old_bit = ok!(old_label.get_bit(lcp_len));
+                DiffSegment {
+                    key,
+                    old: (bit == old_bit).then(|| old.advanced(lcp_len + 1)),
+                    new: (bit != old_bit).then(|| new.advanced(lcp_len + 1)),
+                }
+            };
+            segments.push(segment);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Returns the entry if the node is a leaf, otherwise
+/// pushes its children as one-sided segments.
+fn expand_single<'a>(
+    segments: &mut Vec<DiffSegment<'a>>,
+    mut key: CellBuilder,
+    node: DiffNode<'a>,
+    is_old: bool,
+) -> Result<Option<(CellBuilder, CellSlice<'a>)>, Error> {
+    let (label, data) = ok!(node.read());
+    ok!(key.store_slice_data(label));
+
+    let remaining = node.remaining_bit_len();
+    let label_len = label.remaining_bits();
+    if label_len == remaining {
+        return Ok(Some((key, data)));
+    }
+
+    let child_key_bit_len = remaining - label_len - 1;
+    for bit in [true, false] {
+        let mut key = key.clone();
+        ok!(key.store_bit(bit));
+
+        let child = Some(ok!(DiffNode::child(&data, bit, child_key_bit_len)));
+        segments.push(if is_old {
+            DiffSegment {
+                key,
+                old: child,
+                new: None,
+            }
+        } else {
+            DiffSegment {
+                key,
+                old: None,
+                new: child,
+            }
+        });
+    }
+    Ok(None)
+}
+
+/// Compares the data and the references of two slices.
+fn slices_eq(a: &CellSlice<'_>, b: &CellSlice<'_>) -> bool {
+    let bits = a.remaining_bits();
+    bits == b.remaining_bits()
+        && a.remaining_refs() == b.remaining_refs()
+        && a.longest_common_data_prefix(b).remaining_bits() == bits
+        && a.references()
+            .zip(b.references())
+            .all(|(a, b)| a.repr_hash() == b.repr_hash())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::dict::Dict;
+
+    fn naive_diff(
+        old: &BTreeMap<u32, u32>,
+        new: &BTreeMap<u32, u32>,
+    ) -> Vec<DictDiffItem<u32, u32>> {
+        let mut result = Vec::new();
+        for (&key, &old_value) in old {
+            match new.get(&key) {
+                None => result.push(DictDiffItem::Removed(key, old_value)),
+                Some(&new_value) if new_value != old_value => {
+                    result.push(DictDiffItem::Changed(key, old_value, new_value))
+                }
+                Some(_) => {}
+            }
+        }
+        for (&key, &new_value) in new {
+            if !old.contains_key(&key) {
+                result.push(DictDiffItem::Added(key, new_value));
+            }
+        }
+        result.sort_by_key(|item| *item.key());
+        result
+    }
+
+    fn build_dict(entries: &BTreeMap<u32, u32>) -> anyhow::Result<Dict<u32, u32>> {
+        let mut dict = Dict::new();
+        for (key, value) in entries {
+            dict.set(key, value)?;
+        }
+        Ok(dict)
+    }
+
+    #[test]
+    fn dict_diff() -> anyhow::Result<()> {
+        let base = (0..200u32)
+            .map(|i| (i.wrapping_mul(0x9e3779b9), i))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut versions = vec![BTreeMap::new(), base.clone()];
+
+        // Change values
+        let mut changed = base.clone();
+        for (i, value) in changed.values_mut().enumerate() {
+            if i % 17 == 0 {
+                *value += 1;
+            }
+        }
+        versions.push(changed);
+
+        // Add and remove entries
+        let mut modified = base.clone();
+        for i in 0..50u32 {
+            modified.remove(&i.wrapping_mul(0x9e3779b9));
+            modified.insert(i.wrapping_mul(0x12345679) ^ 1, i);
+        }
+        versions.push(modified);
+
+        // Single entry
+        versions.push(BTreeMap::from([(0x80000000, 1)]));
+
+        for old in &versions {
+            let old_dict = build_dict(old)?;
+            for new in &versions {
+                let new_dict = build_dict(new)?;
+
+                let diff = old_dict.diff(&new_dict).collect::<Result<Vec<_>, _>>()?;
+                assert_eq!(diff, naive_diff(old, new));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_diff_skips_same_subtrees() -> anyhow::Result<()> {
+        let mut old = Dict::<u32, u32>::new();
+        for i in 0..1000 {
+            old.set(i, i)?;
+        }
+
+        let mut new = old.clone();
+        new.set(500, 0)?;
+
+        // Track accessed cells of the old dictionary
+        let usage_tree = UsageTree::new(UsageTreeMode::OnDataAccess);
+        let tracked = Dict::<u32, u32>::from(Some(usage_tree.track(old.root().as_ref().unwrap())));
+
+        assert_eq!(
+            tracked.diff(&new).collect::<Result<Vec<_>, _>>()?,
+            [DictDiffItem::Changed(500, 500, 0)]
+        );
+
+        // Only cells on the changed path are visited
+        let mut stack = vec![old.root().clone().unwrap()];
+        let mut total = 0;
+        let mut visited = 0;
+        while let Some(cell) = stack.pop() {
+            total += 1;
+            visited += usage_tree.contains(cell.repr_hash()) as usize;
+            stack.extend(cell.references().cloned());
+        }
+        assert_eq!(total, 1999);
+        assert!(visited <= 2 * 10 + 1, "visited: {visited}");
+
+        Ok(())
+    }
+}
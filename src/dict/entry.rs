@@ -0,0 +1,557 @@
+use std::marker::PhantomData;
+
+use crate::cell::*;
+use crate::error::Error;
+
+use super::{label_part, make_fork, read_label, replace_label, write_label, Branch, DictKey};
+
+/// A view into a single entry in a [`Dict`], which may either be vacant or occupied.
+///
+/// This enum is constructed from the [`entry`] method on [`Dict`].
+/// The path to the entry is found once, so any modification
+/// rebuilds only this path without another descent.
+///
+/// [`Dict`]: crate::dict::Dict
+/// [`entry`]: crate::dict::Dict::entry
+pub enum Entry<'a, K, V> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V>),
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+/// A view into an occupied entry in a [`Dict`].
+/// It is part of the [`Entry`] enum.
+///
+/// [`Dict`]: crate::dict::Dict
+pub struct OccupiedEntry<'a, K, V> {
+    root: &'a mut Option<Cell>,
+    path: DictPath,
+    key: K,
+    _value: PhantomData<V>,
+}
+
+/// A view into a vacant entry in a [`Dict`].
+/// It is part of the [`Entry`] enum.
+///
+/// [`Dict`]: crate::dict::Dict
+pub struct VacantEntry<'a, K, V> {
+    root: &'a mut Option<Cell>,
+    path: DictPath,
+    key: K,
+    _value: PhantomData<V>,
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    K: DictKey,
+{
+    pub(crate) fn new(
+        root: &'a mut Option<Cell>,
+        key: K,
+        serialized_key: Cell,
+    ) -> Result<Self, Error> {
+        let path = ok!(DictPath::find(root, serialized_key, K::BITS));
+        Ok(if path.found {
+            Self::Occupied(OccupiedEntry {
+                root,
+                path,
+                key,
+                _value: PhantomData,
+            })
+        } else {
+            Self::Vacant(VacantEntry {
+                root,
+                path,
+                key,
+                _value: PhantomData,
+            })
+        })
+    }
+
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        match self {
+            Self::Occupied(entry) => entry.key(),
+            Self::Vacant(entry) => entry.key(),
+        }
+    }
+}
+
+impl<'a, K, V> Entry<'a, K, V>
+where
+    V: Store,
+    for<'b> V: Load<'b>,
+{
+    /// Ensures a value is in the entry by inserting the result of the
+    /// `default` function if empty. Returns the value in the entry.
+    pub fn or_insert_with_ext<F>(
+        self,
+        default: F,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<V, Error>
+    where
+        F: FnOnce() -> V,
+    {
+        match self {
+            Self::Occupied(entry) => entry.get(),
+            Self::Vacant(entry) => {
+                let value = default();
+                ok!(entry.insert_ext(&value, finalizer));
+                Ok(value)
+            }
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry
+    /// before any potential inserts into the dictionary.
+    pub fn and_modify_ext<F>(self, f: F, finalizer: &mut dyn Finalizer) -> Result<Self, Error>
+    where
+        F: FnOnce(&mut V),
+    {
+        match self {
+            Self::Occupied(mut entry) => {
+                let mut value = ok!(entry.get());
+                f(&mut value);
+                ok!(entry.insert_ext(value, finalizer));
+                Ok(Self::Occupied(entry))
+            }
+            Self::Vacant(entry) => Ok(Self::Vacant(entry)),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the
+    /// `default` function if empty. Returns the value in the entry.
+    ///
+    /// Use [`or_insert_with_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`or_insert_with_ext`]: Entry::or_insert_with_ext
+    pub fn or_insert_with<F>(self, default: F) -> Result<V, Error>
+    where
+        F: FnOnce() -> V,
+    {
+        self.or_insert_with_ext(default, &mut Cell::default_finalizer())
+    }
+
+    /// Ensures a value is in the entry by inserting the `default` if empty.
+    /// Returns the value in the entry.
+    ///
+    /// Use [`or_insert_with_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`or_insert_with_ext`]: Entry::or_insert_with_ext
+    pub fn or_insert(self, default: V) -> Result<V, Error> {
+        self.or_insert_with_ext(|| default, &mut Cell::default_finalizer())
+    }
+
+    /// Ensures a value is in the entry by inserting the default value if empty.
+    /// Returns the value in the entry.
+    ///
+    /// Use [`or_insert_with_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`or_insert_with_ext`]: Entry::or_insert_with_ext
+    pub fn or_default(self) -> Result<V, Error>
+    where
+        V: Default,
+    {
+        self.or_insert_with_ext(V::default, &mut Cell::default_finalizer())
+    }
+
+    /// Provides in-place mutable access to an occupied entry
+    /// before any potential inserts into the dictionary.
+    ///
+    /// Use [`and_modify_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`and_modify_ext`]: Entry::and_modify_ext
+    pub fn and_modify<F>(self, f: F) -> Result<Self, Error>
+    where
+        F: FnOnce(&mut V),
+    {
+        self.and_modify_ext(f, &mut Cell::default_finalizer())
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Returns a raw value of the entry.
+    pub fn get_raw(&self) -> Result<CellSlice<'_>, Error> {
+        match ok!(self.path.value()) {
+            Some(value) => Ok(value),
+            None => Err(Error::CellUnderflow),
+        }
+    }
+
+    /// Returns a value of the entry.
+    pub fn get(&self) -> Result<V, Error>
+    where
+        for<'b> V: Load<'b>,
+    {
+        V::load_from(&mut ok!(self.get_raw()))
+    }
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V>
+where
+    V: Store,
+    for<'b> V: Load<'b>,
+{
+    /// Sets the value of the entry. Returns the old value.
+    ///
+    /// The entry remains valid, so it can be modified again.
+    pub fn insert_ext<T>(&mut self, value: T, finalizer: &mut dyn Finalizer) -> Result<V, Error>
+    where
+        T: std::borrow::Borrow<V>,
+    {
+        let old = ok!(self.get());
+        let value = ok!(serialize_value(value.borrow(), finalizer));
+        *self.root = Some(ok!(self.path.insert(&value, finalizer)));
+        Ok(old)
+    }
+
+    /// Removes the entry from the dictionary. Returns the removed value.
+    pub fn remove_ext(self, finalizer: &mut dyn Finalizer) -> Result<V, Error> {
+        let old = ok!(self.get());
+        *self.root = ok!(self.path.remove(finalizer));
+        Ok(old)
+    }
+
+    /// Sets the value of the entry. Returns the old value.
+    ///
+    /// Use [`insert_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`insert_ext`]: OccupiedEntry::insert_ext
+    pub fn insert<T>(&mut self, value: T) -> Result<V, Error>
+    where
+        T: std::borrow::Borrow<V>,
+    {
+        self.insert_ext(value, &mut Cell::default_finalizer())
+    }
+
+    /// Removes the entry from the dictionary. Returns the removed value.
+    ///
+    /// Use [`remove_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`remove_ext`]: OccupiedEntry::remove_ext
+    pub fn remove(self) -> Result<V, Error> {
+        self.remove_ext(&mut Cell::default_finalizer())
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    /// Returns a reference to this entry's key.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Takes ownership of the key.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V>
+where
+    V: Store,
+{
+    /// Sets the value of the entry.
+    pub fn insert_ext<T>(mut self, value: T, finalizer: &mut dyn Finalizer) -> Result<(), Error>
+    where
+        T: std::borrow::Borrow<V>,
+    {
+        let value = ok!(serialize_value(value.borrow(), finalizer));
+        *self.root = Some(ok!(self.path.insert(&value, finalizer)));
+        Ok(())
+    }
+
+    /// Sets the value of the entry.
+    ///
+    /// Use [`insert_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`insert_ext`]: VacantEntry::insert_ext
+    pub fn insert<T>(self, value: T) -> Result<(), Error>
+    where
+        T: std::borrow::Borrow<V>,
+    {
+        self.insert_ext(value, &mut Cell::default_finalizer())
+    }
+}
+
+fn serialize_value<V: Store>(
+    value: &V,
+    finalizer: &mut dyn Finalizer,
+) -> Result<CellBuilder, Error> {
+    let mut builder = CellBuilder::new();
+    ok!(value.store_into(&mut builder, finalizer));
+    Ok(builder)
+}
+
+/// A path from the dictionary root to the position of a key.
+struct DictPath {
+    key: Cell,
+    key_bit_len: u16,
+    /// Forks along the path, starting from the root.
+    forks: Vec<PathFork>,
+    /// The last visited node. It is either a leaf with the key, a node with
+    /// the label which diverges from the key, or `None` for an empty dictionary.
+    node: Option<Cell>,
+    /// Remaining key length at the last visited node.
+    node_key_bit_len: u16,
+    found: bool,
+}
+
+struct PathFork {
+    cell: Cell,
+    /// Remaining key length at the fork.
+    key_bit_len: u16,
+    next_branch: Branch,
+}
+
+impl DictPath {
+    fn find(root: &Option<Cell>, key: Cell, key_bit_len: u16) -> Result<Self, Error> {
+        let mut forks = Vec::new();
+        let mut remaining = key_bit_len;
+        let mut node = root.clone();
+
+        let found = loop {
+            let Some(cell) = node.take() else {
+                break false;
+            };
+
+            let mut key_data = ok!(key.as_slice());
+            if !key_data.try_advance(key_bit_len - remaining, 0) {
+                return Err(Error::CellUnderflow);
+            }
+
+            let mut data = ok!(cell.as_slice());
+            let label = ok!(read_label(&mut data, remaining));
+            let label_len = label.remaining_bits();
+            let lcp_len = key_data.longest_common_data_prefix(&label).remaining_bits();
+
+            if lcp_len == remaining {
+                // All bits match, an existing value was found
+                node = Some(cell);
+                break true;
+            } else if lcp_len < label_len {
+                // The key diverges from the label
+                node = Some(cell);
+                break false;
+            }
+
+            // The key contains the entire label, so the node is a fork
+            let next_branch = match ok!(key_data.get_bit(label_len)) {
+                false => Branch::Left,
+                true => Branch::Right,
+            };
+            let child = match cell.as_ref().reference_cloned(next_branch as u8) {
+                Some(child) if cell.as_ref().reference_count() == 2 => child,
+                _ => return Err(Error::CellUnderflow),
+            };
+
+            forks.push(PathFork {
+                cell,
+                key_bit_len: remaining,
+                next_branch,
+            });
+            remaining -= label_len + 1;
+            node = Some(child);
+        };
+
+        Ok(Self {
+            key,
+            key_bit_len,
+            forks,
+            node,
+            node_key_bit_len: remaining,
+            found,
+        })
+    }
+
+    /// Returns the value of the found leaf.
+    fn value(&self) -> Result<Option<CellSlice<'_>>, Error> {
+        match &self.node {
+            Some(node) if self.found => {
+                let mut data = ok!(node.as_slice());
+                ok!(read_label(&mut data, self.node_key_bit_len));
+                Ok(Some(data))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Sets the value for the key and returns the new root.
+    ///
+    /// The path is updated to point to the new leaf.
+    fn insert(
+        &mut self,
+        value: &CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Cell, Error> {
+        let key = self.key.clone();
+        let mut key_rest = ok!(key.as_slice());
+        if !key_rest.try_advance(self.key_bit_len - self.node_key_bit_len, 0) {
+            return Err(Error::CellUnderflow);
+        }
+
+        let unchanged_forks = self.forks.len();
+        let (leaf, subtree) = match &self.node {
+            // Split the node where the key diverges from its label
+            Some(node) if !self.found => {
+                let node_key_bit_len = self.node_key_bit_len;
+
+                let mut data = ok!(node.as_slice());
+                let label = ok!(read_label(&mut data, node_key_bit_len));
+                let lcp_len = key_rest.longest_common_data_prefix(&label).remaining_bits();
+                let child_key_bit_len = node_key_bit_len - lcp_len - 1;
+
+                let old = ok!(replace_label(
+                    node.as_ref(),
+                    node_key_bit_len,
+                    &CellBuilder::new(),
+                    lcp_len + 1,
+                    child_key_bit_len,
+                    finalizer
+                ));
+
+                let mut new_key = key_rest;
+                if !new_key.try_advance(lcp_len + 1, 0) {
+                    return Err(Error::CellUnderflow);
+                }
+                let leaf = ok!(make_leaf(&new_key, child_key_bit_len, value, finalizer));
+
+                let next_branch = match ok!(key_rest.get_bit(lcp_len)) {
+                    false => Branch::Left,
+                    true => Branch::Right,
+                };
+                let (left, right) = match next_branch {
+                    Branch::Left => (leaf.clone(), old),
+                    Branch::Right => (old, leaf.clone()),
+                };
+
+                let mut builder = CellBuilder::new();
+                ok!(write_label(
+                    &key_rest.get_prefix(lcp_len, 0),
+                    node_key_bit_len,
+                    &mut builder
+                ));
+                let fork = ok!(make_fork(
+                    builder,
+                    left,
+                    right,
+                    child_key_bit_len,
+                    None,
+                    finalizer
+                ));
+
+                self.forks.push(PathFork {
+                    cell: fork.clone(),
+                    key_bit_len: node_key_bit_len,
+                    next_branch,
+                });
+                self.node_key_bit_len = child_key_bit_len;
+                (leaf, fork)
+            }
+            // Replace the existing leaf or create a new root
+            _ => {
+                let leaf = ok!(make_leaf(
+                    &key_rest,
+                    self.node_key_bit_len,
+                    value,
+                    finalizer
+                ));
+                (leaf.clone(), leaf)
+            }
+        };
+
+        self.node = Some(leaf);
+        self.found = true;
+
+        rebuild_path(&mut self.forks[..unchanged_forks], subtree, finalizer)
+    }
+
+    /// Removes the found leaf and returns the new root.
+    fn remove(mut self, finalizer: &mut dyn Finalizer) -> Result<Option<Cell>, Error> {
+        let Some(fork) = self.forks.pop() else {
+            return Ok(None);
+        };
+
+        // Join the fork label with the label of the remaining branch
+        let mut data = ok!(fork.cell.as_slice());
+        let label = ok!(read_label(&mut data, fork.key_bit_len));
+
+        let opposite = fork.next_branch.opposite();
+        let sibling = match fork.cell.as_ref().reference(opposite as u8) {
+            Some(cell) => cell,
+            None => return Err(Error::CellUnderflow),
+        };
+
+        let mut merged_prefix = CellBuilder::new();
+        ok!(merged_prefix.store_slice_data(label));
+        ok!(merged_prefix.store_bit(opposite == Branch::Right));
+
+        let node = ok!(replace_label(
+            sibling,
+            fork.key_bit_len - label.remaining_bits() - 1,
+            &merged_prefix,
+            0,
+            fork.key_bit_len,
+            finalizer
+        ));
+
+        rebuild_path(&mut self.forks, node, finalizer).map(Some)
+    }
+}
+
+fn make_leaf(
+    key: &CellSlice<'_>,
+    key_bit_len: u16,
+    value: &CellBuilder,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Cell, Error> {
+    let mut builder = CellBuilder::new();
+    ok!(write_label(key, key_bit_len, &mut builder));
+    ok!(builder.store_builder(value));
+    builder.build_ext(finalizer)
+}
+
+/// Rebuilds the forks from the bottom with the new child,
+/// replacing fork cells in place. Returns the new root.
+fn rebuild_path(
+    forks: &mut [PathFork],
+    mut child: Cell,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Cell, Error> {
+    for fork in forks.iter_mut().rev() {
+        let data = ok!(fork.cell.as_slice());
+        let mut remaining = data;
+        let label = ok!(read_label(&mut remaining, fork.key_bit_len));
+
+        let opposite = match fork
+            .cell
+            .as_ref()
+            .reference_cloned(fork.next_branch.opposite() as u8)
+        {
+            Some(cell) => cell,
+            None => return Err(Error::CellUnderflow),
+        };
+        let (left, right) = match fork.next_branch {
+            Branch::Left => (child, opposite),
+            Branch::Right => (opposite, child),
+        };
+
+        let mut builder = CellBuilder::new();
+        ok!(builder.store_slice_data(ok!(label_part(&data, &remaining))));
+        child = ok!(make_fork(
+            builder,
+            left,
+            right,
+            fork.key_bit_len - label.remaining_bits() - 1,
+            None,
+            finalizer
+        ));
+        fork.cell = child.clone();
+    }
+    Ok(child)
+}
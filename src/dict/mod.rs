@@ -1,15 +1,28 @@
 //! Dictionary implementation.
 
 use crate::cell::*;
-use crate::error::Error;
+use crate::error::{DictValidationError, Error};
+use crate::merkle::MerkleProof;
 
 pub use aug::*;
+pub use builder::*;
+pub use diff::*;
+pub use entry::*;
+pub use pfx::*;
 pub use raw::*;
 pub use typed::*;
+pub use var::*;
+
+pub use everscale_types_proc::DictKey;
 
 mod aug;
+mod builder;
+mod diff;
+mod entry;
+mod pfx;
 mod raw;
 mod typed;
+mod var;
 
 /// Type which can be used as a dictionary key.
 pub trait DictKey: Sized {
@@ -51,6 +64,61 @@ impl_dict_key! {
     HashBytes => 256 => |d| HashBytes(d[..32].try_into().unwrap()),
 }
 
+macro_rules! impl_dict_key_for_tuples {
+    ($( ($($t:ident),+) ),*$(,)?) => {$(
+        impl<$($t: DictKey),+> DictKey for ($($t),*) {
+            const BITS: u16 = 0 $(+ $t::BITS)+;
+
+            fn from_raw_data(raw_data: &[u8; 128]) -> Option<Self> {
+                let mut raw_data = *raw_data;
+                Some(($({
+                    let value = match $t::from_raw_data(&raw_data) {
+                        Some(value) => value,
+                        None => return None,
+                    };
+                    shift_raw_key_data(&mut raw_data, $t::BITS);
+                    value
+                }),*))
+            }
+        }
+    )*};
+}
+
+impl_dict_key_for_tuples! {
+    (T1, T2),
+    (T1, T2, T3),
+    (T1, T2, T3, T4),
+}
+
+/// Shifts the raw key data to the left by the specified number of bits,
+/// filling the freed space with zeros.
+///
+/// Used to decode composite keys: after a key part was parsed with
+/// [`DictKey::from_raw_data`], the data is shifted by its [`DictKey::BITS`]
+/// so that the next part starts at the beginning of the buffer.
+pub fn shift_raw_key_data(raw_data: &mut [u8; 128], bits: u16) {
+    let bytes = (bits / 8) as usize;
+    let shift = bits % 8;
+
+    if bytes >= raw_data.len() {
+        *raw_data = [0; 128];
+        return;
+    }
+
+    if bytes > 0 {
+        raw_data.copy_within(bytes.., 0);
+        raw_data[128 - bytes..].fill(0);
+    }
+
+    if shift > 0 {
+        let len = 128 - bytes;
+        for i in 0..len {
+            let next = if i + 1 < len { raw_data[i + 1] } else { 0 };
+            raw_data[i] = (raw_data[i] << shift) | (next >> (8 - shift));
+        }
+    }
+}
+
 /// Dictionary insertion mode.
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum SetMode {
@@ -78,6 +146,14 @@ impl SetMode {
     }
 }
 
+/// A function used to compute the augmented value of a fork node
+/// from the augmented values of its children.
+///
+/// Arguments are slices of the left and right extras and a builder
+/// for the resulting extra.
+pub type AugDictFn =
+    fn(&mut CellSlice, &mut CellSlice, &mut CellBuilder, &mut dyn Finalizer) -> Result<(), Error>;
+
 /// Inserts the value associated with key in dictionary
 /// in accordance with the logic of the specified [`SetMode`].
 pub fn dict_insert(
@@ -87,6 +163,44 @@ pub fn dict_insert(
     value: &CellSlice,
     mode: SetMode,
     finalizer: &mut dyn Finalizer,
+) -> Result<Option<Cell>, Error> {
+    dict_insert_impl(root, key, key_bit_len, value, mode, None, finalizer)
+}
+
+/// Inserts the value associated with key in augmented dictionary
+/// in accordance with the logic of the specified [`SetMode`].
+///
+/// The `value` must contain the augmented value followed by the value itself.
+/// Augmented values of all forks along the modified path are recomputed
+/// using the `comparator`.
+pub fn aug_dict_insert(
+    root: &Option<Cell>,
+    key: &mut CellSlice,
+    key_bit_len: u16,
+    value: &CellSlice,
+    mode: SetMode,
+    comparator: AugDictFn,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Option<Cell>, Error> {
+    dict_insert_impl(
+        root,
+        key,
+        key_bit_len,
+        value,
+        mode,
+        Some(comparator),
+        finalizer,
+    )
+}
+
+fn dict_insert_impl(
+    root: &Option<Cell>,
+    key: &mut CellSlice,
+    key_bit_len: u16,
+    value: &CellSlice,
+    mode: SetMode,
+    comparator: Option<AugDictFn>,
+    finalizer: &mut dyn Finalizer,
 ) -> Result<Option<Cell>, Error> {
     // Creates a leaf node
     fn make_leaf(
@@ -108,6 +222,7 @@ pub fn dict_insert(
         lcp: &CellSlice,
         key: &mut CellSlice,
         value: &CellSlice,
+        comparator: Option<AugDictFn>,
         finalizer: &mut dyn Finalizer,
     ) -> Result<Cell, Error> {
         // Advance the key
@@ -133,23 +248,14 @@ pub fn dict_insert(
         // Create fork
         let mut builder = CellBuilder::new();
         ok!(write_label(lcp, prev_key_bit_len, &mut builder));
-        ok!(builder.store_reference(left));
-        ok!(builder.store_reference(right));
-        builder.build_ext(finalizer)
-    }
-
-    #[derive(Clone, Copy, Eq, PartialEq)]
-    enum Branch {
-        // Branch for a key part that starts with bit 0
-        Left = 0,
-        // Branch for a key part that starts with bit 1
-        Right = 1,
-    }
-
-    #[derive(Clone, Copy)]
-    struct Segment<'a> {
-        data: CellSlice<'a>,
-        next_branch: Branch,
+        make_fork(
+            builder,
+            left,
+            right,
+            key.remaining_bits(),
+            comparator,
+            finalizer,
+        )
     }
 
     if key.remaining_bits() != key_bit_len {
@@ -167,7 +273,7 @@ pub fn dict_insert(
 
     let mut stack = Vec::<Segment>::new();
 
-    let mut leaf = loop {
+    let leaf = loop {
         let mut remaining_data = data;
 
         // Read the next part of the key from the current data
@@ -191,7 +297,15 @@ pub fn dict_insert(
                 if !mode.can_add() {
                     return Ok(root.clone());
                 }
-                break ok!(split(&remaining_data, prefix, &lcp, key, value, finalizer));
+                break ok!(split(
+                    &remaining_data,
+                    prefix,
+                    &lcp,
+                    key,
+                    value,
+                    comparator,
+                    finalizer
+                ));
             }
             // The key contains the entire prefix, but there are still some bits left
             std::cmp::Ordering::Less => {
@@ -214,7 +328,11 @@ pub fn dict_insert(
                 let child = ok!(cell.get_reference_as_slice(next_branch as u8));
 
                 // Push an intermediate edge to the stack
-                stack.push(Segment { data, next_branch });
+                stack.push(Segment {
+                    data: ok!(label_part(&data, &remaining_data)),
+                    next_branch,
+                    key_bit_len: key.remaining_bits(),
+                });
                 data = child;
             }
             std::cmp::Ordering::Greater => {
@@ -225,44 +343,784 @@ pub fn dict_insert(
     };
 
     // Rebuild the tree starting from leaves
-    while let Some(last) = stack.pop() {
-        // Load the opposite branch
-        let (left, right) = match last.next_branch {
-            Branch::Left => match last.data.cell().reference_cloned(1) {
-                Some(cell) => (leaf, cell),
-                None => return Err(Error::CellUnderflow),
-            },
-            Branch::Right => match last.data.cell().reference_cloned(0) {
-                Some(cell) => (cell, leaf),
-                None => return Err(Error::CellUnderflow),
-            },
+    rebuild_dict_from_stack(stack, leaf, comparator, finalizer).map(Some)
+}
+
+/// Removes the value associated with key in dictionary.
+///
+/// Returns the new dictionary root and the removed value (if any).
+/// The fork above the removed leaf is collapsed, so its label is joined
+/// with the label of the remaining branch.
+pub fn dict_remove(
+    root: &Option<Cell>,
+    key: &mut CellSlice,
+    key_bit_len: u16,
+    finalizer: &mut dyn Finalizer,
+) -> Result<(Option<Cell>, Option<CellSliceParts>), Error> {
+    dict_remove_impl(root, key, key_bit_len, None, finalizer)
+}
+
+/// Removes the value associated with key in augmented dictionary.
+///
+/// Returns the new dictionary root and the removed value with its
+/// augmentation (if any). Augmented values of all forks along the
+/// modified path are recomputed using the `comparator`.
+pub fn aug_dict_remove(
+    root: &Option<Cell>,
+    key: &mut CellSlice,
+    key_bit_len: u16,
+    comparator: AugDictFn,
+    finalizer: &mut dyn Finalizer,
+) -> Result<(Option<Cell>, Option<CellSliceParts>), Error> {
+    dict_remove_impl(root, key, key_bit_len, Some(comparator), finalizer)
+}
+
+fn dict_remove_impl(
+    root: &Option<Cell>,
+    key: &mut CellSlice,
+    key_bit_len: u16,
+    comparator: Option<AugDictFn>,
+    finalizer: &mut dyn Finalizer,
+) -> Result<(Option<Cell>, Option<CellSliceParts>), Error> {
+    if key.remaining_bits() != key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+
+    let Some(root_cell) = root.as_ref() else {
+        return Ok((None, None));
+    };
+    let mut data = ok!(root_cell.as_slice());
+
+    let mut stack = Vec::<Segment>::new();
+
+    // Remaining key length at the last visited fork
+    let mut fork_key_bit_len = key_bit_len;
+
+    let value_range = loop {
+        let mut remaining_data = data;
+        let prev_key_bit_len = key.remaining_bits();
+
+        // Read the next part of the key from the current data
+        let prefix = ok!(read_label(&mut remaining_data, prev_key_bit_len));
+
+        // Match the prefix with the key
+        let lcp = key.longest_common_data_prefix(&prefix);
+        match lcp.remaining_bits().cmp(&key.remaining_bits()) {
+            // If all bits match, an existing value was found
+            std::cmp::Ordering::Equal => break remaining_data.range(),
+            // The key contains the entire prefix, but there are still some bits left
+            std::cmp::Ordering::Less if lcp.remaining_bits() == prefix.remaining_bits() => {
+                // Fail fast if there are not enough references in the fork
+                if data.cell().reference_count() != 2 {
+                    return Err(Error::CellUnderflow);
+                }
+
+                // Remove the LCP from the key
+                key.try_advance(lcp.remaining_bits(), 0);
+
+                // Load the next branch
+                let next_branch = match ok!(key.load_bit()) {
+                    false => Branch::Left,
+                    true => Branch::Right,
+                };
+
+                let child = ok!(data.cell().get_reference_as_slice(next_branch as u8));
+
+                // Push an intermediate edge to the stack
+                stack.push(Segment {
+                    data: ok!(label_part(&data, &remaining_data)),
+                    next_branch,
+                    key_bit_len: key.remaining_bits(),
+                });
+                fork_key_bit_len = prev_key_bit_len;
+                data = child;
+            }
+            // Key was not found
+            _ => return Ok((root.clone(), None)),
+        }
+    };
+
+    // Get an owned leaf cell to return the removed value
+    let leaf = match stack.last() {
+        Some(parent) => match parent
+            .data
+            .cell()
+            .reference_cloned(parent.next_branch as u8)
+        {
+            Some(cell) => cell,
+            None => return Err(Error::CellUnderflow),
+        },
+        None => root_cell.clone(),
+    };
+    let removed = Some((leaf, value_range));
+
+    // Remove the parent fork and join its label with the opposite branch
+    let Some(fork) = stack.pop() else {
+        return Ok((None, removed));
+    };
+
+    let mut fork_data = fork.data;
+    let fork_prefix = ok!(read_label(&mut fork_data, fork_key_bit_len));
+
+    let opposite = fork.next_branch.opposite();
+    let sibling = match fork.data.cell().reference(opposite as u8) {
+        Some(cell) => cell,
+        None => return Err(Error::CellUnderflow),
+    };
+
+    let mut merged_prefix = CellBuilder::new();
+    ok!(merged_prefix.store_slice_data(fork_prefix));
+    ok!(merged_prefix.store_bit(opposite == Branch::Right));
+
+    let leaf = ok!(replace_label(
+        sibling,
+        fork_key_bit_len - fork_prefix.remaining_bits() - 1,
+        &merged_prefix,
+        0,
+        fork_key_bit_len,
+        finalizer
+    ));
+
+    // Rebuild the tree starting from leaves
+    match rebuild_dict_from_stack(stack, leaf, comparator, finalizer) {
+        Ok(root) => Ok((Some(root), removed)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Splits the dictionary by the next bit after the specified key prefix.
+///
+/// All keys in the dictionary must start with the `prefix`. Returns roots
+/// of the dictionaries with keys starting with `prefix + 0` and `prefix + 1`.
+/// Subtrees are reused, so only the new root cells are created.
+pub fn dict_split_by_prefix(
+    root: &Option<Cell>,
+    key_bit_len: u16,
+    prefix: &CellSlice,
+    finalizer: &mut dyn Finalizer,
+) -> Result<(Option<Cell>, Option<Cell>), Error> {
+    let prefix_len = prefix.remaining_bits();
+    if prefix_len >= key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+
+    let Some(root) = root.as_ref() else {
+        return Ok((None, None));
+    };
+
+    let mut data = ok!(root.as_slice());
+    let label = ok!(read_label(&mut data, key_bit_len));
+    let label_len = label.remaining_bits();
+
+    // All keys must start with the prefix
+    let lcp_len = label.longest_common_data_prefix(prefix).remaining_bits();
+    if lcp_len < prefix_len {
+        return Err(Error::InvalidData);
+    }
+
+    if label_len > prefix_len {
+        // All keys are in the same half
+        return Ok(if ok!(label.get_bit(prefix_len)) {
+            (None, Some(root.clone()))
+        } else {
+            (Some(root.clone()), None)
+        });
+    }
+
+    // Root is a fork right after the prefix, so each half is its child
+    let mut split_child = |branch: Branch| -> Result<Option<Cell>, Error> {
+        let child = match root.as_ref().reference(branch as u8) {
+            Some(child) => child,
+            None => return Err(Error::CellUnderflow),
         };
 
-        let mut builder = CellBuilder::new();
-        ok!(builder.store_slice_data(last.data));
-        ok!(builder.store_reference(left));
-        ok!(builder.store_reference(right));
-        leaf = ok!(builder.build_ext(finalizer));
+        let mut child_prefix = CellBuilder::new();
+        ok!(child_prefix.store_slice_data(label));
+        ok!(child_prefix.store_bit(branch == Branch::Right));
+
+        let child_key_bit_len = key_bit_len - label_len - 1;
+        match replace_label(
+            child,
+            child_key_bit_len,
+            &child_prefix,
+            0,
+            key_bit_len,
+            finalizer,
+        ) {
+            Ok(child) => Ok(Some(child)),
+            Err(e) => Err(e),
+        }
+    };
+
+    let left = ok!(split_child(Branch::Left));
+    let right = ok!(split_child(Branch::Right));
+    Ok((left, right))
+}
+
+/// Merges two dictionaries, reusing their disjoint subtrees.
+///
+/// Values from the `right` dictionary replace values from the `left`
+/// for the same keys.
+pub fn dict_merge(
+    left: &Option<Cell>,
+    right: &Option<Cell>,
+    key_bit_len: u16,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Option<Cell>, Error> {
+    dict_merge_impl(left, right, key_bit_len, None, finalizer)
+}
+
+/// Merges two augmented dictionaries, reusing their disjoint subtrees.
+///
+/// Values from the `right` dictionary replace values from the `left`
+/// for the same keys. Augmented values of all new forks are computed
+/// using the `comparator`.
+pub fn aug_dict_merge(
+    left: &Option<Cell>,
+    right: &Option<Cell>,
+    key_bit_len: u16,
+    comparator: AugDictFn,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Option<Cell>, Error> {
+    dict_merge_impl(left, right, key_bit_len, Some(comparator), finalizer)
+}
+
+fn dict_merge_impl(
+    left: &Option<Cell>,
+    right: &Option<Cell>,
+    key_bit_len: u16,
+    comparator: Option<AugDictFn>,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Option<Cell>, Error> {
+    match (left, right) {
+        (Some(left), Some(right)) => {
+            match merge_nodes(left, right, key_bit_len, comparator, finalizer) {
+                Ok(root) => Ok(Some(root)),
+                Err(e) => Err(e),
+            }
+        }
+        (Some(root), None) | (None, Some(root)) => Ok(Some(root.clone())),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Merges two subtrees with the same remaining key length.
+fn merge_nodes(
+    left: &Cell,
+    right: &Cell,
+    key_bit_len: u16,
+    comparator: Option<AugDictFn>,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Cell, Error> {
+    fn load_child(node: &Cell, branch: Branch) -> Result<Cell, Error> {
+        match node.as_ref().reference_cloned(branch as u8) {
+            Some(child) => Ok(child),
+            None => Err(Error::CellUnderflow),
+        }
+    }
+
+    let mut left_data = ok!(left.as_slice());
+    let left_label = ok!(read_label(&mut left_data, key_bit_len));
+    let mut right_data = ok!(right.as_slice());
+    let right_label = ok!(read_label(&mut right_data, key_bit_len));
+
+    let lcp = left_label.longest_common_data_prefix(&right_label);
+    let lcp_len = lcp.remaining_bits();
+    let left_len = left_label.remaining_bits();
+    let right_len = right_label.remaining_bits();
+
+    let mut builder = CellBuilder::new();
+    ok!(write_label(&lcp, key_bit_len, &mut builder));
+
+    if lcp_len == key_bit_len {
+        // Both nodes are leaves with the same key
+        return Ok(right.clone());
+    }
+    let children_key_bit_len = key_bit_len - lcp_len - 1;
+
+    let (left_child, right_child) = if lcp_len == left_len && lcp_len == right_len {
+        // Both nodes are forks at the same position
+        let left_child = ok!(merge_nodes(
+            &ok!(load_child(left, Branch::Left)),
+            &ok!(load_child(right, Branch::Left)),
+            children_key_bit_len,
+            comparator,
+            finalizer
+        ));
+        let right_child = ok!(merge_nodes(
+            &ok!(load_child(left, Branch::Right)),
+            &ok!(load_child(right, Branch::Right)),
+            children_key_bit_len,
+            comparator,
+            finalizer
+        ));
+        (left_child, right_child)
+    } else if lcp_len < left_len && lcp_len < right_len {
+        // Labels diverge, so a new fork is created
+        let empty = CellBuilder::new();
+        let skip = lcp_len + 1;
+        let left_node = ok!(replace_label(
+            left.as_ref(),
+            key_bit_len,
+            &empty,
+            skip,
+            children_key_bit_len,
+            finalizer
+        ));
+        let right_node = ok!(replace_label(
+            right.as_ref(),
+            key_bit_len,
+            &empty,
+            skip,
+            children_key_bit_len,
+            finalizer
+        ));
+
+        if ok!(left_label.get_bit(lcp_len)) {
+            (right_node, left_node)
+        } else {
+            (left_node, right_node)
+        }
+    } else {
+        // One node is a fork and the other one goes into its subtree
+        let (fork, other, other_label, fork_is_left) = if lcp_len == left_len {
+            (left, right, right_label, true)
+        } else {
+            (right, left, left_label, false)
+        };
+
+        let branch = match ok!(other_label.get_bit(lcp_len)) {
+            false => Branch::Left,
+            true => Branch::Right,
+        };
+
+        let other = ok!(replace_label(
+            other.as_ref(),
+            key_bit_len,
+            &CellBuilder::new(),
+            lcp_len + 1,
+            children_key_bit_len,
+            finalizer
+        ));
+        let child = ok!(load_child(fork, branch));
+
+        // Keep the order of arguments to replace values from the right
+        let merged = ok!(if fork_is_left {
+            merge_nodes(&child, &other, children_key_bit_len, comparator, finalizer)
+        } else {
+            merge_nodes(&other, &child, children_key_bit_len, comparator, finalizer)
+        });
+        let sibling = ok!(load_child(fork, branch.opposite()));
+
+        match branch {
+            Branch::Left => (merged, sibling),
+            Branch::Right => (sibling, merged),
+        }
+    };
+
+    make_fork(
+        builder,
+        left_child,
+        right_child,
+        children_key_bit_len,
+        comparator,
+        finalizer,
+    )
+}
+
+/// Builds a dictionary from entries sorted by key in strictly ascending order.
+///
+/// The label tree is built bottom-up in a single pass, so each dictionary
+/// cell is finalized exactly once. Returns an error if some key has
+/// an invalid length or if keys are not sorted.
+pub fn build_dict_from_sorted_iter<I, K, V>(
+    entries: I,
+    key_bit_len: u16,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Option<Cell>, Error>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Store,
+    V: Store,
+{
+    enum PendingContent {
+        Leaf(CellBuilder),
+        Fork(Cell, Cell),
+    }
+
+    /// Subtree without a label, which is unknown until its parent is created.
+    struct PendingNode {
+        /// Some key from the subtree.
+        key: Cell,
+        /// Length of the common prefix of all keys in the subtree.
+        split: u16,
+        /// Length of the common prefix with the previous subtree on the stack.
+        fork_pos: u16,
+        content: PendingContent,
+    }
+
+    impl PendingNode {
+        fn materialize(
+            self,
+            start: u16,
+            key_bit_len: u16,
+            finalizer: &mut dyn Finalizer,
+        ) -> Result<Cell, Error> {
+            let mut label = ok!(self.key.as_slice()).get_prefix(self.split, 0);
+            if !label.try_advance(start, 0) {
+                return Err(Error::CellUnderflow);
+            }
+
+            let mut builder = CellBuilder::new();
+            ok!(write_label(&label, key_bit_len - start, &mut builder));
+            match self.content {
+                PendingContent::Leaf(value) => ok!(builder.store_builder(&value)),
+                PendingContent::Fork(left, right) => {
+                    ok!(builder.store_reference(left));
+                    ok!(builder.store_reference(right));
+                }
+            }
+            builder.build_ext(finalizer)
+        }
+
+        fn join(
+            left: Self,
+            right: Self,
+            key_bit_len: u16,
+            finalizer: &mut dyn Finalizer,
+        ) -> Result<Self, Error> {
+            let split = right.fork_pos;
+            let fork_pos = left.fork_pos;
+            let key = left.key.clone();
+
+            let left = ok!(left.materialize(split + 1, key_bit_len, finalizer));
+            let right = ok!(right.materialize(split + 1, key_bit_len, finalizer));
+            Ok(Self {
+                key,
+                split,
+                fork_pos,
+                content: PendingContent::Fork(left, right),
+            })
+        }
+    }
+
+    let mut stack = Vec::<PendingNode>::new();
+    let mut prev_key: Option<Cell> = None;
+
+    for (key, value) in entries {
+        let key = {
+            let mut builder = CellBuilder::new();
+            ok!(key.store_into(&mut builder, &mut Cell::default_finalizer()));
+            if builder.bit_len() != key_bit_len {
+                return Err(Error::CellUnderflow);
+            }
+            ok!(builder.build_ext(&mut Cell::default_finalizer()))
+        };
+
+        let mut value_builder = CellBuilder::new();
+        ok!(value.store_into(&mut value_builder, finalizer));
+
+        let fork_pos = match &prev_key {
+            Some(prev_key) => {
+                let key_data = ok!(key.as_slice());
+                let lcp_len = ok!(prev_key.as_slice())
+                    .longest_common_data_prefix(&key_data)
+                    .remaining_bits();
+
+                // Keys must be strictly ascending
+                if lcp_len >= key_bit_len || !ok!(key_data.get_bit(lcp_len)) {
+                    return Err(Error::InvalidData);
+                }
+
+                // Join all subtrees which diverge below the new fork
+                if let Some(mut node) = stack.pop() {
+                    while node.fork_pos > lcp_len {
+                        match stack.pop() {
+                            Some(left) => {
+                                node = ok!(PendingNode::join(left, node, key_bit_len, finalizer))
+                            }
+                            None => break,
+                        }
+                    }
+                    stack.push(node);
+                }
+                lcp_len
+            }
+            None => 0,
+        };
+
+        stack.push(PendingNode {
+            key: key.clone(),
+            split: key_bit_len,
+            fork_pos,
+            content: PendingContent::Leaf(value_builder),
+        });
+        prev_key = Some(key);
+    }
+
+    let mut node = match stack.pop() {
+        Some(node) => node,
+        None => return Ok(None),
+    };
+    while let Some(left) = stack.pop() {
+        node = ok!(PendingNode::join(left, node, key_bit_len, finalizer));
+    }
+
+    match node.materialize(0, key_bit_len, finalizer) {
+        Ok(root) => Ok(Some(root)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Checks the structure of the dictionary.
+///
+/// Verifies that each node is an ordinary cell with a label that fits
+/// into the remaining key length, and that each fork has exactly two
+/// children and no data or references after the label. Each value is passed to the
+/// `check_value` function.
+///
+/// Returns the key prefix of the first invalid node on failure.
+pub fn dict_validate(
+    root: &Option<Cell>,
+    key_bit_len: u16,
+    check_value: &mut dyn FnMut(CellSlice<'_>) -> Result<(), Error>,
+) -> Result<(), DictValidationError> {
+    dict_validate_impl(
+        root,
+        key_bit_len,
+        &mut |extra| {
+            if extra.is_data_empty() && extra.is_refs_empty() {
+                Ok(())
+            } else {
+                Err(Error::InvalidData)
+            }
+        },
+        check_value,
+    )
+}
+
+/// Checks the structure of the augmented dictionary.
+///
+/// Same as [`dict_validate`], but the data and references after
+/// the children of each fork are passed to the `check_extra` function.
+pub fn aug_dict_validate(
+    root: &Option<Cell>,
+    key_bit_len: u16,
+    check_extra: &mut dyn FnMut(CellSlice<'_>) -> Result<(), Error>,
+    check_value: &mut dyn FnMut(CellSlice<'_>) -> Result<(), Error>,
+) -> Result<(), DictValidationError> {
+    dict_validate_impl(root, key_bit_len, check_extra, check_value)
+}
+
+fn dict_validate_impl(
+    root: &Option<Cell>,
+    key_bit_len: u16,
+    check_extra: &mut dyn FnMut(CellSlice<'_>) -> Result<(), Error>,
+    check_value: &mut dyn FnMut(CellSlice<'_>) -> Result<(), Error>,
+) -> Result<(), DictValidationError> {
+    struct Node<'a> {
+        cell: &'a DynCell,
+        key_bit_len: u16,
+        key: CellBuilder,
     }
 
-    Ok(Some(leaf))
+    // Appends the node label to the key and returns the children of the fork,
+    // or `None` for a valid leaf
+    fn check_node<'a>(
+        node: &Node<'a>,
+        key: &mut CellBuilder,
+        check_extra: &mut dyn FnMut(CellSlice<'_>) -> Result<(), Error>,
+        check_value: &mut dyn FnMut(CellSlice<'_>) -> Result<(), Error>,
+    ) -> Result<Option<[&'a DynCell; 2]>, Error> {
+        let descriptor = node.cell.descriptor();
+        if descriptor.is_pruned_branch() {
+            return Err(Error::PrunedBranchAccess);
+        } else if descriptor.is_exotic() {
+            return Err(Error::InvalidCell);
+        }
+
+        let mut data = ok!(node.cell.as_slice());
+        let label = ok!(read_label(&mut data, node.key_bit_len));
+        if label.remaining_bits() > node.key_bit_len {
+            return Err(Error::InvalidData);
+        }
+        ok!(key.store_slice_data(label));
+
+        if label.remaining_bits() == node.key_bit_len {
+            ok!(check_value(data));
+            return Ok(None);
+        }
+
+        let (Ok(left), Ok(right)) = (data.get_reference(0), data.get_reference(1)) else {
+            return Err(Error::CellUnderflow);
+        };
+        if !data.try_advance(0, 2) {
+            return Err(Error::InvalidData);
+        }
+        ok!(check_extra(data));
+
+        Ok(Some([left, right]))
+    }
+
+    let Some(root) = root else {
+        return Ok(());
+    };
+
+    let mut stack = vec![Node {
+        cell: root.as_ref(),
+        key_bit_len,
+        key: CellBuilder::new(),
+    }];
+
+    // Shared subtrees are checked only once for each remaining key length
+    // (node checks don't depend on the key prefix)
+    let mut visited = ahash::HashSet::<(&HashBytes, u16)>::default();
+
+    while let Some(node) = stack.pop() {
+        if !visited.insert((node.cell.repr_hash(), node.key_bit_len)) {
+            continue;
+        }
+
+        let mut key = node.key.clone();
+        let children = match check_node(&node, &mut key, check_extra, check_value) {
+            Ok(Some(children)) => children,
+            Ok(None) => continue,
+            Err(error) => return Err(make_validation_error(key, error)),
+        };
+
+        // Push the right child first to check the left one earlier
+        let children_key_bit_len = node.key_bit_len - (key.bit_len() - node.key.bit_len()) - 1;
+        for (bit, cell) in [(true, children[1]), (false, children[0])] {
+            let mut key = key.clone();
+            if let Err(error) = key.store_bit(bit) {
+                return Err(make_validation_error(node.key, error));
+            }
+            stack.push(Node {
+                cell,
+                key_bit_len: children_key_bit_len,
+                key,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the slice contains exactly one value of type `T`.
+fn load_exact<T>(mut slice: CellSlice<'_>) -> Result<(), Error>
+where
+    for<'a> T: Load<'a>,
+{
+    ok!(T::load_from(&mut slice));
+    if slice.is_data_empty() && slice.is_refs_empty() {
+        Ok(())
+    } else {
+        Err(Error::InvalidData)
+    }
+}
+
+fn make_validation_error(key_prefix: CellBuilder, error: Error) -> DictValidationError {
+    let bit_len = key_prefix.bit_len();
+    let byte_len = (bit_len as usize + 7) / 8;
+    DictValidationError {
+        key_prefix: key_prefix.raw_data()[..byte_len].to_vec(),
+        key_prefix_bit_len: bit_len,
+        error,
+    }
 }
 
 /// Returns a `CellSlice` of the value corresponding to the key.
 pub fn dict_get<'a: 'b, 'b>(
     root: &'a Option<Cell>,
     key_bit_len: u16,
-    mut key: CellSlice<'b>,
+    key: CellSlice<'b>,
 ) -> Result<Option<CellSlice<'a>>, Error> {
     if key.remaining_bits() != key_bit_len {
         return Err(Error::CellUnderflow);
     }
 
-    let mut data = match root.as_ref() {
-        Some(data) => ok!(data.as_slice()),
-        None => return Ok(None),
+    match root.as_ref() {
+        Some(root) => dict_get_impl(root.as_ref(), key),
+        None => Ok(None),
+    }
+}
+
+/// Builds a Merkle proof which contains all cells on the lookup path of the key.
+///
+/// If the key is present, the proof contains its leaf with the value.
+/// Otherwise the proof contains the node with the label which diverges
+/// from the key, which proves the absence of the key.
+///
+/// Fails with [`Error::EmptyProof`] if the dictionary is empty.
+pub fn dict_prove_key<'a: 'b, 'b>(
+    root: &'a Option<Cell>,
+    key_bit_len: u16,
+    mut key: CellSlice<'b>,
+    finalizer: &mut dyn Finalizer,
+) -> Result<MerkleProof, Error> {
+    if key.remaining_bits() != key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+
+    let root = match root {
+        Some(root) => root.as_ref(),
+        None => return Err(Error::EmptyProof),
     };
 
+    let mut cells = ahash::HashSet::<&HashBytes>::default();
+    let mut data = ok!(root.as_slice());
+    loop {
+        cells.insert(data.cell().repr_hash());
+
+        // Read the key part written in the current edge
+        let prefix = ok!(read_label(&mut data, key.remaining_bits()));
+
+        // Continue only if the node is a fork on the path to the key
+        match key.strip_data_prefix(&prefix) {
+            Some(stripped_key) if !stripped_key.is_data_empty() && data.remaining_refs() >= 2 => {
+                key = stripped_key;
+            }
+            _ => break,
+        }
+
+        // Load next child based on the next bit
+        let child_index = ok!(key.load_bit()) as u8;
+        data = ok!(data.cell().get_reference_as_slice(child_index));
+    }
+
+    MerkleProof::create(root, cells).build_ext(finalizer)
+}
+
+/// Returns a `CellSlice` of the value corresponding to the key
+/// from the Merkle proof of the dictionary with the specified root hash.
+///
+/// Returns `None` if the proof shows that the key is absent.
+/// Fails if the proof is for another dictionary or if it doesn't
+/// contain the lookup path of the key.
+pub fn dict_verify_key_proof<'a: 'b, 'b>(
+    root_hash: &HashBytes,
+    proof: &'a MerkleProof,
+    key_bit_len: u16,
+    key: CellSlice<'b>,
+) -> Result<Option<CellSlice<'a>>, Error> {
+    if key.remaining_bits() != key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+
+    let root = proof.cell.as_ref();
+    if proof.hash != *root_hash || root.hash(0) != root_hash {
+        return Err(Error::InvalidData);
+    }
+
+    dict_get_impl(root, key)
+}
+
+fn dict_get_impl<'a: 'b, 'b>(
+    root: &'a DynCell,
+    mut key: CellSlice<'b>,
+) -> Result<Option<CellSlice<'a>>, Error> {
+    let mut data = ok!(root.as_slice());
+
     // Try to find the required leaf
     let is_key_empty = loop {
         // Read the key part written in the current edge
@@ -293,6 +1151,314 @@ pub fn dict_get<'a: 'b, 'b>(
     Ok(if is_key_empty { Some(data) } else { None })
 }
 
+/// Returns the root of a dictionary with all keys that start with the `prefix`.
+///
+/// If `remove_prefix` is set, the prefix is stripped from the keys
+/// and the resulting dictionary has keys of `key_bit_len - prefix_len` bits.
+/// Subtrees are reused, so at most a single new root cell is created.
+pub fn dict_get_subdict(
+    root: &Option<Cell>,
+    key_bit_len: u16,
+    prefix: &CellSlice<'_>,
+    remove_prefix: bool,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Option<Cell>, Error> {
+    let prefix_len = prefix.remaining_bits();
+    if prefix_len > key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+
+    let mut node = match root {
+        Some(root) => root.as_ref(),
+        None => return Ok(None),
+    };
+
+    // Find the topmost node with all keys starting with the prefix
+    let mut remaining = *prefix;
+    let mut node_key_bit_len = key_bit_len;
+    loop {
+        let mut data = ok!(node.as_slice());
+        let label = ok!(read_label(&mut data, node_key_bit_len));
+
+        let lcp_len = label
+            .longest_common_data_prefix(&remaining)
+            .remaining_bits();
+        if lcp_len == remaining.remaining_bits() {
+            break;
+        } else if lcp_len < label.remaining_bits() {
+            // Keys diverge from the prefix
+            return Ok(None);
+        }
+
+        // Label is a part of the prefix, so the node is a fork
+        let label_len = label.remaining_bits();
+        if !remaining.try_advance(label_len, 0) {
+            return Err(Error::CellUnderflow);
+        }
+        let branch = ok!(remaining.load_bit()) as u8;
+        node_key_bit_len -= label_len + 1;
+
+        node = match node.reference(branch) {
+            Some(child) => child,
+            None => return Err(Error::CellUnderflow),
+        };
+    }
+
+    let consumed_len = prefix_len - remaining.remaining_bits();
+    let mut new_prefix = CellBuilder::new();
+    let (skip, new_key_bit_len) = if remove_prefix {
+        (remaining.remaining_bits(), key_bit_len - prefix_len)
+    } else if consumed_len == 0 {
+        // All keys already start with the prefix
+        return Ok(root.clone());
+    } else {
+        ok!(new_prefix.store_slice_data(prefix.get_prefix(consumed_len, 0)));
+        (0, key_bit_len)
+    };
+
+    match replace_label(
+        node,
+        node_key_bit_len,
+        &new_prefix,
+        skip,
+        new_key_bit_len,
+        finalizer,
+    ) {
+        Ok(subdict) => Ok(Some(subdict)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Dictionary bound.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DictBound {
+    /// The lowest dictionary key.
+    Min,
+    /// The largest dictionary key.
+    Max,
+}
+
+impl DictBound {
+    fn update_direction(
+        self,
+        prefix: &CellSlice<'_>,
+        signed: bool,
+        direction: &mut Option<Branch>,
+    ) -> Branch {
+        match direction {
+            // Compute direction by the first part
+            None => {
+                let mut branch = *direction.insert(self.into_branch());
+                // Invert first bit for signed keys if starting from the empty part
+                if signed && prefix.is_data_empty() {
+                    branch = branch.opposite();
+                }
+                branch
+            }
+            // Use the same direction for all remaining parts
+            Some(direction) => *direction,
+        }
+    }
+
+    fn into_branch(self) -> Branch {
+        match self {
+            Self::Min => Branch::Left,
+            Self::Max => Branch::Right,
+        }
+    }
+}
+
+/// Finds the specified dictionary bound and returns a key and a value corresponding to the key.
+pub fn dict_find_bound(
+    root: &Option<Cell>,
+    key_bit_len: u16,
+    bound: DictBound,
+    signed: bool,
+) -> Result<Option<(CellBuilder, CellSlice<'_>)>, Error> {
+    match root {
+        Some(root) => match dict_find_bound_impl(
+            root.as_ref(),
+            key_bit_len,
+            CellBuilder::new(),
+            bound,
+            signed,
+        ) {
+            Ok(entry) => Ok(Some(entry)),
+            Err(e) => Err(e),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Descends to the leftmost or rightmost leaf of the subtree.
+fn dict_find_bound_impl(
+    mut data: &DynCell,
+    mut key_bit_len: u16,
+    mut key: CellBuilder,
+    bound: DictBound,
+    signed: bool,
+) -> Result<(CellBuilder, CellSlice<'_>), Error> {
+    let mut direction = None;
+    loop {
+        let mut remaining_data = ok!(data.as_slice());
+
+        // Read the key part written in the current edge
+        let prefix = ok!(read_label(&mut remaining_data, key_bit_len));
+        match key_bit_len.checked_sub(prefix.remaining_bits()) {
+            // Return the value if there are no remaining bits to read
+            Some(0) => {
+                ok!(key.store_slice_data(prefix));
+                return Ok((key, remaining_data));
+            }
+            // Well-formed `Dict` should have the required number of bits
+            // for each value
+            None => return Err(Error::CellUnderflow),
+            Some(remaining) => key_bit_len = remaining,
+        }
+
+        // Select the next branch
+        let next_branch = bound.update_direction(&prefix, signed, &mut direction);
+
+        ok!(key.store_slice_data(prefix));
+        ok!(key.store_bit(next_branch == Branch::Right));
+        key_bit_len -= 1;
+
+        data = match data.reference(next_branch as u8) {
+            Some(child) => child,
+            None => return Err(Error::CellUnderflow),
+        };
+    }
+}
+
+/// Finds the closest key in the specified direction and returns
+/// a key and a value corresponding to the key.
+///
+/// - `towards: DictBound::Max` searches for the next key (greater than the specified one).
+/// - `towards: DictBound::Min` searches for the previous key (less than the specified one).
+///
+/// If `inclusive` is `true`, the specified key itself is also considered.
+/// If `signed` is `true`, keys are treated as two's complement integers
+/// (so the first bit has the inverted order).
+pub fn dict_find<'a>(
+    root: &'a Option<Cell>,
+    key_bit_len: u16,
+    mut key: CellSlice<'_>,
+    towards: DictBound,
+    inclusive: bool,
+    signed: bool,
+) -> Result<Option<(CellBuilder, CellSlice<'a>)>, Error> {
+    // Builds a key from the prefix of the original key and one more bit
+    fn make_key(original_key: &CellSlice<'_>, prefix_len: u16) -> Result<CellBuilder, Error> {
+        let mut builder = CellBuilder::new();
+        ok!(builder.store_slice_data(original_key.get_prefix(prefix_len, 0)));
+        Ok(builder)
+    }
+
+    if key.remaining_bits() != key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+
+    let mut data = match root {
+        Some(root) => root.as_ref(),
+        None => return Ok(None),
+    };
+
+    let original_key = key;
+    let mut remaining_bit_len = key_bit_len;
+
+    // The closest subtree which lies entirely in the search direction
+    // (child cell, bit position of the branch, branch)
+    let mut fallback: Option<(&'a DynCell, u16, Branch)> = None;
+
+    loop {
+        let mut remaining_data = ok!(data.as_slice());
+
+        // Read the key part written in the current edge
+        let prefix = ok!(read_label(&mut remaining_data, remaining_bit_len));
+
+        let lcp_len = key.longest_common_data_prefix(&prefix).remaining_bits();
+        if lcp_len < prefix.remaining_bits() {
+            // The edge diverges from the key, so the whole subtree lies
+            // either before or after the key
+            let bit_position = key_bit_len - remaining_bit_len + lcp_len;
+            let key_bit = ok!(key.get_bit(lcp_len));
+            let subtree_is_greater = !key_bit ^ (signed && bit_position == 0);
+
+            if subtree_is_greater == (towards == DictBound::Max) {
+                let prefix_len = key_bit_len - remaining_bit_len;
+                let builder = ok!(make_key(&original_key, prefix_len));
+                let bound = match towards {
+                    DictBound::Min => DictBound::Max,
+                    DictBound::Max => DictBound::Min,
+                };
+                return match dict_find_bound_impl(
+                    data,
+                    remaining_bit_len,
+                    builder,
+                    bound,
+                    signed && prefix_len == 0,
+                ) {
+                    Ok(entry) => Ok(Some(entry)),
+                    Err(e) => Err(e),
+                };
+            }
+            break;
+        }
+
+        // Remove the prefix from the key
+        key.try_advance(lcp_len, 0);
+        remaining_bit_len -= lcp_len;
+
+        if remaining_bit_len == 0 {
+            // The exact key was found
+            if inclusive {
+                let builder = ok!(make_key(&original_key, key_bit_len));
+                return Ok(Some((builder, remaining_data)));
+            }
+            break;
+        }
+
+        // Load the next branch
+        let bit_position = key_bit_len - remaining_bit_len;
+        let next_branch = match ok!(key.load_bit()) {
+            false => Branch::Left,
+            true => Branch::Right,
+        };
+
+        // Remember the opposite branch if it lies in the search direction
+        let opposite = next_branch.opposite();
+        let opposite_is_greater = (opposite == Branch::Right) ^ (signed && bit_position == 0);
+        if opposite_is_greater == (towards == DictBound::Max) {
+            if let Some(child) = data.reference(opposite as u8) {
+                fallback = Some((child, bit_position, opposite));
+            }
+        }
+
+        data = match data.reference(next_branch as u8) {
+            Some(child) => child,
+            None => return Err(Error::CellUnderflow),
+        };
+        remaining_bit_len -= 1;
+    }
+
+    // Find the closest entry in the remembered subtree
+    let Some((child, bit_position, branch)) = fallback else {
+        return Ok(None);
+    };
+
+    let mut builder = ok!(make_key(&original_key, bit_position));
+    ok!(builder.store_bit(branch == Branch::Right));
+
+    let bound = match towards {
+        DictBound::Min => DictBound::Max,
+        DictBound::Max => DictBound::Min,
+    };
+    match dict_find_bound_impl(child, key_bit_len - bit_position - 1, builder, bound, false) {
+        Ok(entry) => Ok(Some(entry)),
+        Err(e) => Err(e),
+    }
+}
+
 /// Loads a non-empty dictionary from the root cell.
 pub fn dict_load_from_root(
     slice: &mut CellSlice<'_>,
@@ -318,6 +1484,152 @@ pub fn dict_load_from_root(
     builder.build_ext(finalizer)
 }
 
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Branch {
+    // Branch for a key part that starts with bit 0
+    Left = 0,
+    // Branch for a key part that starts with bit 1
+    Right = 1,
+}
+
+impl Branch {
+    fn opposite(self) -> Self {
+        match self {
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Segment<'a> {
+    /// Fork label (without children and augmentation).
+    data: CellSlice<'a>,
+    next_branch: Branch,
+    /// Remaining key length of the fork children.
+    key_bit_len: u16,
+}
+
+/// Returns the label part of the node slice.
+fn label_part<'a>(data: &CellSlice<'a>, remaining: &CellSlice<'a>) -> Result<CellSlice<'a>, Error> {
+    match data
+        .remaining_bits()
+        .checked_sub(remaining.remaining_bits())
+    {
+        Some(bits) => Ok(data.get_prefix(bits, 0)),
+        None => Err(Error::CellUnderflow),
+    }
+}
+
+/// Creates a fork node from the builder with a label.
+///
+/// Computes the augmented value of the fork if the `comparator` is specified.
+fn make_fork(
+    mut builder: CellBuilder,
+    left: Cell,
+    right: Cell,
+    children_key_bit_len: u16,
+    comparator: Option<AugDictFn>,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Cell, Error> {
+    ok!(builder.store_reference(left.clone()));
+    ok!(builder.store_reference(right.clone()));
+
+    if let Some(comparator) = comparator {
+        let mut left_extra = ok!(read_aug_extra(left.as_ref(), children_key_bit_len));
+        let mut right_extra = ok!(read_aug_extra(right.as_ref(), children_key_bit_len));
+        ok!(comparator(
+            &mut left_extra,
+            &mut right_extra,
+            &mut builder,
+            finalizer
+        ));
+    }
+
+    builder.build_ext(finalizer)
+}
+
+/// Returns a slice of the augmented value of the node.
+fn read_aug_extra(node: &DynCell, key_bit_len: u16) -> Result<CellSlice<'_>, Error> {
+    let mut slice = ok!(node.as_slice());
+    let label = ok!(read_label(&mut slice, key_bit_len));
+    if label.remaining_bits() != key_bit_len && !slice.try_advance(0, 2) {
+        return Err(Error::CellUnderflow);
+    }
+    Ok(slice)
+}
+
+/// Rebuilds the node with a new label, keeping its content.
+///
+/// The new label consists of the `prefix` followed by the old
+/// label without the first `skip` bits.
+fn replace_label(
+    node: &DynCell,
+    key_bit_len: u16,
+    prefix: &CellBuilder,
+    skip: u16,
+    new_key_bit_len: u16,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Cell, Error> {
+    let mut data = ok!(node.as_slice());
+    let mut label = ok!(read_label(&mut data, key_bit_len));
+    if !label.try_advance(skip, 0) {
+        return Err(Error::CellUnderflow);
+    }
+
+    let new_label = {
+        let mut builder = CellBuilder::new();
+        ok!(builder.store_builder(prefix));
+        ok!(builder.store_slice_data(label));
+        ok!(builder.build_ext(&mut Cell::default_finalizer()))
+    };
+
+    let mut builder = CellBuilder::new();
+    ok!(write_label(
+        &ok!(new_label.as_slice()),
+        new_key_bit_len,
+        &mut builder
+    ));
+    ok!(builder.store_slice(data));
+    builder.build_ext(finalizer)
+}
+
+/// Rebuilds the path from the modified node to the root,
+/// replacing the visited branch of each fork with the new child.
+fn rebuild_dict_from_stack(
+    mut segments: Vec<Segment<'_>>,
+    mut leaf: Cell,
+    comparator: Option<AugDictFn>,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Cell, Error> {
+    while let Some(last) = segments.pop() {
+        // Load the opposite branch
+        let (left, right) = match last.next_branch {
+            Branch::Left => match last.data.cell().reference_cloned(1) {
+                Some(cell) => (leaf, cell),
+                None => return Err(Error::CellUnderflow),
+            },
+            Branch::Right => match last.data.cell().reference_cloned(0) {
+                Some(cell) => (cell, leaf),
+                None => return Err(Error::CellUnderflow),
+            },
+        };
+
+        let mut builder = CellBuilder::new();
+        ok!(builder.store_slice_data(last.data));
+        leaf = ok!(make_fork(
+            builder,
+            left,
+            right,
+            last.key_bit_len,
+            comparator,
+            finalizer
+        ));
+    }
+
+    Ok(leaf)
+}
+
 fn write_label(key: &CellSlice, key_bit_len: u16, label: &mut CellBuilder) -> Result<(), Error> {
     if key_bit_len == 0 || key.is_data_empty() {
         return write_hml_empty(label);
@@ -0,0 +1,675 @@
+use crate::cell::*;
+use crate::error::Error;
+
+use super::{
+    label_part, make_fork, read_label, rebuild_dict_from_stack, replace_label, write_label, Branch,
+    Segment, SetMode,
+};
+
+/// Prefix dictionary with variable length keys (where `N` is a max number of bits in each key).
+///
+/// Keys form a prefix code, so no key can be a prefix of another key.
+///
+/// # TLB scheme
+///
+/// ```text
+/// phm_edge#_ {n:#} {X:Type} {l:#} {m:#} label:(HmLabel ~l n)
+///            {n = (~m) + l} node:(PfxHashmapNode m X) = PfxHashmap n X;
+///
+/// phmn_leaf$0 {n:#} {X:Type} value:X = PfxHashmapNode n X;
+/// phmn_fork$1 {n:#} {X:Type} left:^(PfxHashmap n X)
+///             right:^(PfxHashmap n X) = PfxHashmapNode (n + 1) X;
+///
+/// phme_empty$0 {n:#} {X:Type} = PfxHashmapE n X;
+/// phme_root$1 {n:#} {X:Type} root:^(PfxHashmap n X) = PfxHashmapE n X;
+/// ```
+pub struct PfxDict<const N: u16>(pub(crate) Option<Cell>);
+
+impl<'a, const N: u16> Load<'a> for PfxDict<N> {
+    #[inline]
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match <_>::load_from(slice) {
+            Ok(dict) => Ok(Self(dict)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<const N: u16> Store for PfxDict<N> {
+    #[inline]
+    fn store_into(
+        &self,
+        builder: &mut CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        self.0.store_into(builder, finalizer)
+    }
+}
+
+impl<const N: u16> Default for PfxDict<N> {
+    #[inline]
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<const N: u16> Clone for PfxDict<N> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<const N: u16> Eq for PfxDict<N> {}
+impl<const N: u16> PartialEq for PfxDict<N> {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(this), Some(other)) => this.as_ref() == other.as_ref(),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<const N: u16> From<Option<Cell>> for PfxDict<N> {
+    #[inline]
+    fn from(value: Option<Cell>) -> Self {
+        Self(value)
+    }
+}
+
+impl<const N: u16> std::fmt::Debug for PfxDict<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PfxDict")
+            .field("max_key_bit_len", &N)
+            .field("root", &self.0)
+            .finish()
+    }
+}
+
+impl<const N: u16> PfxDict<N> {
+    /// Creates an empty dictionary.
+    pub const fn new() -> Self {
+        Self(None)
+    }
+
+    /// Returns `true` if the dictionary contains no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns the underlying root cell of the dictionary.
+    #[inline]
+    pub const fn root(&self) -> &Option<Cell> {
+        &self.0
+    }
+
+    /// Returns a `CellSlice` of the value corresponding to the key.
+    pub fn get<'a: 'b, 'b>(&'a self, key: CellSlice<'b>) -> Result<Option<CellSlice<'a>>, Error> {
+        match ok!(pfx_dict_lookup(&self.0, N, key, true)) {
+            Some((_, value)) => Ok(Some(value)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns `true` if the dictionary contains a value for the specified key.
+    pub fn contains_key(&self, key: CellSlice<'_>) -> Result<bool, Error> {
+        Ok(ok!(pfx_dict_lookup(&self.0, N, key, true)).is_some())
+    }
+
+    /// Finds the key which is a prefix of the specified bitstring.
+    /// Returns the length of the found key and its value.
+    ///
+    /// Since no key is a prefix of another, there is at most one such key.
+    pub fn get_prefix<'a: 'b, 'b>(
+        &'a self,
+        key: CellSlice<'b>,
+    ) -> Result<Option<(u16, CellSlice<'a>)>, Error> {
+        pfx_dict_lookup(&self.0, N, key, false)
+    }
+
+    /// Sets the value associated with the key in the dictionary.
+    ///
+    /// Returns `false` if the key was not inserted because it
+    /// is a prefix of an existing key or vice versa.
+    pub fn set_ext(
+        &mut self,
+        key: CellSlice<'_>,
+        value: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<bool, Error> {
+        self.insert_impl(key, value, SetMode::Set, finalizer)
+    }
+
+    /// Sets the value associated with the key in the dictionary
+    /// only if the key was already present in it.
+    ///
+    /// Returns `false` if the key was not found.
+    pub fn replace_ext(
+        &mut self,
+        key: CellSlice<'_>,
+        value: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<bool, Error> {
+        self.insert_impl(key, value, SetMode::Replace, finalizer)
+    }
+
+    /// Sets the value associated with key in dictionary,
+    /// but only if it is not already present.
+    ///
+    /// Returns `false` if the key was not inserted because it is
+    /// already present, or it is a prefix of an existing key or vice versa.
+    pub fn add_ext(
+        &mut self,
+        key: CellSlice<'_>,
+        value: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<bool, Error> {
+        self.insert_impl(key, value, SetMode::Add, finalizer)
+    }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value as cell slice parts.
+    pub fn remove_ext(
+        &mut self,
+        mut key: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Option<CellSliceParts>, Error> {
+        let (root, removed) = ok!(pfx_dict_remove(&self.0, &mut key, N, finalizer));
+        self.0 = root;
+        Ok(removed)
+    }
+
+    /// Sets the value associated with the key in the dictionary.
+    ///
+    /// Returns `false` if the key was not inserted because it
+    /// is a prefix of an existing key or vice versa.
+    ///
+    /// Use [`set_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`set_ext`]: PfxDict::set_ext
+    pub fn set(&mut self, key: CellSlice<'_>, value: CellSlice<'_>) -> Result<bool, Error> {
+        self.set_ext(key, value, &mut Cell::default_finalizer())
+    }
+
+    /// Sets the value associated with the key in the dictionary
+    /// only if the key was already present in it.
+    ///
+    /// Returns `false` if the key was not found.
+    ///
+    /// Use [`replace_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`replace_ext`]: PfxDict::replace_ext
+    pub fn replace(&mut self, key: CellSlice<'_>, value: CellSlice<'_>) -> Result<bool, Error> {
+        self.replace_ext(key, value, &mut Cell::default_finalizer())
+    }
+
+    /// Sets the value associated with key in dictionary,
+    /// but only if it is not already present.
+    ///
+    /// Returns `false` if the key was not inserted because it is
+    /// already present, or it is a prefix of an existing key or vice versa.
+    ///
+    /// Use [`add_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`add_ext`]: PfxDict::add_ext
+    pub fn add(&mut self, key: CellSlice<'_>, value: CellSlice<'_>) -> Result<bool, Error> {
+        self.add_ext(key, value, &mut Cell::default_finalizer())
+    }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value as cell slice parts.
+    ///
+    /// Use [`remove_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`remove_ext`]: PfxDict::remove_ext
+    pub fn remove(&mut self, key: CellSlice<'_>) -> Result<Option<CellSliceParts>, Error> {
+        self.remove_ext(key, &mut Cell::default_finalizer())
+    }
+
+    fn insert_impl(
+        &mut self,
+        mut key: CellSlice<'_>,
+        value: CellSlice<'_>,
+        mode: SetMode,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<bool, Error> {
+        let (root, inserted) = ok!(pfx_dict_insert(
+            &self.0, &mut key, N, &value, mode, finalizer
+        ));
+        self.0 = root;
+        Ok(inserted)
+    }
+}
+
+/// Returns the value corresponding to the key in the prefix dictionary.
+///
+/// If `exact` is `false`, finds the key which is a prefix of the specified
+/// bitstring instead. Returns the length of the found key and its value.
+pub fn pfx_dict_lookup<'a: 'b, 'b>(
+    root: &'a Option<Cell>,
+    key_bit_len: u16,
+    mut key: CellSlice<'b>,
+    exact: bool,
+) -> Result<Option<(u16, CellSlice<'a>)>, Error> {
+    if exact && key.remaining_bits() > key_bit_len {
+        return Ok(None);
+    }
+
+    let mut data = match root.as_ref() {
+        Some(data) => ok!(data.as_slice()),
+        None => return Ok(None),
+    };
+
+    let mut remaining_bit_len = key_bit_len;
+    let mut prefix_len = 0;
+    loop {
+        // Read the key part written in the current edge
+        let prefix = ok!(read_label(&mut data, remaining_bit_len));
+
+        // Remove this prefix from the key
+        key = match key.strip_data_prefix(&prefix) {
+            Some(stripped_key) => stripped_key,
+            None => return Ok(None),
+        };
+        remaining_bit_len -= prefix.remaining_bits();
+        prefix_len += prefix.remaining_bits();
+
+        if !ok!(data.load_bit()) {
+            // Reached leaf
+            return Ok(if !exact || key.is_data_empty() {
+                Some((prefix_len, data))
+            } else {
+                None
+            });
+        } else if key.is_data_empty() {
+            // Key is a prefix of some other keys
+            return Ok(None);
+        } else if remaining_bit_len == 0 {
+            return Err(Error::InvalidData);
+        }
+
+        // Load next child based on the next bit
+        let child_index = ok!(key.load_bit()) as u8;
+        data = ok!(data.cell().get_reference_as_slice(child_index));
+        remaining_bit_len -= 1;
+        prefix_len += 1;
+    }
+}
+
+/// Inserts the value associated with key in prefix dictionary
+/// in accordance with the logic of the specified [`SetMode`].
+///
+/// Returns the new dictionary root and whether the value was inserted.
+/// The value is not inserted if the key is a prefix of an existing key
+/// or vice versa.
+pub fn pfx_dict_insert(
+    root: &Option<Cell>,
+    key: &mut CellSlice,
+    key_bit_len: u16,
+    value: &CellSlice,
+    mode: SetMode,
+    finalizer: &mut dyn Finalizer,
+) -> Result<(Option<Cell>, bool), Error> {
+    // Creates a leaf node
+    fn make_leaf(
+        key: &CellSlice,
+        key_bit_len: u16,
+        value: &CellSlice,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Cell, Error> {
+        let mut builder = CellBuilder::new();
+        ok!(write_label(key, key_bit_len, &mut builder));
+        ok!(builder.store_bit_zero());
+        ok!(builder.store_slice(value));
+        builder.build_ext(finalizer)
+    }
+
+    if key.remaining_bits() > key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+
+    let mut data = match root.as_ref() {
+        Some(data) => ok!(data.as_slice()),
+        None if mode.can_add() => {
+            let data = ok!(make_leaf(key, key_bit_len, value, finalizer));
+            return Ok((Some(data), true));
+        }
+        None => return Ok((None, false)),
+    };
+
+    let mut stack = Vec::<Segment>::new();
+    let mut remaining_bit_len = key_bit_len;
+
+    let leaf = loop {
+        let mut remaining_data = data;
+
+        // Read the next part of the key from the current data
+        let prefix = ok!(read_label(&mut remaining_data, remaining_bit_len));
+
+        // Match the prefix with the key
+        let lcp = key.longest_common_data_prefix(&prefix);
+        let lcp_len = lcp.remaining_bits();
+
+        if lcp_len < prefix.remaining_bits() {
+            // The key is a prefix of existing keys
+            if lcp_len == key.remaining_bits() || !mode.can_add() {
+                return Ok((root.clone(), false));
+            }
+
+            // Split the edge
+            let child_bit_len = remaining_bit_len - lcp_len - 1;
+            let old = ok!(replace_label(
+                data.cell(),
+                remaining_bit_len,
+                &CellBuilder::new(),
+                lcp_len + 1,
+                child_bit_len,
+                finalizer
+            ));
+
+            key.try_advance(lcp_len, 0);
+            let new_to_right = ok!(key.load_bit());
+            let new = ok!(make_leaf(key, child_bit_len, value, finalizer));
+
+            let (left, right) = if new_to_right { (old, new) } else { (new, old) };
+
+            let mut builder = CellBuilder::new();
+            ok!(write_label(&lcp, remaining_bit_len, &mut builder));
+            ok!(builder.store_bit_one());
+            break ok!(make_fork(
+                builder,
+                left,
+                right,
+                child_bit_len,
+                None,
+                finalizer
+            ));
+        }
+
+        // The key contains the entire prefix
+        key.try_advance(lcp_len, 0);
+        let node_bit_len = remaining_bit_len;
+        remaining_bit_len -= lcp_len;
+
+        if !ok!(remaining_data.load_bit()) {
+            // Existing key is a prefix of the key
+            if !key.is_data_empty() || !mode.can_replace() {
+                return Ok((root.clone(), false));
+            }
+
+            // Replace the existing value
+            break ok!(make_leaf(&prefix, node_bit_len, value, finalizer));
+        }
+
+        // The key is a prefix of existing keys
+        if key.is_data_empty() {
+            return Ok((root.clone(), false));
+        }
+
+        // Fail fast if there are not enough references in the fork
+        let cell = data.cell();
+        if remaining_bit_len == 0 || cell.reference_count() != 2 {
+            return Err(Error::CellUnderflow);
+        }
+
+        // Load the next branch
+        let next_branch = match ok!(key.load_bit()) {
+            false => Branch::Left,
+            true => Branch::Right,
+        };
+        remaining_bit_len -= 1;
+
+        let child = ok!(cell.get_reference_as_slice(next_branch as u8));
+
+        // Push an intermediate edge (with the fork tag) to the stack
+        stack.push(Segment {
+            data: ok!(label_part(&data, &remaining_data)),
+            next_branch,
+            key_bit_len: remaining_bit_len,
+        });
+        data = child;
+    };
+
+    // Rebuild the tree starting from leaves
+    match rebuild_dict_from_stack(stack, leaf, None, finalizer) {
+        Ok(root) => Ok((Some(root), true)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Removes the value associated with key in prefix dictionary.
+///
+/// Returns the new dictionary root and the removed value (if any).
+pub fn pfx_dict_remove(
+    root: &Option<Cell>,
+    key: &mut CellSlice,
+    key_bit_len: u16,
+    finalizer: &mut dyn Finalizer,
+) -> Result<(Option<Cell>, Option<CellSliceParts>), Error> {
+    if key.remaining_bits() > key_bit_len {
+        return Ok((root.clone(), None));
+    }
+
+    let Some(root_cell) = root.as_ref() else {
+        return Ok((None, None));
+    };
+    let mut data = ok!(root_cell.as_slice());
+
+    let mut stack = Vec::<Segment>::new();
+    let mut remaining_bit_len = key_bit_len;
+
+    // Remaining key length at the last visited fork
+    let mut fork_key_bit_len = key_bit_len;
+
+    let value_range = loop {
+        let mut remaining_data = data;
+
+        // Read the next part of the key from the current data
+        let prefix = ok!(read_label(&mut remaining_data, remaining_bit_len));
+
+        // Remove this prefix from the key
+        let lcp = key.longest_common_data_prefix(&prefix);
+        if lcp.remaining_bits() != prefix.remaining_bits() {
+            return Ok((root.clone(), None));
+        }
+        key.try_advance(lcp.remaining_bits(), 0);
+
+        let is_fork = ok!(remaining_data.load_bit());
+        match (is_fork, key.is_data_empty()) {
+            // Found the leaf for the key
+            (false, true) => break remaining_data.range(),
+            // Descend into the fork
+            (true, false) => {}
+            // Key was not found
+            _ => return Ok((root.clone(), None)),
+        }
+
+        // Fail fast if there are not enough references in the fork
+        let node_bit_len = remaining_bit_len;
+        remaining_bit_len -= prefix.remaining_bits();
+        if remaining_bit_len == 0 || data.cell().reference_count() != 2 {
+            return Err(Error::CellUnderflow);
+        }
+
+        // Load the next branch
+        let next_branch = match ok!(key.load_bit()) {
+            false => Branch::Left,
+            true => Branch::Right,
+        };
+        remaining_bit_len -= 1;
+
+        let child = ok!(data.cell().get_reference_as_slice(next_branch as u8));
+
+        // Push an intermediate edge (with the fork tag) to the stack
+        stack.push(Segment {
+            data: ok!(label_part(&data, &remaining_data)),
+            next_branch,
+            key_bit_len: remaining_bit_len,
+        });
+        fork_key_bit_len = node_bit_len;
+        data = child;
+    };
+
+    // Get an owned leaf cell to return the removed value
+    let leaf = match stack.last() {
+        Some(parent) => match parent
+            .data
+            .cell()
+            .reference_cloned(parent.next_branch as u8)
+        {
+            Some(cell) => cell,
+            None => return Err(Error::CellUnderflow),
+        },
+        None => root_cell.clone(),
+    };
+    let removed = Some((leaf, value_range));
+
+    // Remove the parent fork and join its label with the opposite branch
+    let Some(fork) = stack.pop() else {
+        return Ok((None, removed));
+    };
+
+    let mut fork_data = fork.data;
+    let fork_prefix = ok!(read_label(&mut fork_data, fork_key_bit_len));
+
+    let opposite = fork.next_branch.opposite();
+    let sibling = match fork.data.cell().reference(opposite as u8) {
+        Some(cell) => cell,
+        None => return Err(Error::CellUnderflow),
+    };
+
+    let mut merged_prefix = CellBuilder::new();
+    ok!(merged_prefix.store_slice_data(fork_prefix));
+    ok!(merged_prefix.store_bit(opposite == Branch::Right));
+
+    let node = ok!(replace_label(
+        sibling,
+        fork.key_bit_len,
+        &merged_prefix,
+        0,
+        fork_key_bit_len,
+        finalizer
+    ));
+
+    match rebuild_dict_from_stack(stack, node, None, finalizer) {
+        Ok(root) => Ok((Some(root), removed)),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn make_key(bits: &str) -> anyhow::Result<Cell> {
+        let mut builder = CellBuilder::new();
+        for bit in bits.chars() {
+            builder.store_bit(bit == '1')?;
+        }
+        Ok(builder.build()?)
+    }
+
+    #[test]
+    fn pfx_dict_set_get_remove() -> anyhow::Result<()> {
+        let mut dict = PfxDict::<16>::new();
+        let mut expected = BTreeMap::new();
+
+        let keys = [
+            "0",
+            "10",
+            "110",
+            "1110",
+            "11110000",
+            "11110001",
+            "1111001",
+            "111101",
+            "1111111111111111",
+        ];
+        for (i, key) in keys.into_iter().enumerate() {
+            let key_cell = make_key(key)?;
+            let value = CellBuilder::build_from(i as u32)?;
+            assert!(dict.set(key_cell.as_slice()?, value.as_slice()?)?);
+            expected.insert(key, i as u32);
+        }
+
+        // Prefix conflicts
+        for key in ["", "1", "11", "1111", "00", "101", "1111000", "11110000111"] {
+            let key_cell = make_key(key)?;
+            let value = CellBuilder::build_from(123u32)?;
+            let before = dict.clone();
+            assert!(!dict.set(key_cell.as_slice()?, value.as_slice()?)?, "{key}");
+            assert_eq!(dict, before);
+        }
+
+        // Too long key
+        let long_key = make_key("11111111111111111")?;
+        let value = CellBuilder::build_from(0u32)?;
+        assert!(dict.set(long_key.as_slice()?, value.as_slice()?).is_err());
+
+        // Replace and add
+        let key_cell = make_key("110")?;
+        let value = CellBuilder::build_from(100u32)?;
+        assert!(!dict.add(key_cell.as_slice()?, value.as_slice()?)?);
+        assert!(dict.replace(key_cell.as_slice()?, value.as_slice()?)?);
+        expected.insert("110", 100);
+
+        let key_cell = make_key("1111010")?;
+        assert!(!dict.replace(key_cell.as_slice()?, value.as_slice()?)?);
+
+        for (key, value) in &expected {
+            let key_cell = make_key(key)?;
+            let found = dict.get(key_cell.as_slice()?)?;
+            assert_eq!(found.map(|mut s| s.load_u32()).transpose()?, Some(*value));
+        }
+        for key in ["", "1", "111", "11110", "01", "1111000011"] {
+            let key_cell = make_key(key)?;
+            assert!(!dict.contains_key(key_cell.as_slice()?)?);
+        }
+
+        // Prefix lookup
+        for (input, key) in [
+            ("0111", Some("0")),
+            ("1101010101", Some("110")),
+            ("111100011", Some("11110001")),
+            ("11111111111111111111", Some("1111111111111111")),
+            ("1111", None),
+            ("11111110", None),
+        ] {
+            let input_cell = make_key(input)?;
+            let found = dict.get_prefix(input_cell.as_slice()?)?;
+            match key {
+                Some(key) => {
+                    let (len, mut value) = found.unwrap();
+                    assert_eq!(len as usize, key.len());
+                    assert_eq!(value.load_u32()?, expected[key]);
+                }
+                None => assert!(found.is_none()),
+            }
+        }
+
+        // Removal
+        for key in keys {
+            let key_cell = make_key(key)?;
+            let removed = dict.remove(key_cell.as_slice()?)?;
+            let (cell, range) = removed.unwrap();
+            assert_eq!(
+                range.apply(cell.as_ref())?.load_u32()?,
+                expected.remove(key).unwrap()
+            );
+            assert!(dict.remove(key_cell.as_slice()?)?.is_none());
+
+            let mut rebuilt = PfxDict::<16>::new();
+            for (key, value) in &expected {
+                let key_cell = make_key(key)?;
+                let value = CellBuilder::build_from(*value)?;
+                rebuilt.set(key_cell.as_slice()?, value.as_slice()?)?;
+            }
+            assert_eq!(dict, rebuilt);
+        }
+        assert!(dict.is_empty());
+
+        Ok(())
+    }
+}
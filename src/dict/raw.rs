@@ -1,8 +1,17 @@
+use std::ops::{Bound, RangeBounds};
+
 use crate::cell::*;
-use crate::error::Error;
+use crate::error::{DictValidationError, Error};
+use crate::merkle::MerkleProof;
 use crate::util::{unlikely, IterStatus};
 
-use super::{dict_get, dict_insert, dict_load_from_root, read_label, SetMode};
+use super::diff::*;
+use super::{
+    build_dict_from_sorted_iter, dict_find, dict_find_bound, dict_get, dict_get_subdict,
+    dict_insert, dict_load_from_root, dict_merge, dict_prove_key, dict_remove,
+    dict_split_by_prefix, dict_validate, dict_verify_key_proof, read_label, Branch, DictBound,
+    SetMode,
+};
 
 /// Dictionary with fixed length keys (where `N` is a number of bits in each key).
 ///
@@ -124,6 +133,40 @@ impl<const N: u16> RawDict<N> {
         }
     }
 
+    /// Builds a dictionary from entries sorted by key in strictly ascending order.
+    /// Each dictionary cell is finalized exactly once.
+    ///
+    /// Returns an error if some key has an invalid length or if keys are not sorted.
+    pub fn from_sorted_iter_ext<I, K, V>(
+        entries: I,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Store,
+        V: Store,
+    {
+        match build_dict_from_sorted_iter(entries, N, finalizer) {
+            Ok(root) => Ok(Self(root)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Builds a dictionary from entries sorted by key in strictly ascending order.
+    /// Each dictionary cell is finalized exactly once.
+    ///
+    /// Use [`from_sorted_iter_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`from_sorted_iter_ext`]: RawDict::from_sorted_iter_ext
+    pub fn from_sorted_iter<I, K, V>(entries: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Store,
+        V: Store,
+    {
+        Self::from_sorted_iter_ext(entries, &mut Cell::default_finalizer())
+    }
+
     /// Returns a `CellSlice` of the value corresponding to the key.
     pub fn get<'a: 'b, 'b>(&'a self, key: CellSlice<'b>) -> Result<Option<CellSlice<'a>>, Error> {
         dict_get(&self.0, N, key)
@@ -134,6 +177,73 @@ impl<const N: u16> RawDict<N> {
         Ok(ok!(dict_get(&self.0, N, key)).is_some())
     }
 
+    /// Checks the structure of the dictionary cells.
+    ///
+    /// Values are not checked since their type is unknown.
+    /// Returns the key prefix of the first invalid node on failure.
+    pub fn validate(&self) -> Result<(), DictValidationError> {
+        dict_validate(&self.0, N, &mut |_| Ok(()))
+    }
+
+    /// Returns the lowest key and a value corresponding to the key.
+    pub fn get_min(&self, signed: bool) -> Result<Option<(CellBuilder, CellSlice<'_>)>, Error> {
+        dict_find_bound(&self.0, N, DictBound::Min, signed)
+    }
+
+    /// Returns the largest key and a value corresponding to the key.
+    pub fn get_max(&self, signed: bool) -> Result<Option<(CellBuilder, CellSlice<'_>)>, Error> {
+        dict_find_bound(&self.0, N, DictBound::Max, signed)
+    }
+
+    /// Finds the specified dictionary bound and returns a key and a value corresponding to the key.
+    pub fn get_bound(
+        &self,
+        bound: DictBound,
+        signed: bool,
+    ) -> Result<Option<(CellBuilder, CellSlice<'_>)>, Error> {
+        dict_find_bound(&self.0, N, bound, signed)
+    }
+
+    /// Returns the closest key greater than the specified one
+    /// and a value corresponding to the key.
+    pub fn get_next(
+        &self,
+        key: CellSlice<'_>,
+        signed: bool,
+    ) -> Result<Option<(CellBuilder, CellSlice<'_>)>, Error> {
+        dict_find(&self.0, N, key, DictBound::Max, false, signed)
+    }
+
+    /// Returns the closest key less than the specified one
+    /// and a value corresponding to the key.
+    pub fn get_prev(
+        &self,
+        key: CellSlice<'_>,
+        signed: bool,
+    ) -> Result<Option<(CellBuilder, CellSlice<'_>)>, Error> {
+        dict_find(&self.0, N, key, DictBound::Min, false, signed)
+    }
+
+    /// Returns the specified key or the closest greater one
+    /// and a value corresponding to the key.
+    pub fn get_or_next(
+        &self,
+        key: CellSlice<'_>,
+        signed: bool,
+    ) -> Result<Option<(CellBuilder, CellSlice<'_>)>, Error> {
+        dict_find(&self.0, N, key, DictBound::Max, true, signed)
+    }
+
+    /// Returns the specified key or the closest less one
+    /// and a value corresponding to the key.
+    pub fn get_or_prev(
+        &self,
+        key: CellSlice<'_>,
+        signed: bool,
+    ) -> Result<Option<(CellBuilder, CellSlice<'_>)>, Error> {
+        dict_find(&self.0, N, key, DictBound::Min, true, signed)
+    }
+
     /// Sets the value associated with the key in the dictionary.
     pub fn set_ext(
         &mut self,
@@ -190,6 +300,81 @@ impl<const N: u16> RawDict<N> {
         Ok(())
     }
 
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value as cell slice parts.
+    pub fn remove_ext(
+        &mut self,
+        mut key: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Option<CellSliceParts>, Error> {
+        let (root, removed) = ok!(dict_remove(&self.0, &mut key, N, finalizer));
+        self.0 = root;
+        Ok(removed)
+    }
+
+    /// Splits the dictionary by the next bit after the key prefix.
+    /// Returns dictionaries with keys starting with `prefix + 0`
+    /// and `prefix + 1` respectively.
+    ///
+    /// All keys must start with the `prefix`, otherwise an error is returned.
+    pub fn split_by_prefix_ext(
+        &self,
+        prefix: &CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(Self, Self), Error> {
+        let (left, right) = ok!(dict_split_by_prefix(&self.0, N, prefix, finalizer));
+        Ok((Self(left), Self(right)))
+    }
+
+    /// Merges the other dictionary into this one, reusing disjoint subtrees.
+    /// Values from the `other` dictionary replace values with the same keys.
+    pub fn merge_ext(&mut self, other: &Self, finalizer: &mut dyn Finalizer) -> Result<(), Error> {
+        self.0 = ok!(dict_merge(&self.0, &other.0, N, finalizer));
+        Ok(())
+    }
+
+    /// Returns a dictionary with all keys that start with the `prefix`.
+    /// The resulting dictionary shares cells with the original one.
+    pub fn get_subdict_ext(
+        &self,
+        prefix: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Self, Error> {
+        match dict_get_subdict(&self.0, N, &prefix, false, finalizer) {
+            Ok(root) => Ok(Self(root)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns a dictionary with all keys that start with the `prefix`,
+    /// with the prefix removed from them. `M` must be equal to `N - prefix_len`.
+    /// The resulting dictionary shares cells with the original one.
+    pub fn get_stripped_subdict_ext<const M: u16>(
+        &self,
+        prefix: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<RawDict<M>, Error> {
+        if M.checked_add(prefix.remaining_bits()) != Some(N) {
+            return Err(Error::CellUnderflow);
+        }
+        match dict_get_subdict(&self.0, N, &prefix, true, finalizer) {
+            Ok(root) => Ok(RawDict(root)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Builds a Merkle proof with all cells on the lookup path of the key.
+    /// It proves either the value or the absence of the key.
+    ///
+    /// Fails with [`Error::EmptyProof`] if the dictionary is empty.
+    pub fn prove_key_ext(
+        &self,
+        key: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<MerkleProof, Error> {
+        dict_prove_key(&self.0, N, key, finalizer)
+    }
+
     /// Gets an iterator over the entries of the dictionary, sorted by key.
     /// The iterator element type is `Result<(CellBuilder, CellSlice)>`.
     ///
@@ -206,6 +391,38 @@ impl<const N: u16> RawDict<N> {
         RawIter::new(&self.0, N)
     }
 
+    /// Gets an iterator over the differences between this dictionary
+    /// and the `other` one, sorted by key. This dictionary is treated
+    /// as the old version and the `other` one as the new version.
+    /// The iterator element type is `Result<DictDiffItem<CellBuilder, CellSlice>>`.
+    ///
+    /// Identical subtrees are skipped by comparing their hashes,
+    /// so the cost is proportional to the size of the change.
+    ///
+    /// If any dictionary is invalid, finishes after the first invalid element,
+    /// returning an error.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> RawDictDiff<'a> {
+        RawDictDiff::new(&self.0, &other.0, N)
+    }
+
+    /// Gets an iterator over a sub-range of entries of the dictionary,
+    /// sorted by key. The iterator element type is `Result<(CellBuilder, CellSlice)>`.
+    ///
+    /// If `signed` is `true`, keys are compared as two's complement integers,
+    /// and the iteration order is also signed. Otherwise keys are compared
+    /// bit by bit (as big-endian unsigned integers).
+    /// The iterator seeks directly to the bounds, so creating it costs
+    /// only a single descent for each bound.
+    ///
+    /// Returns an error if bounds have an invalid length or the path
+    /// to them is invalid.
+    pub fn range<'b, R>(&'_ self, range: R, signed: bool) -> Result<RawIter<'_>, Error>
+    where
+        R: RangeBounds<CellSlice<'b>>,
+    {
+        RawIter::new_range(&self.0, N, range.start_bound(), range.end_bound(), signed)
+    }
+
     /// Gets an iterator over the keys of the dictionary, in sorted order.
     /// The iterator element type is `Result<CellBuilder>`.
     ///
@@ -259,20 +476,108 @@ impl<const N: u16> RawDict<N> {
     pub fn add(&mut self, key: CellSlice<'_>, value: CellSlice<'_>) -> Result<(), Error> {
         self.add_ext(key, value, &mut Cell::default_finalizer())
     }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value as cell slice parts.
+    ///
+    /// Use [`remove_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`remove_ext`]: RawDict::remove_ext
+    pub fn remove(&mut self, key: CellSlice<'_>) -> Result<Option<CellSliceParts>, Error> {
+        self.remove_ext(key, &mut Cell::default_finalizer())
+    }
+
+    /// Splits the dictionary by the next bit after the key prefix.
+    /// Returns dictionaries with keys starting with `prefix + 0`
+    /// and `prefix + 1` respectively.
+    ///
+    /// Use [`split_by_prefix_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`split_by_prefix_ext`]: RawDict::split_by_prefix_ext
+    pub fn split_by_prefix(&self, prefix: &CellSlice<'_>) -> Result<(Self, Self), Error> {
+        self.split_by_prefix_ext(prefix, &mut Cell::default_finalizer())
+    }
+
+    /// Merges the other dictionary into this one, reusing disjoint subtrees.
+    /// Values from the `other` dictionary replace values with the same keys.
+    ///
+    /// Use [`merge_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`merge_ext`]: RawDict::merge_ext
+    pub fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        self.merge_ext(other, &mut Cell::default_finalizer())
+    }
+
+    /// Returns a dictionary with all keys that start with the `prefix`.
+    /// The resulting dictionary shares cells with the original one.
+    ///
+    /// Use [`get_subdict_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`get_subdict_ext`]: RawDict::get_subdict_ext
+    pub fn get_subdict(&self, prefix: CellSlice<'_>) -> Result<Self, Error> {
+        self.get_subdict_ext(prefix, &mut Cell::default_finalizer())
+    }
+
+    /// Returns a dictionary with all keys that start with the `prefix`,
+    /// with the prefix removed from them. `M` must be equal to `N - prefix_len`.
+    /// The resulting dictionary shares cells with the original one.
+    ///
+    /// Use [`get_stripped_subdict_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`get_stripped_subdict_ext`]: RawDict::get_stripped_subdict_ext
+    pub fn get_stripped_subdict<const M: u16>(
+        &self,
+        prefix: CellSlice<'_>,
+    ) -> Result<RawDict<M>, Error> {
+        self.get_stripped_subdict_ext(prefix, &mut Cell::default_finalizer())
+    }
+
+    /// Builds a Merkle proof with all cells on the lookup path of the key.
+    /// It proves either the value or the absence of the key.
+    ///
+    /// Fails with [`Error::EmptyProof`] if the dictionary is empty.
+    ///
+    /// Use [`prove_key_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`prove_key_ext`]: RawDict::prove_key_ext
+    pub fn prove_key(&self, key: CellSlice<'_>) -> Result<MerkleProof, Error> {
+        self.prove_key_ext(key, &mut Cell::default_finalizer())
+    }
+
+    /// Returns a `CellSlice` of the value corresponding to the key from the
+    /// Merkle proof of the dictionary with the specified root hash.
+    ///
+    /// Returns `None` if the proof shows that the key is absent.
+    /// Fails if the proof is for another dictionary or if it doesn't
+    /// contain the lookup path of the key.
+    pub fn verify_key_proof<'a: 'b, 'b>(
+        root_hash: &HashBytes,
+        proof: &'a MerkleProof,
+        key: CellSlice<'b>,
+    ) -> Result<Option<CellSlice<'a>>, Error> {
+        dict_verify_key_proof(root_hash, proof, N, key)
+    }
 }
 
 /// An iterator over the entries of a [`RawDict`] or a [`Dict`].
 ///
-/// This struct is created by the [`iter`] method on [`RawDict`] or the [`raw_iter`] method on [`Dict`].
-/// See their documentation for more.
+/// This struct is created by the [`iter`] or [`range`] methods on [`RawDict`]
+/// or the [`raw_iter`] method on [`Dict`]. See their documentation for more.
 ///
 /// [`Dict`]: crate::dict::Dict
 /// [`iter`]: RawDict::iter
+/// [`range`]: RawDict::range
 /// [`raw_iter`]: crate::dict::Dict::raw_iter
 #[derive(Clone)]
 pub struct RawIter<'a> {
     // TODO: replace `Vec` with on-stack stuff
     segments: Vec<IterSegment<'a>>,
+    back_segments: Vec<IterSegment<'a>>,
+    lower: Bound<CellBuilder>,
+    upper: Bound<CellBuilder>,
+    signed: bool,
+    front_started: bool,
+    back_started: bool,
     status: IterStatus,
 }
 
@@ -287,6 +592,12 @@ impl<'a> RawIter<'a> {
             if unlikely(data.descriptor().is_pruned_branch()) {
                 return Self {
                     segments: Vec::new(),
+                    back_segments: Vec::new(),
+                    lower: Bound::Unbounded,
+                    upper: Bound::Unbounded,
+                    signed: false,
+                    front_started: false,
+                    back_started: false,
                     status: IterStatus::Pruned,
                 };
             }
@@ -299,22 +610,74 @@ impl<'a> RawIter<'a> {
         }
 
         Self {
+            back_segments: segments.clone(),
             segments,
+            lower: Bound::Unbounded,
+            upper: Bound::Unbounded,
+            signed: false,
+            front_started: false,
+            back_started: false,
             status: IterStatus::Valid,
         }
     }
 
+    /// Creates an iterator over a sub-range of entries of a dictionary.
+    ///
+    /// If `signed` is `true`, keys are compared as two's complement integers.
+    /// Otherwise keys are compared as big-endian unsigned integers (bit by bit).
+    /// Bounds must have exactly `bit_len` bits.
+    pub fn new_range(
+        root: &'a Option<Cell>,
+        bit_len: u16,
+        start: Bound<&CellSlice<'_>>,
+        end: Bound<&CellSlice<'_>>,
+        signed: bool,
+    ) -> Result<Self, Error> {
+        let lower = ok!(make_bound(start, bit_len));
+        let upper = ok!(make_bound(end, bit_len));
+
+        let root = match root {
+            Some(root) => {
+                let data = root.as_ref();
+                if unlikely(data.descriptor().is_pruned_branch()) {
+                    return Err(Error::PrunedBranchAccess);
+                }
+                data
+            }
+            None => {
+                return Ok(Self {
+                    segments: Vec::new(),
+                    back_segments: Vec::new(),
+                    lower,
+                    upper,
+                    signed,
+                    front_started: false,
+                    back_started: false,
+                    status: IterStatus::Valid,
+                })
+            }
+        };
+
+        Ok(Self {
+            segments: ok!(seek_segments(root, bit_len, start, Branch::Right, signed)),
+            back_segments: ok!(seek_segments(root, bit_len, end, Branch::Left, signed)),
+            lower,
+            upper,
+            signed,
+            front_started: false,
+            back_started: false,
+            status: IterStatus::Valid,
+        })
+    }
+
     #[inline]
     pub(crate) fn finish(&mut self, err: Error) -> Error {
         self.status = IterStatus::Broken;
         err
     }
-}
 
-impl<'a> Iterator for RawIter<'a> {
-    type Item = Result<(CellBuilder, CellSlice<'a>), Error>;
-
-    fn next(&mut self) -> Option<Self::Item> {
+    #[inline]
+    fn next_impl(&mut self, reverse: bool) -> Option<<Self as Iterator>::Item> {
         if unlikely(!self.status.is_valid()) {
             return if self.status.is_pruned() {
                 self.status = IterStatus::Broken;
@@ -324,88 +687,318 @@ impl<'a> Iterator for RawIter<'a> {
             };
         }
 
-        while let Some(mut segment) = self.segments.pop() {
-            // Load segment data
-            let mut data = match segment.data.as_slice() {
-                Ok(data) => data,
-                Err(e) => return Some(Err(self.finish(e))),
-            };
+        let (started, other_started) = if reverse {
+            (&mut self.back_started, self.front_started)
+        } else {
+            (&mut self.front_started, self.back_started)
+        };
 
-            // Read the next key part from the latest segment
-            let prefix = match read_label(&mut data, segment.remaining_bit_len) {
-                Ok(prefix) => prefix,
-                Err(e) => return Some(Err(self.finish(e))),
+        // Returned keys are only recorded once both sides are used,
+        // so the first step from this side stops at the next key of the other side
+        if !std::mem::replace(started, true) && other_started {
+            let mut other_segments = if reverse {
+                self.segments.clone()
+            } else {
+                self.back_segments.clone()
             };
-
-            // Check remaining bits
-            segment.remaining_bit_len = match segment
-                .remaining_bit_len
-                .checked_sub(prefix.remaining_bits())
-            {
-                // Well-formed `Dict` should have the required number of bits
-                // for each value
-                Some(remaining) => {
-                    // Try to store the next prefix into the segment key
-                    if let Err(e) = segment.key.store_slice_data(prefix) {
-                        return Some(Err(self.finish(e)));
-                    } else if remaining == 0 {
-                        // Return the next entry if there are no remaining bits to read
-                        return Some(Ok((segment.key, data)));
-                    } else {
-                        // Continue reading
-                        remaining
-                    }
+            let key = match next_entry(&mut other_segments, !reverse, self.signed) {
+                Ok(Some((key, _))) => key,
+                Ok(None) => {
+                    self.status = IterStatus::Broken;
+                    return None;
                 }
-                None => return Some(Err(self.finish(Error::CellUnderflow))),
+                Err(e) => return Some(Err(self.finish(e))),
             };
+            if reverse {
+                self.lower = Bound::Included(key);
+            } else {
+                self.upper = Bound::Included(key);
+            }
+        }
 
-            // Trying to load the left child cell
-            let left_child = match data.cell().reference(0) {
-                Some(child) => {
-                    // Handle pruned branch access
-                    if unlikely(child.descriptor().is_pruned_branch()) {
-                        return Some(Err(self.finish(Error::PrunedBranchAccess)));
-                    }
-                    child
-                }
-                None => return Some(Err(self.finish(Error::CellUnderflow))),
-            };
+        let segments = if reverse {
+            &mut self.back_segments
+        } else {
+            &mut self.segments
+        };
 
-            // Trying to load the right child cell
-            let right_child = match data.cell().reference(1) {
-                Some(child) => {
-                    // Handle pruned branch access
-                    if unlikely(child.descriptor().is_pruned_branch()) {
-                        return Some(Err(self.finish(Error::PrunedBranchAccess)));
-                    }
-                    child
+        let (key, value) = match next_entry(segments, reverse, self.signed) {
+            Ok(Some(entry)) => entry,
+            Ok(None) => {
+                self.status = IterStatus::Broken;
+                return None;
+            }
+            Err(e) => return Some(Err(self.finish(e))),
+        };
+
+        // Stop when the entry is out of bounds or was already
+        // returned from the other side
+        let (in_range, other_bound) = if reverse {
+            (is_above(&key, &self.lower, self.signed), &mut self.upper)
+        } else {
+            (is_below(&key, &self.upper, self.signed), &mut self.lower)
+        };
+        if !in_range {
+            self.status = IterStatus::Broken;
+            return None;
+        }
+        if other_started {
+            *other_bound = Bound::Excluded(key.clone());
+        }
+
+        Some(Ok((key, value)))
+    }
+}
+
+impl<'a> Iterator for RawIter<'a> {
+    type Item = Result<(CellBuilder, CellSlice<'a>), Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_impl(false)
+    }
+}
+
+impl<'a> DoubleEndedIterator for RawIter<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.next_impl(true)
+    }
+}
+
+/// Pops the next leaf from the iterator stack.
+///
+/// Forks are expanded so that the closest subtree is always at the top
+/// of the stack (left first for the forward direction, right first
+/// for the reverse one). For signed keys the order of the fork
+/// at the first key bit is inverted.
+fn next_entry<'a>(
+    segments: &mut Vec<IterSegment<'a>>,
+    reverse: bool,
+    signed: bool,
+) -> Result<Option<(CellBuilder, CellSlice<'a>)>, Error> {
+    while let Some(mut segment) = segments.pop() {
+        // Load segment data
+        let mut data = ok!(segment.data.as_slice());
+
+        // Read the next key part from the latest segment
+        let prefix = ok!(read_label(&mut data, segment.remaining_bit_len));
+
+        // Check remaining bits
+        segment.remaining_bit_len = match segment
+            .remaining_bit_len
+            .checked_sub(prefix.remaining_bits())
+        {
+            // Well-formed `Dict` should have the required number of bits
+            // for each value
+            Some(remaining) => {
+                // Try to store the next prefix into the segment key
+                ok!(segment.key.store_slice_data(prefix));
+                if remaining == 0 {
+                    // Return the next entry if there are no remaining bits to read
+                    return Ok(Some((segment.key, data)));
                 }
-                None => return Some(Err(self.finish(Error::CellUnderflow))),
-            };
+                // Continue reading
+                remaining
+            }
+            None => return Err(Error::CellUnderflow),
+        };
 
-            // Push cells in reverse order
-            self.segments.reserve(2);
-            self.segments.push(IterSegment {
-                data: right_child,
-                remaining_bit_len: segment.remaining_bit_len - 1,
-                key: {
-                    let mut key = segment.key.clone();
-                    _ = key.store_bit_one();
-                    key
-                },
+        let left_child = ok!(load_child(data.cell(), Branch::Left));
+        let right_child = ok!(load_child(data.cell(), Branch::Right));
+
+        // Push the farthest child first
+        let mut children = [(Branch::Right, right_child), (Branch::Left, left_child)];
+        if reverse != is_sign_fork(&segment.key, signed) {
+            children.reverse();
+        }
+        let [(far_branch, far_child), (near_branch, near_child)] = children;
+
+        segments.reserve(2);
+        segments.push(IterSegment {
+            data: far_child,
+            remaining_bit_len: segment.remaining_bit_len - 1,
+            key: {
+                let mut key = segment.key.clone();
+                ok!(key.store_bit(far_branch == Branch::Right));
+                key
+            },
+        });
+        segments.push(IterSegment {
+            data: near_child,
+            remaining_bit_len: segment.remaining_bit_len - 1,
+            key: {
+                ok!(segment.key.store_bit(near_branch == Branch::Right));
+                segment.key
+            },
+        });
+    }
+
+    // No segments left
+    Ok(None)
+}
+
+/// Builds an iterator stack which starts at the specified bound.
+///
+/// `after` is the branch which contains entries further in the
+/// iteration direction (right for the forward iterator).
+fn seek_segments<'a>(
+    root: &'a DynCell,
+    bit_len: u16,
+    bound: Bound<&CellSlice<'_>>,
+    after: Branch,
+    signed: bool,
+) -> Result<Vec<IterSegment<'a>>, Error> {
+    let mut segments = Vec::new();
+
+    let (bound_key, inclusive) = match bound {
+        Bound::Included(key) => (*key, true),
+        Bound::Excluded(key) => (*key, false),
+        Bound::Unbounded => {
+            segments.push(IterSegment {
+                data: root,
+                remaining_bit_len: bit_len,
+                key: CellBuilder::new(),
             });
-            self.segments.push(IterSegment {
-                data: left_child,
-                remaining_bit_len: segment.remaining_bit_len - 1,
-                key: {
-                    _ = segment.key.store_bit_zero();
-                    segment.key
-                },
+            return Ok(segments);
+        }
+    };
+    let mut data = root;
+    let mut key = CellBuilder::new();
+    let mut remaining_bit_len = bit_len;
+    loop {
+        let mut remaining_data = ok!(data.as_slice());
+
+        // Read the next key part from the current node
+        let prefix = ok!(read_label(&mut remaining_data, remaining_bit_len));
+
+        let mut bound_part = bound_key;
+        bound_part.try_advance(bit_len - remaining_bit_len, 0);
+
+        let lcp_len = bound_part
+            .longest_common_data_prefix(&prefix)
+            .remaining_bits();
+        if lcp_len < prefix.remaining_bits() {
+            // The whole subtree is either before or after the bound
+            let mut bound_bit = ok!(bound_part.get_bit(lcp_len));
+            if lcp_len == 0 && is_sign_fork(&key, signed) {
+                bound_bit = !bound_bit;
+            }
+            if bound_bit != (after == Branch::Right) {
+                segments.push(IterSegment {
+                    data,
+                    remaining_bit_len,
+                    key,
+                });
+            }
+            return Ok(segments);
+        }
+
+        remaining_bit_len = match remaining_bit_len.checked_sub(lcp_len) {
+            // The bound key itself
+            Some(0) => {
+                if inclusive {
+                    segments.push(IterSegment {
+                        data,
+                        remaining_bit_len,
+                        key,
+                    });
+                }
+                return Ok(segments);
+            }
+            Some(remaining) => remaining,
+            None => return Err(Error::CellUnderflow),
+        };
+        ok!(key.store_slice_data(prefix));
+
+        let next_branch = match ok!(bound_part.get_bit(lcp_len)) {
+            false => Branch::Left,
+            true => Branch::Right,
+        };
+
+        // Remember the sibling subtree if it is further
+        let further = if is_sign_fork(&key, signed) {
+            after.opposite()
+        } else {
+            after
+        };
+        if next_branch != further {
+            let mut sibling_key = key.clone();
+            ok!(sibling_key.store_bit(further == Branch::Right));
+            segments.push(IterSegment {
+                data: ok!(load_child(data, further)),
+                remaining_bit_len: remaining_bit_len - 1,
+                key: sibling_key,
             });
         }
 
-        // No segments left
-        None
+        ok!(key.store_bit(next_branch == Branch::Right));
+        data = ok!(load_child(data, next_branch));
+        remaining_bit_len -= 1;
+    }
+}
+
+fn load_child(data: &DynCell, branch: Branch) -> Result<&DynCell, Error> {
+    match data.reference(branch as u8) {
+        // Handle pruned branch access
+        Some(child) if unlikely(child.descriptor().is_pruned_branch()) => {
+            Err(Error::PrunedBranchAccess)
+        }
+        Some(child) => Ok(child),
+        None => Err(Error::CellUnderflow),
+    }
+}
+
+fn make_bound(bound: Bound<&CellSlice<'_>>, bit_len: u16) -> Result<Bound<CellBuilder>, Error> {
+    let make_key = |key: &CellSlice<'_>| {
+        if key.remaining_bits() != bit_len {
+            return Err(Error::CellUnderflow);
+        }
+        let mut builder = CellBuilder::new();
+        ok!(builder.store_slice_data(key));
+        Ok(builder)
+    };
+
+    Ok(match bound {
+        Bound::Included(key) => Bound::Included(ok!(make_key(key))),
+        Bound::Excluded(key) => Bound::Excluded(ok!(make_key(key))),
+        Bound::Unbounded => Bound::Unbounded,
+    })
+}
+
+/// Returns `true` if the next bit after the key prefix is the sign bit.
+#[inline]
+fn is_sign_fork(key_prefix: &CellBuilder, signed: bool) -> bool {
+    signed && key_prefix.bit_len() == 0
+}
+
+/// Compares keys of the same length.
+fn cmp_keys(left: &CellBuilder, right: &CellBuilder, signed: bool) -> std::cmp::Ordering {
+    let left = left.raw_data();
+    let right = right.raw_data();
+    if signed && (left[0] ^ right[0]) & 0x80 != 0 {
+        // Negative keys (with the sign bit set) go first
+        right[0].cmp(&left[0])
+    } else {
+        left.cmp(right)
+    }
+}
+
+/// Returns `true` if the key is not greater than the upper bound.
+fn is_below(key: &CellBuilder, upper: &Bound<CellBuilder>, signed: bool) -> bool {
+    match upper {
+        Bound::Included(upper) => cmp_keys(key, upper, signed).is_le(),
+        Bound::Excluded(upper) => cmp_keys(key, upper, signed).is_lt(),
+        Bound::Unbounded => true,
+    }
+}
+
+/// Returns `true` if the key is not less than the lower bound.
+fn is_above(key: &CellBuilder, lower: &Bound<CellBuilder>, signed: bool) -> bool {
+    match lower {
+        Bound::Included(lower) => cmp_keys(key, lower, signed).is_ge(),
+        Bound::Excluded(lower) => cmp_keys(key, lower, signed).is_gt(),
+        Bound::Unbounded => true,
     }
 }
 
@@ -449,6 +1042,15 @@ impl<'a> Iterator for RawKeys<'a> {
     }
 }
 
+impl<'a> DoubleEndedIterator for RawKeys<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        match self.inner.next_back()? {
+            Ok((key, _)) => Some(Ok(key)),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
 /// An iterator over the values of a [`RawDict`] or a [`Dict`].
 ///
 /// This struct is created by the [`values`] method on [`RawDict`] or the [`raw_values`] method on [`Dict`].
@@ -710,6 +1312,338 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn dict_remove() -> anyhow::Result<()> {
+        let mut dict = RawDict::<32>::new();
+
+        for i in 0..10 {
+            let key = build_cell(|b| b.store_u32(i));
+            let value = build_cell(|b| b.store_u32(i * 10));
+            dict.set(key.as_slice()?, value.as_slice()?)?;
+        }
+
+        // Remove missing key
+        let key = build_cell(|b| b.store_u32(123));
+        assert!(dict.remove(key.as_slice()?)?.is_none());
+
+        for i in (0..10).rev() {
+            let key = build_cell(|b| b.store_u32(i));
+            let (cell, range) = dict.remove(key.as_slice()?)?.unwrap();
+            let mut value = range.apply(cell.as_ref())?;
+            assert_eq!(value.load_u32()?, i * 10);
+            assert!(value.is_data_empty() && value.is_refs_empty());
+
+            assert!(!dict.contains_key(key.as_slice()?)?);
+            assert_eq!(dict.values().count(), i as usize);
+
+            // Remaining entries are still reachable
+            for j in 0..i {
+                let key = build_cell(|b| b.store_u32(j));
+                assert!(dict.contains_key(key.as_slice()?)?);
+            }
+        }
+        assert!(dict.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // takes too long to execute on miri
+    fn dict_remove_restores_structure() -> anyhow::Result<()> {
+        let value = build_cell(|b| b.store_bit_one());
+
+        let mut dict = RawDict::<32>::new();
+        for i in [1u32, 5, 0xffff, 0xf0000000, 0xf0000001] {
+            let key = build_cell(|b| b.store_u32(i));
+            dict.set(key.as_slice()?, value.as_slice()?)?;
+        }
+        let expected = dict.clone();
+
+        // Adding and then removing a key must produce the same tree
+        for i in [0u32, 2, 4, 6, 0x1000, u32::MAX, 0xf0000002] {
+            let key = build_cell(|b| b.store_u32(i));
+            dict.set(key.as_slice()?, value.as_slice()?)?;
+            assert!(dict.remove(key.as_slice()?)?.is_some());
+            assert_eq!(dict, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_from_sorted_iter() -> anyhow::Result<()> {
+        let key_sets: [&[u32]; 5] = [
+            &[],
+            &[123],
+            &[0, 1, 2, 3, 4, 5, 6, 7],
+            &[
+                0,
+                1,
+                5,
+                0x1000,
+                0x7fffffff,
+                0x80000000,
+                0xf0000000,
+                u32::MAX,
+            ],
+            &[1, 3, 0x100, 0x101, 0x10000, 0x10001, 0x10002, 0xffff0000],
+        ];
+
+        for keys in key_sets {
+            let mut expected = RawDict::<32>::new();
+            let mut entries = Vec::new();
+            for &i in keys {
+                let key = build_cell(|b| b.store_u32(i));
+                let value = build_cell(|b| b.store_u32(!i));
+                expected.set(key.as_slice()?, value.as_slice()?)?;
+                entries.push((i, !i));
+            }
+
+            // Each cell is finalized exactly once
+            let mut finalized = 0;
+            let mut finalizer = |parts: CellParts| {
+                finalized += 1;
+                Cell::default_finalizer().finalize_cell(parts)
+            };
+            let dict = RawDict::<32>::from_sorted_iter_ext(entries, &mut finalizer)?;
+            assert_eq!(dict, expected);
+            assert_eq!(finalized, (keys.len() * 2).saturating_sub(1));
+        }
+
+        // Keys must be strictly ascending
+        assert!(RawDict::<32>::from_sorted_iter([(2u32, ()), (1, ())]).is_err());
+        assert!(RawDict::<32>::from_sorted_iter([(1u32, ()), (1, ())]).is_err());
+
+        // Keys must have a valid length
+        assert!(RawDict::<32>::from_sorted_iter([(1u16, ())]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_split_merge() -> anyhow::Result<()> {
+        let keys = [
+            0u32,
+            1,
+            5,
+            0x1000,
+            0x7fffffff,
+            0x80000000,
+            0xf0000000,
+            u32::MAX,
+        ];
+
+        let mut dict = RawDict::<32>::new();
+        for &i in &keys {
+            let key = build_cell(|b| b.store_u32(i));
+            let value = build_cell(|b| b.store_u32(!i));
+            dict.set(key.as_slice()?, value.as_slice()?)?;
+        }
+
+        let collect_keys = |dict: &RawDict<32>| -> anyhow::Result<Vec<u32>> {
+            let mut result = Vec::new();
+            for entry in dict.iter() {
+                let (key, mut value) = entry?;
+                let key = key.build()?.as_slice()?.load_u32()?;
+                assert_eq!(value.load_u32()?, !key);
+                result.push(key);
+            }
+            Ok(result)
+        };
+
+        // Split by the first bit
+        let (mut left, right) = dict.split_by_prefix(&Cell::empty_cell_ref().as_slice()?)?;
+        assert_eq!(collect_keys(&left)?, &keys[..5]);
+        assert_eq!(collect_keys(&right)?, &keys[5..]);
+
+        // Split by the second bit
+        let prefix = build_cell(|b| b.store_bit_one());
+        let (right_left, right_right) = right.split_by_prefix(&prefix.as_slice()?)?;
+        assert_eq!(collect_keys(&right_left)?, &keys[5..6]);
+        assert_eq!(collect_keys(&right_right)?, &keys[6..]);
+
+        // All keys must start with the prefix
+        assert!(dict.split_by_prefix(&prefix.as_slice()?).is_err());
+
+        // Merge back into the same tree
+        let mut merged = right_right.clone();
+        merged.merge(&right_left)?;
+        assert_eq!(merged, right);
+
+        left.merge(&merged)?;
+        assert_eq!(left, dict);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_get_subdict() -> anyhow::Result<()> {
+        let keys = [
+            0u32,
+            1,
+            5,
+            0x1000,
+            0x1001,
+            0x7fffffff,
+            0x80000000,
+            0xf0000000,
+            u32::MAX,
+        ];
+
+        let mut dict = RawDict::<32>::new();
+        for &i in &keys {
+            let key = build_cell(|b| b.store_u32(i));
+            let value = build_cell(|b| b.store_u32(!i));
+            dict.set(key.as_slice()?, value.as_slice()?)?;
+        }
+
+        fn collect_keys<const N: u16>(dict: &RawDict<N>) -> anyhow::Result<Vec<u32>> {
+            let mut result = Vec::new();
+            for entry in dict.iter() {
+                let (key, _) = entry?;
+                result.push(key.build()?.as_slice()?.load_uint(N)? as u32);
+            }
+            Ok(result)
+        }
+
+        for prefix_len in 0..=32u16 {
+            for prefix in [0u32, 1, 0x1000, 0x7fffffff, 0xf0000000, 0xf8000000] {
+                let shift = 32 - prefix_len as u32;
+                let prefix = prefix.checked_shr(shift).unwrap_or(0);
+                let prefix_cell = build_cell(|b| b.store_uint(prefix as u64, prefix_len));
+
+                let matches = |key: &u32| key.checked_shr(shift).unwrap_or(0) == prefix;
+
+                let subdict = dict.get_subdict(prefix_cell.as_slice()?)?;
+                let expected = keys.iter().copied().filter(matches).collect::<Vec<_>>();
+                assert_eq!(collect_keys(&subdict)?, expected);
+
+                if prefix_len == 8 {
+                    let stripped = dict.get_stripped_subdict::<24>(prefix_cell.as_slice()?)?;
+                    let expected = expected
+                        .iter()
+                        .map(|key| key & 0xffffff)
+                        .collect::<Vec<_>>();
+                    assert_eq!(collect_keys(&stripped)?, expected);
+                }
+            }
+        }
+
+        // Prefix must not be longer than the key
+        let prefix = build_cell(|b| b.store_uint(0, 33));
+        assert!(dict.get_subdict(prefix.as_slice()?).is_err());
+
+        // Stripped key length must match
+        let prefix = build_cell(|b| b.store_u8(0));
+        assert!(dict.get_stripped_subdict::<8>(prefix.as_slice()?).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_merge_overlapping() -> anyhow::Result<()> {
+        let mut left = RawDict::<32>::new();
+        let mut right = RawDict::<32>::new();
+        let mut expected = RawDict::<32>::new();
+
+        for i in 0..40u32 {
+            let key = build_cell(|b| b.store_u32(i * 37));
+            let value = build_cell(|b| b.store_u32(i));
+            left.set(key.as_slice()?, value.as_slice()?)?;
+            expected.set(key.as_slice()?, value.as_slice()?)?;
+        }
+        for i in 20..80u32 {
+            let key = build_cell(|b| b.store_u32(i * 37));
+            let value = build_cell(|b| b.store_u32(i + 1000));
+            right.set(key.as_slice()?, value.as_slice()?)?;
+            expected.set(key.as_slice()?, value.as_slice()?)?;
+        }
+
+        let mut merged = left.clone();
+        merged.merge(&right)?;
+        assert_eq!(merged, expected);
+
+        // Merging with an empty dictionary changes nothing
+        merged.merge(&RawDict::new())?;
+        assert_eq!(merged, expected);
+
+        let mut empty = RawDict::<32>::new();
+        empty.merge(&left)?;
+        assert_eq!(empty, left);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_validate() -> anyhow::Result<()> {
+        let mut dict = RawDict::<32>::new();
+        dict.validate()?;
+        for i in 0..100u32 {
+            let key = build_cell(|b| b.store_u32(i * 1234567));
+            let value = build_cell(|b| b.store_u32(i));
+            dict.set(key.as_slice()?, value.as_slice()?)?;
+        }
+        dict.validate()?;
+
+        let mut leaf = RawDict::<7>::new();
+        let key = build_cell(|b| b.store_small_uint(0b1010101, 7));
+        leaf.set(key.as_slice()?, Cell::empty_cell_ref().as_slice()?)?;
+        let leaf = leaf.root().clone().unwrap();
+
+        let make_fork = |left: Cell, right: Cell, extra: bool| {
+            build_cell(|b| {
+                // Empty short label
+                b.store_small_uint(0b00, 2)?;
+                if extra {
+                    b.store_bit_one()?;
+                }
+                b.store_reference(left)?;
+                b.store_reference(right)
+            })
+        };
+
+        // Valid fork
+        let dict = RawDict::<8>(Some(make_fork(leaf.clone(), leaf.clone(), false)));
+        dict.validate()?;
+
+        // Fork with extra data
+        let dict = RawDict::<8>(Some(make_fork(leaf.clone(), leaf.clone(), true)));
+        let e = dict.validate().unwrap_err();
+        assert_eq!(e.error, Error::InvalidData);
+        assert_eq!(e.key_prefix_bit_len, 0);
+        assert!(e.key_prefix.is_empty());
+
+        // Invalid right child
+        let dict = RawDict::<8>(Some(make_fork(leaf.clone(), Cell::empty_cell(), false)));
+        let e = dict.validate().unwrap_err();
+        assert_eq!(e.error, Error::CellUnderflow);
+        assert_eq!(e.key_prefix, [0x80]);
+        assert_eq!(e.key_prefix_bit_len, 1);
+        assert_eq!(
+            e.to_string(),
+            "invalid dictionary node at key prefix 80/1: cell underflow"
+        );
+
+        // Shared subtrees are checked only once
+        let mut node = leaf.clone();
+        for _ in 0..25 {
+            node = make_fork(node.clone(), node, false);
+        }
+        let dict = RawDict::<32>(Some(node));
+        dict.validate()?;
+
+        // Shared subtrees at different depths are checked separately
+        let left = make_fork(leaf.clone(), leaf.clone(), false);
+        let dict = RawDict::<9>(Some(make_fork(left, leaf.clone(), false)));
+        assert!(dict.validate().is_err());
+
+        // Label is longer than the key
+        let dict = RawDict::<6>(Some(leaf));
+        assert_eq!(dict.validate().unwrap_err().error, Error::InvalidData);
+
+        Ok(())
+    }
+
     #[test]
     fn dict_get() -> anyhow::Result<()> {
         let boc =
@@ -750,4 +1684,281 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn dict_get_bounds() -> anyhow::Result<()> {
+        let mut dict = RawDict::<32>::new();
+        assert!(dict.get_min(false)?.is_none());
+        assert!(dict.get_max(true)?.is_none());
+
+        for i in -10..=10i32 {
+            let key = build_cell(|b| b.store_u32(i as u32));
+            let value = build_cell(|b| b.store_u32(i as u32));
+            dict.set(key.as_slice()?, value.as_slice()?)?;
+        }
+
+        let load_key =
+            |key: CellBuilder| -> anyhow::Result<u32> { Ok(key.build()?.as_slice()?.load_u32()?) };
+
+        let (key, mut value) = dict.get_min(false)?.unwrap();
+        assert_eq!(load_key(key)?, 0);
+        assert_eq!(value.load_u32()?, 0);
+
+        let (key, _) = dict.get_max(false)?.unwrap();
+        assert_eq!(load_key(key)?, -1i32 as u32);
+
+        let (key, _) = dict.get_min(true)?.unwrap();
+        assert_eq!(load_key(key)? as i32, -10);
+
+        let (key, mut value) = dict.get_max(true)?.unwrap();
+        assert_eq!(load_key(key)? as i32, 10);
+        assert_eq!(value.load_u32()?, 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_find_nearest() -> anyhow::Result<()> {
+        let keys = [-1000i32, -30, -7, -6, -1, 0, 3, 4, 5, 64, 100, 9999];
+
+        let mut dict = RawDict::<32>::new();
+        for key in keys {
+            let key = build_cell(|b| b.store_u32(key as u32));
+            dict.set(key.as_slice()?, key.as_slice()?)?;
+        }
+
+        for signed in [false, true] {
+            let mut sorted = keys.map(|key| key as u32 as i64);
+            if signed {
+                sorted = keys.map(|key| key as i64);
+            }
+            sorted.sort_unstable();
+
+            let queries = keys
+                .iter()
+                .flat_map(|&key| [key.wrapping_sub(1), key, key.wrapping_add(1)])
+                .chain([i32::MIN, i32::MAX, -2000, 2000, 50]);
+
+            for query in queries {
+                let q = if signed {
+                    query as i64
+                } else {
+                    query as u32 as i64
+                };
+                let key = build_cell(|b| b.store_u32(query as u32));
+
+                let load = |entry: Option<(CellBuilder, CellSlice<'_>)>| -> anyhow::Result<_> {
+                    Ok(match entry {
+                        Some((key, mut value)) => {
+                            let key = key.build()?.as_slice()?.load_u32()?;
+                            assert_eq!(key, value.load_u32()?);
+                            Some(if signed {
+                                key as i32 as i64
+                            } else {
+                                key as i64
+                            })
+                        }
+                        None => None,
+                    })
+                };
+
+                let next = sorted.iter().copied().find(|&k| k > q);
+                let or_next = sorted.iter().copied().find(|&k| k >= q);
+                let prev = sorted.iter().rev().copied().find(|&k| k < q);
+                let or_prev = sorted.iter().rev().copied().find(|&k| k <= q);
+
+                assert_eq!(load(dict.get_next(key.as_slice()?, signed)?)?, next);
+                assert_eq!(load(dict.get_or_next(key.as_slice()?, signed)?)?, or_next);
+                assert_eq!(load(dict.get_prev(key.as_slice()?, signed)?)?, prev);
+                assert_eq!(load(dict.get_or_prev(key.as_slice()?, signed)?)?, or_prev);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_iter_rev() -> anyhow::Result<()> {
+        let mut dict = RawDict::<32>::new();
+        for i in 0..20u32 {
+            let key = build_cell(|b| b.store_u32(i * 3));
+            dict.set(key.as_slice()?, key.as_slice()?)?;
+        }
+
+        let load_key = |entry: Result<(CellBuilder, CellSlice<'_>), Error>| {
+            let (key, _) = entry.unwrap();
+            key.build().unwrap().as_slice().unwrap().load_u32().unwrap()
+        };
+
+        let keys = dict.iter().rev().map(load_key).collect::<Vec<_>>();
+        let expected = (0..20).rev().map(|i| i * 3).collect::<Vec<_>>();
+        assert_eq!(keys, expected);
+
+        // Both ends must meet without returning the same entry twice
+        let mut iter = dict.iter();
+        let mut front = Vec::new();
+        let mut back = Vec::new();
+        loop {
+            match iter.next() {
+                Some(entry) => front.push(load_key(entry)),
+                None => break,
+            }
+            match iter.next_back() {
+                Some(entry) => back.push(load_key(entry)),
+                None => break,
+            }
+        }
+        assert!(iter.next().is_none() && iter.next_back().is_none());
+        front.extend(back.into_iter().rev());
+        assert_eq!(front, (0..20).map(|i| i * 3).collect::<Vec<_>>());
+
+        // Switching sides after several steps from one of them
+        let all = (0..20).map(|i| i * 3).collect::<Vec<_>>();
+        for split in 0..=20 {
+            let mut iter = dict.iter();
+            let mut keys = iter.by_ref().take(split).map(load_key).collect::<Vec<_>>();
+            let mut back = iter.by_ref().rev().map(load_key).collect::<Vec<_>>();
+            back.reverse();
+            keys.extend(back);
+            assert_eq!(keys, all);
+
+            let mut iter = dict.iter();
+            let mut back = iter.by_ref().rev().take(split).map(load_key).collect::<Vec<_>>();
+            let mut keys = iter.by_ref().map(load_key).collect::<Vec<_>>();
+            back.reverse();
+            keys.extend(back);
+            assert_eq!(keys, all);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_range() -> anyhow::Result<()> {
+        use std::ops::Bound;
+
+        let keys = [
+            0u32,
+            1,
+            2,
+            7,
+            8,
+            100,
+            101,
+            1000,
+            12345,
+            i32::MAX as u32,
+            i32::MIN as u32,
+            i32::MIN as u32 + 1,
+            u32::MAX - 1,
+            u32::MAX,
+        ];
+
+        let mut dict = RawDict::<32>::new();
+        assert_eq!(dict.range(.., false)?.count(), 0);
+
+        for key in keys {
+            let key = build_cell(|b| b.store_u32(key));
+            dict.set(key.as_slice()?, key.as_slice()?)?;
+        }
+
+        let load_key = |entry: Result<(CellBuilder, CellSlice<'_>), Error>| {
+            let (key, mut value) = entry.unwrap();
+            let key = key.build().unwrap().as_slice().unwrap().load_u32().unwrap();
+            assert_eq!(value.load_u32().unwrap(), key);
+            key
+        };
+
+        let queries = keys
+            .iter()
+            .flat_map(|&key| [key.wrapping_sub(1), key, key.wrapping_add(1)])
+            .chain([50, 5000])
+            .collect::<Vec<_>>();
+
+        fn make_bound(kind: u8, key: CellSlice<'_>) -> Bound<CellSlice<'_>> {
+            match kind {
+                0 => Bound::Included(key),
+                1 => Bound::Excluded(key),
+                _ => Bound::Unbounded,
+            }
+        }
+        // Compares keys as `i32` for signed ranges
+        let ord_key = |key: u32, signed: bool| {
+            if signed {
+                key as i32 as i64
+            } else {
+                key as i64
+            }
+        };
+        let contains = |kind: u8, bound: u32, key: u32, upper: bool, signed: bool| {
+            let (key, bound) = (ord_key(key, signed), ord_key(bound, signed));
+            match kind {
+                0 if upper => key <= bound,
+                0 => key >= bound,
+                1 if upper => key < bound,
+                1 => key > bound,
+                _ => true,
+            }
+        };
+
+        for signed in [false, true] {
+            let mut sorted_keys = keys.to_vec();
+            sorted_keys.sort_by_key(|&key| ord_key(key, signed));
+
+            for &start in &queries {
+                let start_cell = build_cell(|b| b.store_u32(start));
+                let start_slice = start_cell.as_slice()?;
+
+                for &end in &queries {
+                    let end_cell = build_cell(|b| b.store_u32(end));
+                    let end_slice = end_cell.as_slice()?;
+
+                    for start_kind in 0..3 {
+                        for end_kind in 0..3 {
+                            let range = (
+                                make_bound(start_kind, start_slice),
+                                make_bound(end_kind, end_slice),
+                            );
+
+                            let expected = sorted_keys
+                                .iter()
+                                .copied()
+                                .filter(|&key| {
+                                    contains(start_kind, start, key, false, signed)
+                                        && contains(end_kind, end, key, true, signed)
+                                })
+                                .collect::<Vec<_>>();
+
+                            let forward =
+                                dict.range(range, signed)?.map(load_key).collect::<Vec<_>>();
+                            assert_eq!(forward, expected);
+
+                            let mut backward = dict
+                                .range(range, signed)?
+                                .rev()
+                                .map(load_key)
+                                .collect::<Vec<_>>();
+                            backward.reverse();
+                            assert_eq!(backward, expected);
+
+                            let mut iter = dict.range(range, signed)?;
+                            let half = expected.len() / 2;
+                            let mut mixed =
+                                iter.by_ref().take(half).map(load_key).collect::<Vec<_>>();
+                            let mut back = iter.rev().map(load_key).collect::<Vec<_>>();
+                            back.reverse();
+                            mixed.extend(back);
+                            assert_eq!(mixed, expected);
+                        }
+                    }
+                }
+            }
+        }
+
+        // Invalid bound length
+        let key = build_cell(|b| b.store_u16(123));
+        assert!(dict.range(key.as_slice()?.., false).is_err());
+
+        Ok(())
+    }
 }
@@ -1,12 +1,21 @@
 use std::borrow::Borrow;
 use std::marker::PhantomData;
+use std::ops::{Bound, RangeBounds};
 
 use crate::cell::*;
-use crate::error::Error;
+use crate::error::{DictValidationError, Error};
+use crate::merkle::MerkleProof;
 use crate::util::*;
 
+use super::diff::*;
+use super::entry::*;
 use super::raw::*;
-use super::{dict_get, dict_insert, dict_load_from_root, serialize_entry, DictKey, SetMode};
+use super::{
+    build_dict_from_sorted_iter, dict_find, dict_find_bound, dict_get, dict_get_subdict,
+    dict_insert, dict_load_from_root, dict_merge, dict_prove_key, dict_remove,
+    dict_split_by_prefix, dict_validate, dict_verify_key_proof, load_exact, serialize_entry,
+    DictBound, DictKey, SetMode,
+};
 
 /// Typed dictionary with fixed length keys.
 pub struct Dict<K, V> {
@@ -120,6 +129,104 @@ impl<K: DictKey, V> Dict<K, V> {
             Err(e) => Err(e),
         }
     }
+
+    /// Checks the structure of the dictionary cells and that
+    /// each value is fully loaded as `V` without any remaining data.
+    ///
+    /// Returns the key prefix of the first invalid node on failure.
+    pub fn validate(&self) -> Result<(), DictValidationError>
+    where
+        for<'a> V: Load<'a>,
+    {
+        dict_validate(&self.root, K::BITS, &mut load_exact::<V>)
+    }
+}
+
+impl<K, V> Dict<K, V>
+where
+    K: Store + DictKey,
+    V: Store,
+{
+    /// Builds a dictionary from entries sorted by key in strictly ascending order.
+    /// Each dictionary cell is finalized exactly once.
+    ///
+    /// Returns an error if keys are not sorted.
+    pub fn try_from_sorted_iter_ext<I>(
+        entries: I,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        match build_dict_from_sorted_iter(entries, K::BITS, finalizer) {
+            Ok(root) => Ok(Self::from(root)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Builds a dictionary from entries in any order.
+    /// Entries are sorted first, and the last value is used for duplicate keys.
+    pub fn try_from_iter_ext<I>(entries: I, finalizer: &mut dyn Finalizer) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut entries = {
+            let mut result = Vec::new();
+            for (key, value) in entries {
+                let key = ok!(serialize_entry(&key, &mut Cell::default_finalizer()));
+                result.push((key, value));
+            }
+            result
+        };
+
+        // Sort by key bits, keeping the order of equal keys
+        entries.sort_by(|(a, _), (b, _)| a.as_ref().data().cmp(b.as_ref().data()));
+
+        // Keep the last value for each key
+        entries.dedup_by(|next, prev| {
+            let is_same = next.0.as_ref().data() == prev.0.as_ref().data();
+            if is_same {
+                std::mem::swap(&mut next.1, &mut prev.1);
+            }
+            is_same
+        });
+
+        let mut sorted = Vec::with_capacity(entries.len());
+        for (key, value) in &entries {
+            sorted.push((ok!(key.as_slice()), value));
+        }
+
+        match build_dict_from_sorted_iter(sorted, K::BITS, finalizer) {
+            Ok(root) => Ok(Self::from(root)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Builds a dictionary from entries sorted by key in strictly ascending order.
+    /// Each dictionary cell is finalized exactly once.
+    ///
+    /// Use [`try_from_sorted_iter_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`try_from_sorted_iter_ext`]: Dict::try_from_sorted_iter_ext
+    pub fn try_from_sorted_iter<I>(entries: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        Self::try_from_sorted_iter_ext(entries, &mut Cell::default_finalizer())
+    }
+
+    /// Builds a dictionary from entries in any order.
+    /// Entries are sorted first, and the last value is used for duplicate keys.
+    ///
+    /// Use [`try_from_iter_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`try_from_iter_ext`]: Dict::try_from_iter_ext
+    pub fn try_from_iter<I>(entries: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        Self::try_from_iter_ext(entries, &mut Cell::default_finalizer())
+    }
 }
 
 impl<K, V> Dict<K, V>
@@ -166,6 +273,155 @@ where
     {
         self.get_raw_ext(key, &mut Cell::default_finalizer())
     }
+
+    /// Builds a Merkle proof with all cells on the lookup path of the key.
+    /// It proves either the value or the absence of the key.
+    ///
+    /// Fails with [`Error::EmptyProof`] if the dictionary is empty.
+    ///
+    /// Use [`prove_key_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`prove_key_ext`]: Dict::prove_key_ext
+    pub fn prove_key<Q>(&self, key: Q) -> Result<MerkleProof, Error>
+    where
+        Q: Borrow<K>,
+    {
+        self.prove_key_ext(key, &mut Cell::default_finalizer())
+    }
+
+    /// Returns the value corresponding to the key from the Merkle proof
+    /// of the dictionary with the specified root hash.
+    ///
+    /// Returns `None` if the proof shows that the key is absent.
+    /// Fails if the proof is for another dictionary or if it doesn't
+    /// contain the lookup path of the key.
+    ///
+    /// Key is serialized using the default finalizer.
+    pub fn verify_key_proof<'a, Q>(
+        root_hash: &HashBytes,
+        proof: &'a MerkleProof,
+        key: Q,
+    ) -> Result<Option<V>, Error>
+    where
+        Q: Borrow<K>,
+        V: Load<'a>,
+    {
+        let key = ok!(serialize_entry(
+            key.borrow(),
+            &mut Cell::default_finalizer()
+        ));
+        let key = ok!(key.as_ref().as_slice());
+        let Some(mut value) = ok!(dict_verify_key_proof(root_hash, proof, K::BITS, key)) else {
+            return Ok(None);
+        };
+
+        match V::load_from(&mut value) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<K, V> Dict<K, V>
+where
+    K: DictKey,
+{
+    /// Returns the lowest key and a value corresponding to the key.
+    ///
+    /// If `signed` is `true`, keys are compared as two's complement integers.
+    pub fn get_min<'a>(&'a self, signed: bool) -> Result<Option<(K, V)>, Error>
+    where
+        V: Load<'a>,
+    {
+        match dict_find_bound(&self.root, K::BITS, DictBound::Min, signed) {
+            Ok(entry) => load_entry(entry),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns the largest key and a value corresponding to the key.
+    ///
+    /// If `signed` is `true`, keys are compared as two's complement integers.
+    pub fn get_max<'a>(&'a self, signed: bool) -> Result<Option<(K, V)>, Error>
+    where
+        V: Load<'a>,
+    {
+        match dict_find_bound(&self.root, K::BITS, DictBound::Max, signed) {
+            Ok(entry) => load_entry(entry),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<K, V> Dict<K, V>
+where
+    K: Store + DictKey,
+{
+    /// Returns the closest key greater than the specified one
+    /// and a value corresponding to the key.
+    ///
+    /// Key is serialized using the default finalizer.
+    pub fn get_next<'a, Q>(&'a self, key: Q, signed: bool) -> Result<Option<(K, V)>, Error>
+    where
+        Q: Borrow<K>,
+        V: Load<'a>,
+    {
+        self.find_ext(key.borrow(), DictBound::Max, false, signed)
+    }
+
+    /// Returns the closest key less than the specified one
+    /// and a value corresponding to the key.
+    ///
+    /// Key is serialized using the default finalizer.
+    pub fn get_prev<'a, Q>(&'a self, key: Q, signed: bool) -> Result<Option<(K, V)>, Error>
+    where
+        Q: Borrow<K>,
+        V: Load<'a>,
+    {
+        self.find_ext(key.borrow(), DictBound::Min, false, signed)
+    }
+
+    /// Returns the specified key or the closest greater one
+    /// and a value corresponding to the key.
+    ///
+    /// Key is serialized using the default finalizer.
+    pub fn get_or_next<'a, Q>(&'a self, key: Q, signed: bool) -> Result<Option<(K, V)>, Error>
+    where
+        Q: Borrow<K>,
+        V: Load<'a>,
+    {
+        self.find_ext(key.borrow(), DictBound::Max, true, signed)
+    }
+
+    /// Returns the specified key or the closest less one
+    /// and a value corresponding to the key.
+    ///
+    /// Key is serialized using the default finalizer.
+    pub fn get_or_prev<'a, Q>(&'a self, key: Q, signed: bool) -> Result<Option<(K, V)>, Error>
+    where
+        Q: Borrow<K>,
+        V: Load<'a>,
+    {
+        self.find_ext(key.borrow(), DictBound::Min, true, signed)
+    }
+
+    fn find_ext<'a>(
+        &'a self,
+        key: &K,
+        towards: DictBound,
+        inclusive: bool,
+        signed: bool,
+    ) -> Result<Option<(K, V)>, Error>
+    where
+        V: Load<'a>,
+    {
+        let key = ok!(serialize_entry(key, &mut Cell::default_finalizer()));
+        let key = ok!(key.as_ref().as_slice());
+        match dict_find(&self.root, K::BITS, key, towards, inclusive, signed) {
+            Ok(entry) => load_entry(entry),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 impl<K, V> Dict<K, V>
@@ -213,6 +469,48 @@ where
     {
         self.add_ext(key, value, &mut Cell::default_finalizer())
     }
+
+    /// Gets the given key's corresponding entry in the dictionary
+    /// for in-place manipulation.
+    ///
+    /// Use [`entry_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`entry_ext`]: Dict::entry_ext
+    pub fn entry(&mut self, key: K) -> Result<Entry<'_, K, V>, Error> {
+        self.entry_ext(key, &mut Cell::default_finalizer())
+    }
+}
+
+impl<K, V> Dict<K, V>
+where
+    K: Store + DictKey,
+{
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value.
+    ///
+    /// Use [`remove_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`remove_ext`]: Dict::remove_ext
+    pub fn remove<Q>(&mut self, key: Q) -> Result<Option<V>, Error>
+    where
+        Q: Borrow<K>,
+        for<'a> V: Load<'a> + 'static,
+    {
+        self.remove_ext(key, &mut Cell::default_finalizer())
+    }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value as cell slice parts.
+    ///
+    /// Use [`remove_raw_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`remove_raw_ext`]: Dict::remove_raw_ext
+    pub fn remove_raw<Q>(&mut self, key: Q) -> Result<Option<CellSliceParts>, Error>
+    where
+        Q: Borrow<K>,
+    {
+        self.remove_raw_ext(key, &mut Cell::default_finalizer())
+    }
 }
 
 impl<K, V> Dict<K, V>
@@ -239,6 +537,23 @@ where
         Iter::new(&self.root)
     }
 
+    /// Gets an iterator over the differences between this dictionary
+    /// and the `other` one, sorted by key. This dictionary is treated
+    /// as the old version and the `other` one as the new version.
+    /// The iterator element type is `Result<DictDiffItem<K, V>>`.
+    ///
+    /// Identical subtrees are skipped by comparing their hashes,
+    /// so the cost is proportional to the size of the change.
+    ///
+    /// If any dictionary is invalid, finishes after the first invalid element,
+    /// returning an error.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> DictDiff<'a, K, V>
+    where
+        V: Load<'a>,
+    {
+        DictDiff::new(&self.root, &other.root)
+    }
+
     /// Gets an iterator over the keys of the dictionary, in sorted order.
     /// The iterator element type is `Result<K>`.
     ///
@@ -254,6 +569,73 @@ where
     pub fn keys(&'_ self) -> Keys<'_, K> {
         Keys::new(&self.root)
     }
+
+    /// Gets an iterator over a sub-range of entries of the dictionary,
+    /// sorted by key. The iterator element type is `Result<(K, V)>`.
+    ///
+    /// If `signed` is `true`, keys are compared as two's complement integers
+    /// (use it for signed key types like `i32`), and the iteration order is
+    /// also signed. Otherwise keys are compared by their serialized bits
+    /// (as big-endian unsigned integers).
+    /// The iterator seeks directly to the bounds, so creating it costs
+    /// only a single descent for each bound.
+    ///
+    /// Bounds are serialized using the default finalizer.
+    pub fn range<'a, R>(&'a self, range: R, signed: bool) -> Result<Iter<'a, K, V>, Error>
+    where
+        R: RangeBounds<K>,
+        V: Load<'a>,
+    {
+        fn range_impl<'a, K>(
+            root: &'a Option<Cell>,
+            start: Bound<&K>,
+            end: Bound<&K>,
+            signed: bool,
+        ) -> Result<RawIter<'a>, Error>
+        where
+            K: Store + DictKey,
+        {
+            let serialize_bound = |bound: Bound<&K>| -> Result<Option<Cell>, Error> {
+                match bound {
+                    Bound::Included(key) | Bound::Excluded(key) => {
+                        match serialize_entry(key, &mut Cell::default_finalizer()) {
+                            Ok(key) => Ok(Some(key)),
+                            Err(e) => Err(e),
+                        }
+                    }
+                    Bound::Unbounded => Ok(None),
+                }
+            };
+
+            let start_key = ok!(serialize_bound(start));
+            let end_key = ok!(serialize_bound(end));
+            let start_key = match &start_key {
+                Some(key) => Some(ok!(key.as_ref().as_slice())),
+                None => None,
+            };
+            let end_key = match &end_key {
+                Some(key) => Some(ok!(key.as_ref().as_slice())),
+                None => None,
+            };
+
+            RawIter::new_range(
+                root,
+                K::BITS,
+                replace_bound(start, start_key.as_ref()),
+                replace_bound(end, end_key.as_ref()),
+                signed,
+            )
+        }
+
+        match range_impl(&self.root, range.start_bound(), range.end_bound(), signed) {
+            Ok(inner) => Ok(Iter {
+                inner,
+                _key: PhantomData,
+                _value: PhantomData,
+            }),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 impl<K, V> Dict<K, V>
@@ -338,6 +720,23 @@ where
         get_raw_ext_impl(&self.root, key.borrow(), finalizer)
     }
 
+    /// Builds a Merkle proof with all cells on the lookup path of the key.
+    /// It proves either the value or the absence of the key.
+    ///
+    /// Key is serialized using the provided finalizer.
+    /// Fails with [`Error::EmptyProof`] if the dictionary is empty.
+    pub fn prove_key_ext<Q>(
+        &self,
+        key: Q,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<MerkleProof, Error>
+    where
+        Q: Borrow<K>,
+    {
+        let key = ok!(serialize_entry(key.borrow(), finalizer));
+        dict_prove_key(&self.root, K::BITS, ok!(key.as_ref().as_slice()), finalizer)
+    }
+
     /// Gets an iterator over the raw entries of the dictionary, sorted by key.
     /// The iterator element type is `Result<(CellBuilder, CellSlice)>`.
     ///
@@ -355,6 +754,16 @@ where
         RawIter::new(&self.root, K::BITS)
     }
 
+    /// Gets an iterator over the raw differences between this dictionary
+    /// and the `other` one, sorted by key.
+    /// The iterator element type is `Result<DictDiffItem<CellBuilder, CellSlice>>`.
+    ///
+    /// If any dictionary is invalid, finishes after the first invalid element,
+    /// returning an error.
+    pub fn raw_diff<'a>(&'a self, other: &'a Self) -> RawDictDiff<'a> {
+        RawDictDiff::new(&self.root, &other.root, K::BITS)
+    }
+
     /// Gets an iterator over the raw keys of the dictionary, in sorted order.
     /// The iterator element type is `Result<CellBuilder>`.
     ///
@@ -436,6 +845,17 @@ where
         self.insert_impl(key.borrow(), value.borrow(), SetMode::Add, finalizer)
     }
 
+    /// Gets the given key's corresponding entry in the dictionary
+    /// for in-place manipulation.
+    pub fn entry_ext(
+        &mut self,
+        key: K,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Entry<'_, K, V>, Error> {
+        let serialized_key = ok!(serialize_entry(&key, finalizer));
+        Entry::new(&mut self.root, key, serialized_key)
+    }
+
     fn insert_impl(
         &mut self,
         key: &K,
@@ -461,6 +881,166 @@ where
     }
 }
 
+impl<K, V> Dict<K, V>
+where
+    K: Store + DictKey,
+{
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value.
+    pub fn remove_ext<Q>(
+        &mut self,
+        key: Q,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Option<V>, Error>
+    where
+        Q: Borrow<K>,
+        for<'a> V: Load<'a> + 'static,
+    {
+        match ok!(self.remove_raw_ext(key, finalizer)) {
+            Some((cell, range)) => {
+                let mut slice = ok!(range.apply(cell.as_ref()));
+                match V::load_from(&mut slice) {
+                    Ok(value) => Ok(Some(value)),
+                    Err(e) => Err(e),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value as cell slice parts.
+    pub fn remove_raw_ext<Q>(
+        &mut self,
+        key: Q,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Option<CellSliceParts>, Error>
+    where
+        Q: Borrow<K>,
+    {
+        fn remove_raw_ext_impl<K>(
+            root: &mut Option<Cell>,
+            key: &K,
+            finalizer: &mut dyn Finalizer,
+        ) -> Result<Option<CellSliceParts>, Error>
+        where
+            K: Store + DictKey,
+        {
+            let key = ok!(serialize_entry(key, finalizer));
+            let (new_root, removed) = ok!(dict_remove(
+                root,
+                &mut ok!(key.as_ref().as_slice()),
+                K::BITS,
+                finalizer
+            ));
+            *root = new_root;
+            Ok(removed)
+        }
+
+        remove_raw_ext_impl(&mut self.root, key.borrow(), finalizer)
+    }
+}
+
+impl<K, V> Dict<K, V>
+where
+    K: DictKey,
+{
+    /// Splits the dictionary by the next bit after the key prefix.
+    /// Returns dictionaries with keys starting with `prefix + 0`
+    /// and `prefix + 1` respectively.
+    ///
+    /// All keys must start with the `prefix`, otherwise an error is returned.
+    pub fn split_by_prefix_ext(
+        &self,
+        prefix: &CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(Self, Self), Error> {
+        let (left, right) = ok!(dict_split_by_prefix(&self.root, K::BITS, prefix, finalizer));
+        Ok((Self::from(left), Self::from(right)))
+    }
+
+    /// Merges the other dictionary into this one, reusing disjoint subtrees.
+    /// Values from the `other` dictionary replace values with the same keys.
+    pub fn merge_ext(&mut self, other: &Self, finalizer: &mut dyn Finalizer) -> Result<(), Error> {
+        self.root = ok!(dict_merge(&self.root, &other.root, K::BITS, finalizer));
+        Ok(())
+    }
+
+    /// Returns a dictionary with all keys that start with the `prefix`.
+    /// The resulting dictionary shares cells with the original one.
+    pub fn get_subdict_ext(
+        &self,
+        prefix: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Self, Error> {
+        match dict_get_subdict(&self.root, K::BITS, &prefix, false, finalizer) {
+            Ok(root) => Ok(Self::from(root)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Splits the dictionary by the next bit after the key prefix.
+    /// Returns dictionaries with keys starting with `prefix + 0`
+    /// and `prefix + 1` respectively.
+    ///
+    /// Use [`split_by_prefix_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`split_by_prefix_ext`]: Dict::split_by_prefix_ext
+    pub fn split_by_prefix(&self, prefix: &CellSlice<'_>) -> Result<(Self, Self), Error> {
+        self.split_by_prefix_ext(prefix, &mut Cell::default_finalizer())
+    }
+
+    /// Merges the other dictionary into this one, reusing disjoint subtrees.
+    /// Values from the `other` dictionary replace values with the same keys.
+    ///
+    /// Use [`merge_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`merge_ext`]: Dict::merge_ext
+    pub fn merge(&mut self, other: &Self) -> Result<(), Error> {
+        self.merge_ext(other, &mut Cell::default_finalizer())
+    }
+
+    /// Returns a dictionary with all keys that start with the `prefix`.
+    /// The resulting dictionary shares cells with the original one.
+    ///
+    /// Use [`get_subdict_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`get_subdict_ext`]: Dict::get_subdict_ext
+    pub fn get_subdict(&self, prefix: CellSlice<'_>) -> Result<Self, Error> {
+        self.get_subdict_ext(prefix, &mut Cell::default_finalizer())
+    }
+}
+
+/// Replaces the value of the bound, keeping its kind.
+fn replace_bound<T, U>(bound: Bound<&T>, value: Option<U>) -> Bound<U> {
+    match (bound, value) {
+        (Bound::Included(_), Some(value)) => Bound::Included(value),
+        (Bound::Excluded(_), Some(value)) => Bound::Excluded(value),
+        _ => Bound::Unbounded,
+    }
+}
+
+fn load_entry<'a, K, V>(
+    entry: Option<(CellBuilder, CellSlice<'a>)>,
+) -> Result<Option<(K, V)>, Error>
+where
+    K: DictKey,
+    V: Load<'a>,
+{
+    match entry {
+        Some((key, mut value)) => {
+            let Some(key) = K::from_raw_data(key.raw_data()) else {
+                return Err(Error::CellUnderflow);
+            };
+            match V::load_from(&mut value) {
+                Ok(value) => Ok(Some((key, value))),
+                Err(e) => Err(e),
+            }
+        }
+        None => Ok(None),
+    }
+}
+
 /// An iterator over the entries of a [`Dict`].
 ///
 /// This struct is created by the [`iter`] method on [`Dict`]. See its documentation for more.
@@ -504,11 +1084,37 @@ where
     type Item = Result<(K, V), Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        Some(match self.inner.next()? {
+        let entry = self.inner.next()?;
+        Some(self.load_entry(entry))
+    }
+}
+
+impl<'a, K, V> DoubleEndedIterator for Iter<'a, K, V>
+where
+    K: DictKey,
+    V: Load<'a>,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let entry = self.inner.next_back()?;
+        Some(self.load_entry(entry))
+    }
+}
+
+impl<'a, K, V> Iter<'a, K, V>
+where
+    K: DictKey,
+    V: Load<'a>,
+{
+    #[inline]
+    fn load_entry(
+        &mut self,
+        entry: Result<(CellBuilder, CellSlice<'a>), Error>,
+    ) -> Result<(K, V), Error> {
+        match entry {
             Ok((key, mut value)) => {
                 let err = if let Some(key) = K::from_raw_data(key.raw_data()) {
                     match V::load_from(&mut value) {
-                        Ok(value) => return Some(Ok((key, value))),
+                        Ok(value) => return Ok((key, value)),
                         Err(e) => e,
                     }
                 } else {
@@ -517,7 +1123,7 @@ where
                 Err(self.inner.finish(err))
             }
             Err(e) => Err(e),
-        })
+        }
     }
 }
 
@@ -571,6 +1177,21 @@ where
     }
 }
 
+impl<'a, K> DoubleEndedIterator for Keys<'a, K>
+where
+    K: DictKey,
+{
+    fn next_back(&mut self) -> Option<Self::Item> {
+        Some(match self.inner.next_back()? {
+            Ok((key, _)) => match K::from_raw_data(key.raw_data()) {
+                Some(key) => Ok(key),
+                None => Err(self.inner.finish(Error::CellUnderflow)),
+            },
+            Err(e) => Err(e),
+        })
+    }
+}
+
 /// An iterator over the values of a [`Dict`].
 ///
 /// This struct is created by the [`values`] method on [`Dict`]. See its documentation for more.
@@ -664,6 +1285,421 @@ mod tests {
         assert_eq!(dict.get(123).unwrap(), Some(false));
     }
 
+    #[test]
+    fn dict_remove() {
+        let mut dict = Dict::<u32, u32>::new();
+
+        for i in 0..10 {
+            assert!(dict.set(i, i).is_ok());
+        }
+
+        let mut check_remove = |n: u32, expected: Option<u32>| {
+            let removed = dict.remove(n).unwrap();
+            assert_eq!(removed, expected);
+        };
+
+        check_remove(0, Some(0));
+
+        check_remove(4, Some(4));
+
+        check_remove(9, Some(9));
+        check_remove(9, None);
+
+        check_remove(5, Some(5));
+        check_remove(5, None);
+
+        check_remove(100, None);
+
+        check_remove(1, Some(1));
+        check_remove(2, Some(2));
+        check_remove(3, Some(3));
+        check_remove(6, Some(6));
+        check_remove(7, Some(7));
+        check_remove(8, Some(8));
+
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn dict_get_bounds() {
+        let mut dict = Dict::<i32, i32>::new();
+        assert_eq!(dict.get_min(true).unwrap(), None);
+
+        for i in -10..=10 {
+            dict.set(i, i * 10).unwrap();
+        }
+
+        assert_eq!(dict.get_min(false).unwrap(), Some((0, 0)));
+        assert_eq!(dict.get_max(false).unwrap(), Some((-1, -10)));
+        assert_eq!(dict.get_min(true).unwrap(), Some((-10, -100)));
+        assert_eq!(dict.get_max(true).unwrap(), Some((10, 100)));
+    }
+
+    #[test]
+    fn dict_get_nearest() {
+        let mut dict = Dict::<u32, u32>::new();
+        for i in [10, 20, 30, 40] {
+            dict.set(i, i * 10).unwrap();
+        }
+
+        assert_eq!(dict.get_next(10, false).unwrap(), Some((20, 200)));
+        assert_eq!(dict.get_next(15, false).unwrap(), Some((20, 200)));
+        assert_eq!(dict.get_next(40, false).unwrap(), None);
+        assert_eq!(dict.get_or_next(10, false).unwrap(), Some((10, 100)));
+        assert_eq!(dict.get_or_next(0, false).unwrap(), Some((10, 100)));
+
+        assert_eq!(dict.get_prev(20, false).unwrap(), Some((10, 100)));
+        assert_eq!(dict.get_prev(10, false).unwrap(), None);
+        assert_eq!(dict.get_or_prev(35, false).unwrap(), Some((30, 300)));
+        assert_eq!(dict.get_or_prev(u32::MAX, false).unwrap(), Some((40, 400)));
+
+        let mut dict = Dict::<i32, i32>::new();
+        for i in [-20, -10, 10, 20] {
+            dict.set(i, i).unwrap();
+        }
+
+        assert_eq!(dict.get_next(-10, true).unwrap(), Some((10, 10)));
+        assert_eq!(dict.get_prev(10, true).unwrap(), Some((-10, -10)));
+        assert_eq!(dict.get_next(-30, true).unwrap(), Some((-20, -20)));
+        assert_eq!(dict.get_prev(-20, true).unwrap(), None);
+        assert_eq!(dict.get_or_prev(0, true).unwrap(), Some((-10, -10)));
+        assert_eq!(dict.get_next(20, true).unwrap(), None);
+
+        // Unsigned order places negative keys after positive ones
+        assert_eq!(dict.get_next(20, false).unwrap(), Some((-20, -20)));
+        assert_eq!(dict.get_prev(-20, false).unwrap(), Some((20, 20)));
+    }
+
+    #[test]
+    fn dict_from_iter() -> anyhow::Result<()> {
+        let mut expected = Dict::<u16, u32>::new();
+        for i in 0..1000u16 {
+            expected.set(i.wrapping_mul(7919), i as u32)?;
+        }
+
+        let mut entries = (0..1000u16)
+            .map(|i| (i.wrapping_mul(7919), i as u32))
+            .collect::<Vec<_>>();
+
+        // Duplicates are replaced by the last value
+        let dict = Dict::<u16, u32>::try_from_iter(
+            entries
+                .iter()
+                .map(|&(key, _)| (key, 0))
+                .chain(entries.iter().copied()),
+        )?;
+        assert_eq!(dict, expected);
+
+        entries.sort_unstable();
+        let dict = Dict::<u16, u32>::try_from_sorted_iter(entries.iter().copied())?;
+        assert_eq!(dict, expected);
+
+        entries.reverse();
+        assert!(Dict::<u16, u32>::try_from_sorted_iter(entries).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_get_subdict() -> anyhow::Result<()> {
+        let mut dict = Dict::<u32, u16>::new();
+        for i in 0..100u16 {
+            dict.set(i as u32, i)?;
+        }
+
+        // All entries in [32; 48)
+        let mut prefix = CellBuilder::new();
+        prefix.store_uint(32 >> 4, 28)?;
+        let prefix = prefix.build()?;
+
+        let subdict = dict.get_subdict(prefix.as_slice()?)?;
+        let entries = subdict.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries, (32..48).map(|i| (i as u32, i)).collect::<Vec<_>>());
+
+        // No entries
+        let mut prefix = CellBuilder::new();
+        prefix.store_bit_one()?;
+        let prefix = prefix.build()?;
+        assert!(dict.get_subdict(prefix.as_slice()?)?.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_split_merge() -> anyhow::Result<()> {
+        let mut dict = Dict::<u16, u32>::new();
+        for i in 0..100u16 {
+            dict.set(i * 650, i as u32)?;
+        }
+
+        let (left, right) = dict.split_by_prefix(&Cell::empty_cell_ref().as_slice()?)?;
+        for entry in left.iter() {
+            let (key, value) = entry?;
+            assert!(key < 0x8000);
+            assert_eq!(key, value as u16 * 650);
+        }
+        for entry in right.iter() {
+            let (key, value) = entry?;
+            assert!(key >= 0x8000);
+            assert_eq!(key, value as u16 * 650);
+        }
+        assert_eq!(left.values().count() + right.values().count(), 100);
+
+        let mut merged = right.clone();
+        merged.merge(&left)?;
+        assert_eq!(merged, dict);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_prove_key() -> anyhow::Result<()> {
+        let mut dict = Dict::<u32, u64>::new();
+        assert_eq!(dict.prove_key(0).unwrap_err(), Error::EmptyProof);
+
+        for i in 0..200u32 {
+            dict.set(i * 1000, i as u64 * 10)?;
+        }
+        let root_hash = *dict.root().as_ref().unwrap().repr_hash();
+
+        // Inclusion
+        let proof = dict.prove_key(5000)?;
+        assert_eq!(proof.hash, root_hash);
+        let value = Dict::<u32, u64>::verify_key_proof(&root_hash, &proof, 5000)?;
+        assert_eq!(value, Some(50));
+
+        // Proof contains only the lookup path
+        assert_eq!(
+            Dict::<u32, u64>::verify_key_proof(&root_hash, &proof, 150000).unwrap_err(),
+            Error::PrunedBranchAccess
+        );
+
+        // Serialization roundtrip
+        let proof_cell = CellBuilder::build_from(&proof)?;
+        let proof = proof_cell.parse::<MerkleProof>()?;
+        let value = Dict::<u32, u64>::verify_key_proof(&root_hash, &proof, 5000)?;
+        assert_eq!(value, Some(50));
+
+        // Exclusion
+        for key in [5001, 0xffffffff, 1] {
+            let proof = dict.prove_key(key)?;
+            let value = Dict::<u32, u64>::verify_key_proof(&root_hash, &proof, key)?;
+            assert_eq!(value, None);
+        }
+
+        // Proof for another dictionary
+        let other_hash = HashBytes([0x55; 32]);
+        assert_eq!(
+            Dict::<u32, u64>::verify_key_proof(&other_hash, &proof, 5000).unwrap_err(),
+            Error::InvalidData
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_validate() -> anyhow::Result<()> {
+        let mut dict = Dict::<u32, u64>::new();
+        for i in 0..100u32 {
+            dict.set(i * 1234567, i as u64)?;
+        }
+        dict.validate()?;
+
+        // Values with remaining data
+        let dict = Dict::<u32, u32>::from(dict.root().clone());
+        let e = dict.validate().unwrap_err();
+        assert_eq!(e.error, Error::InvalidData);
+
+        // Values with not enough data
+        let dict = Dict::<u32, HashBytes>::from(dict.root().clone());
+        let e = dict.validate().unwrap_err();
+        assert_eq!(e.error, Error::CellUnderflow);
+        assert_eq!(e.key_prefix, [0; 4]);
+        assert_eq!(e.key_prefix_bit_len, 32);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_composite_keys() -> anyhow::Result<()> {
+        use crate::num::{Uint12, Uint9};
+
+        fn check_keys<K>(keys: Vec<K>) -> anyhow::Result<()>
+        where
+            K: Store + DictKey + Eq + Ord + std::fmt::Debug,
+        {
+            let mut dict = Dict::<K, u32>::new();
+            for (i, key) in keys.iter().enumerate() {
+                dict.set(key, i as u32)?;
+            }
+            for (i, key) in keys.iter().enumerate() {
+                assert_eq!(dict.get(key)?, Some(i as u32));
+            }
+
+            let mut sorted = dict.keys().collect::<Result<Vec<_>, _>>()?;
+            sorted.sort();
+            let mut expected = keys;
+            expected.sort();
+            assert_eq!(sorted, expected);
+            Ok(())
+        }
+
+        check_keys(vec![
+            (-1i32, HashBytes([0x11; 32])),
+            (0, HashBytes([0x22; 32])),
+            (0, HashBytes([0x33; 32])),
+            (1, HashBytes::ZERO),
+        ])?;
+
+        check_keys(vec![
+            (Uint9::new(0x1ff), Uint12::new(1), true),
+            (Uint9::new(0), Uint12::new(0xfff), false),
+            (Uint9::new(123), Uint12::new(0xabc), true),
+        ])?;
+
+        #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Store, Load, DictKey)]
+        #[tlb(tag = "#a")]
+        struct TaggedKey {
+            flag: bool,
+            id: u16,
+            lt: u64,
+        }
+
+        assert_eq!(<TaggedKey as DictKey>::BITS, 4 + 1 + 16 + 64);
+        check_keys(vec![
+            TaggedKey {
+                flag: true,
+                id: 1,
+                lt: 1000,
+            },
+            TaggedKey {
+                flag: false,
+                id: 0xffff,
+                lt: 0,
+            },
+        ])?;
+
+        // Keys with an invalid tag are not parsed
+        let mut raw_data = [0u8; 128];
+        raw_data[0] = 0xb0;
+        assert_eq!(TaggedKey::from_raw_data(&raw_data), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_entry() -> anyhow::Result<()> {
+        let keys = [5u32, 3, 5, 1000, 7, 3, 5, 0xffffffff, 0, 1000];
+
+        let mut dict = Dict::<u32, u16>::new();
+        let mut expected = Dict::<u32, u16>::new();
+        for key in keys {
+            let count = dict
+                .entry(key)?
+                .and_modify(|count| *count += 1)?
+                .or_insert_with(|| 1)?;
+
+            let prev = expected.get(key)?.unwrap_or_default();
+            expected.set(key, prev + 1)?;
+            assert_eq!(count, prev + 1);
+            assert_eq!(dict, expected);
+        }
+        assert_eq!(dict.get(5)?, Some(3));
+        assert_eq!(dict.get(0)?, Some(1));
+
+        match dict.entry(1000)? {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.get()?, 2);
+                assert_eq!(entry.insert(10)?, 2);
+                assert_eq!(entry.insert(20)?, 10);
+                assert_eq!(entry.remove()?, 20);
+            }
+            Entry::Vacant(_) => panic!("entry must be occupied"),
+        }
+        expected.remove(1000)?;
+        assert_eq!(dict, expected);
+
+        match dict.entry(123)? {
+            Entry::Vacant(entry) => entry.insert(321)?,
+            Entry::Occupied(_) => panic!("entry must be vacant"),
+        }
+        expected.set(123, 321)?;
+        assert_eq!(dict, expected);
+
+        for key in [0, 3, 5, 7, 123, 0xffffffff] {
+            let Entry::Occupied(entry) = dict.entry(key)? else {
+                panic!("entry must be occupied");
+            };
+            assert_eq!(entry.remove()?, expected.remove(key)?.unwrap());
+            assert_eq!(dict, expected);
+        }
+        assert!(dict.is_empty());
+
+        assert_eq!(dict.entry(10)?.or_default()?, 0);
+        assert_eq!(dict.get(10)?, Some(0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn dict_range() {
+        let mut dict = Dict::<u32, u32>::new();
+        for i in 0..100 {
+            dict.set(i * 2, i).unwrap();
+        }
+
+        let keys =
+            |iter: Iter<'_, u32, u32>| iter.map(|entry| entry.unwrap().0).collect::<Vec<_>>();
+
+        assert_eq!(
+            keys(dict.range(10..20, false).unwrap()),
+            [10, 12, 14, 16, 18]
+        );
+        assert_eq!(
+            keys(dict.range(11..=20, false).unwrap()),
+            [12, 14, 16, 18, 20]
+        );
+        assert_eq!(
+            keys(dict.range(190.., false).unwrap()),
+            [190, 192, 194, 196, 198]
+        );
+        assert_eq!(keys(dict.range(..5, false).unwrap()), [0, 2, 4]);
+        assert!(keys(dict.range(200.., false).unwrap()).is_empty());
+        assert_eq!(dict.range(.., false).unwrap().count(), 100);
+
+        let rev = dict.range(10..20, false).unwrap().rev();
+        let rev = rev.map(|entry| entry.unwrap().0).collect::<Vec<_>>();
+        assert_eq!(rev, [18, 16, 14, 12, 10]);
+
+        let mut iter = dict.iter();
+        assert_eq!(iter.next().unwrap().unwrap(), (0, 0));
+        assert_eq!(iter.next_back().unwrap().unwrap(), (198, 99));
+        assert_eq!(iter.count(), 98);
+
+        // Signed keys
+        let mut dict = Dict::<i32, i32>::new();
+        for i in -10..10 {
+            dict.set(i, i).unwrap();
+        }
+
+        let keys =
+            |iter: Iter<'_, i32, i32>| iter.map(|entry| entry.unwrap().0).collect::<Vec<_>>();
+
+        assert_eq!(keys(dict.range(-2..2, true).unwrap()), [-2, -1, 0, 1]);
+        assert_eq!(keys(dict.range(..-8, true).unwrap()), [-10, -9]);
+        assert_eq!(keys(dict.range(8.., true).unwrap()), [8, 9]);
+        assert_eq!(keys(dict.range(-5..-3, true).unwrap()), [-5, -4]);
+        assert_eq!(
+            keys(dict.range(.., true).unwrap()),
+            (-10..10).collect::<Vec<_>>()
+        );
+        assert!(keys(dict.range(-2..2, false).unwrap()).is_empty());
+
+        let rev = dict.range(-2..=1, true).unwrap().rev();
+        let rev = rev.map(|entry| entry.unwrap().0).collect::<Vec<_>>();
+        assert_eq!(rev, [1, 0, -1, -2]);
+    }
+
     #[test]
     fn dict_iter() {
         let boc = Boc::decode_base64("te6ccgEBFAEAeAABAcABAgPOQAUCAgHUBAMACQAAAI3gAAkAAACjoAIBIA0GAgEgCgcCASAJCAAJAAAAciAACQAAAIfgAgEgDAsACQAAAFZgAAkAAABsIAIBIBEOAgEgEA8ACQAAADqgAAkAAABQYAIBIBMSAAkAAAAe4AAJAAAAv2A=").unwrap();
@@ -0,0 +1,969 @@
+use crate::cell::*;
+use crate::error::Error;
+use crate::util::{unlikely, IterStatus};
+
+use super::{read_label, replace_label, write_label, Branch, SetMode};
+
+/// Dictionary with variable length keys (where `N` is a max number of bits in each key).
+///
+/// Unlike [`PfxDict`], keys can be prefixes of other keys,
+/// so values are also stored in the internal nodes.
+///
+/// [`PfxDict`]: crate::dict::PfxDict
+///
+/// # TLB scheme
+///
+/// ```text
+/// vhm_edge#_ {n:#} {X:Type} {l:#} {m:#} label:(HmLabel ~l n)
+///            {n = (~m) + l} node:(VarHashmapNode m X) = VarHashmap n X;
+///
+/// vhmn_leaf$00 {n:#} {X:Type} value:X = VarHashmapNode n X;
+/// vhmn_fork$01 {n:#} {X:Type} left:^(VarHashmap n X)
+///              right:^(VarHashmap n X) value:(Maybe X) = VarHashmapNode (n + 1) X;
+/// vhmn_cont$1 {n:#} {X:Type} branch:Bit child:^(VarHashmap n X)
+///             value:X = VarHashmapNode (n + 1) X;
+///
+/// vhme_empty$0 {n:#} {X:Type} = VarHashmapE n X;
+/// vhme_root$1 {n:#} {X:Type} root:^(VarHashmap n X) = VarHashmapE n X;
+/// ```
+pub struct VarDict<const N: u16>(pub(crate) Option<Cell>);
+
+impl<'a, const N: u16> Load<'a> for VarDict<N> {
+    #[inline]
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match <_>::load_from(slice) {
+            Ok(dict) => Ok(Self(dict)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<const N: u16> Store for VarDict<N> {
+    #[inline]
+    fn store_into(
+        &self,
+        builder: &mut CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        self.0.store_into(builder, finalizer)
+    }
+}
+
+impl<const N: u16> Default for VarDict<N> {
+    #[inline]
+    fn default() -> Self {
+        Self(None)
+    }
+}
+
+impl<const N: u16> Clone for VarDict<N> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<const N: u16> Eq for VarDict<N> {}
+impl<const N: u16> PartialEq for VarDict<N> {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(this), Some(other)) => this.as_ref() == other.as_ref(),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<const N: u16> From<Option<Cell>> for VarDict<N> {
+    #[inline]
+    fn from(value: Option<Cell>) -> Self {
+        Self(value)
+    }
+}
+
+impl<const N: u16> std::fmt::Debug for VarDict<N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VarDict")
+            .field("max_key_bit_len", &N)
+            .field("root", &self.0)
+            .finish()
+    }
+}
+
+impl<const N: u16> VarDict<N> {
+    /// Creates an empty dictionary.
+    pub const fn new() -> Self {
+        Self(None)
+    }
+
+    /// Returns `true` if the dictionary contains no elements.
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_none()
+    }
+
+    /// Returns the underlying root cell of the dictionary.
+    #[inline]
+    pub const fn root(&self) -> &Option<Cell> {
+        &self.0
+    }
+
+    /// Returns a `CellSlice` of the value corresponding to the key.
+    pub fn get<'a: 'b, 'b>(&'a self, key: CellSlice<'b>) -> Result<Option<CellSlice<'a>>, Error> {
+        var_dict_get(&self.0, N, key)
+    }
+
+    /// Returns `true` if the dictionary contains a value for the specified key.
+    pub fn contains_key(&self, key: CellSlice<'_>) -> Result<bool, Error> {
+        Ok(ok!(var_dict_get(&self.0, N, key)).is_some())
+    }
+
+    /// Sets the value associated with the key in the dictionary.
+    pub fn set_ext(
+        &mut self,
+        key: CellSlice<'_>,
+        value: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        self.0 = ok!(var_dict_insert(
+            &self.0,
+            key,
+            N,
+            &value,
+            SetMode::Set,
+            finalizer
+        ));
+        Ok(())
+    }
+
+    /// Sets the value associated with the key in the dictionary
+    /// only if the key was already present in it.
+    pub fn replace_ext(
+        &mut self,
+        key: CellSlice<'_>,
+        value: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        self.0 = ok!(var_dict_insert(
+            &self.0,
+            key,
+            N,
+            &value,
+            SetMode::Replace,
+            finalizer
+        ));
+        Ok(())
+    }
+
+    /// Sets the value associated with key in dictionary,
+    /// but only if it is not already present.
+    pub fn add_ext(
+        &mut self,
+        key: CellSlice<'_>,
+        value: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        self.0 = ok!(var_dict_insert(
+            &self.0,
+            key,
+            N,
+            &value,
+            SetMode::Add,
+            finalizer
+        ));
+        Ok(())
+    }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value as cell slice parts.
+    pub fn remove_ext(
+        &mut self,
+        key: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Option<CellSliceParts>, Error> {
+        let (root, removed) = ok!(var_dict_remove(&self.0, key, N, finalizer));
+        self.0 = root;
+        Ok(removed)
+    }
+
+    /// Gets an iterator over the entries of the dictionary.
+    /// The iterator element type is `Result<(CellBuilder, CellSlice)>`.
+    ///
+    /// Entries are sorted by key, and each key goes before
+    /// all keys for which it is a prefix.
+    ///
+    /// If the dictionary is invalid, finishes after the first invalid element,
+    /// returning an error.
+    pub fn iter(&'_ self) -> VarIter<'_> {
+        VarIter::new(&self.0, N)
+    }
+
+    /// Sets the value associated with the key in the dictionary.
+    ///
+    /// Use [`set_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`set_ext`]: VarDict::set_ext
+    pub fn set(&mut self, key: CellSlice<'_>, value: CellSlice<'_>) -> Result<(), Error> {
+        self.set_ext(key, value, &mut Cell::default_finalizer())
+    }
+
+    /// Sets the value associated with the key in the dictionary
+    /// only if the key was already present in it.
+    ///
+    /// Use [`replace_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`replace_ext`]: VarDict::replace_ext
+    pub fn replace(&mut self, key: CellSlice<'_>, value: CellSlice<'_>) -> Result<(), Error> {
+        self.replace_ext(key, value, &mut Cell::default_finalizer())
+    }
+
+    /// Sets the value associated with key in dictionary,
+    /// but only if it is not already present.
+    ///
+    /// Use [`add_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`add_ext`]: VarDict::add_ext
+    pub fn add(&mut self, key: CellSlice<'_>, value: CellSlice<'_>) -> Result<(), Error> {
+        self.add_ext(key, value, &mut Cell::default_finalizer())
+    }
+
+    /// Removes the value associated with the key in the dictionary.
+    /// Returns an optional removed value as cell slice parts.
+    ///
+    /// Use [`remove_ext`] if you need to use a custom finalizer.
+    ///
+    /// [`remove_ext`]: VarDict::remove_ext
+    pub fn remove(&mut self, key: CellSlice<'_>) -> Result<Option<CellSliceParts>, Error> {
+        self.remove_ext(key, &mut Cell::default_finalizer())
+    }
+}
+
+/// Returns a `CellSlice` of the value corresponding to the key
+/// in the dictionary with variable length keys.
+pub fn var_dict_get<'a: 'b, 'b>(
+    root: &'a Option<Cell>,
+    key_bit_len: u16,
+    mut key: CellSlice<'b>,
+) -> Result<Option<CellSlice<'a>>, Error> {
+    if key.remaining_bits() > key_bit_len {
+        return Ok(None);
+    }
+
+    let Some(root) = root.as_ref() else {
+        return Ok(None);
+    };
+
+    let mut node = ok!(VarNode::load(root.as_ref(), key_bit_len));
+    loop {
+        // Remove the node label from the key
+        key = match key.strip_data_prefix(&node.label) {
+            Some(stripped_key) => stripped_key,
+            None => return Ok(None),
+        };
+
+        if key.is_data_empty() {
+            return Ok(node.value);
+        }
+
+        // Load next child based on the next bit
+        let branch = match ok!(key.load_bit()) {
+            false => Branch::Left,
+            true => Branch::Right,
+        };
+        node = match node.child(branch) {
+            Some(child) => ok!(VarNode::load(child, node.children_key_bit_len())),
+            None => return Ok(None),
+        };
+    }
+}
+
+/// Inserts the value associated with key in dictionary with variable
+/// length keys in accordance with the logic of the specified [`SetMode`].
+pub fn var_dict_insert(
+    root: &Option<Cell>,
+    key: CellSlice<'_>,
+    key_bit_len: u16,
+    value: &CellSlice<'_>,
+    mode: SetMode,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Option<Cell>, Error> {
+    fn insert_impl(
+        node: &DynCell,
+        key_bit_len: u16,
+        mut key: CellSlice<'_>,
+        value: &CellSlice<'_>,
+        mode: SetMode,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Option<Cell>, Error> {
+        let node_info = ok!(VarNode::load(node, key_bit_len));
+        let label = node_info.label;
+
+        // Split the edge if the key diverges from the label or ends within it
+        let lcp = key.longest_common_data_prefix(&label);
+        let lcp_len = lcp.remaining_bits();
+        if lcp_len < label.remaining_bits() {
+            if !mode.can_add() {
+                return Ok(None);
+            }
+
+            let child_key_bit_len = key_bit_len - lcp_len - 1;
+            let old = ok!(replace_label(
+                node,
+                key_bit_len,
+                &CellBuilder::new(),
+                lcp_len + 1,
+                child_key_bit_len,
+                finalizer
+            ));
+            let old_branch = match ok!(label.get_bit(lcp_len)) {
+                false => Branch::Left,
+                true => Branch::Right,
+            };
+
+            let node = if lcp_len == key.remaining_bits() {
+                ok!(build_node(
+                    &key,
+                    key_bit_len,
+                    VarChildren::Cont(old_branch, old),
+                    Some(value),
+                    finalizer
+                ))
+            } else {
+                key.try_advance(lcp_len + 1, 0);
+                let new = ok!(build_node(
+                    &key,
+                    child_key_bit_len,
+                    VarChildren::None,
+                    Some(value),
+                    finalizer
+                ));
+                let children = match old_branch {
+                    Branch::Left => VarChildren::Fork(old, new),
+                    Branch::Right => VarChildren::Fork(new, old),
+                };
+                ok!(build_node(&lcp, key_bit_len, children, None, finalizer))
+            };
+            return Ok(Some(node));
+        }
+
+        // The key contains the entire label
+        key.try_advance(lcp_len, 0);
+
+        // Set the value of the current node
+        if key.is_data_empty() {
+            let allowed = match node_info.value {
+                Some(_) => mode.can_replace(),
+                None => mode.can_add(),
+            };
+            if !allowed {
+                return Ok(None);
+            }
+
+            let children = ok!(node_info.children());
+            return match build_node(&label, key_bit_len, children, Some(value), finalizer) {
+                Ok(node) => Ok(Some(node)),
+                Err(e) => Err(e),
+            };
+        }
+
+        let branch = match ok!(key.load_bit()) {
+            false => Branch::Left,
+            true => Branch::Right,
+        };
+        let child_key_bit_len = node_info.children_key_bit_len();
+
+        let children = match node_info.child(branch) {
+            // Insert the value into the existing child
+            Some(child) => {
+                match ok!(insert_impl(
+                    child,
+                    child_key_bit_len,
+                    key,
+                    value,
+                    mode,
+                    finalizer
+                )) {
+                    Some(child) => ok!(node_info.children_with(branch, child)),
+                    None => return Ok(None),
+                }
+            }
+            // Add a new child
+            None => {
+                if !mode.can_add() {
+                    return Ok(None);
+                }
+
+                let new = ok!(build_node(
+                    &key,
+                    child_key_bit_len,
+                    VarChildren::None,
+                    Some(value),
+                    finalizer
+                ));
+                ok!(node_info.children_with(branch, new))
+            }
+        };
+
+        match build_node(
+            &label,
+            key_bit_len,
+            children,
+            node_info.value.as_ref(),
+            finalizer,
+        ) {
+            Ok(node) => Ok(Some(node)),
+            Err(e) => Err(e),
+        }
+    }
+
+    if key.remaining_bits() > key_bit_len {
+        return Err(Error::CellUnderflow);
+    }
+
+    let Some(root_cell) = root.as_ref() else {
+        if !mode.can_add() {
+            return Ok(None);
+        }
+        return match build_node(&key, key_bit_len, VarChildren::None, Some(value), finalizer) {
+            Ok(root) => Ok(Some(root)),
+            Err(e) => Err(e),
+        };
+    };
+
+    match ok!(insert_impl(
+        root_cell.as_ref(),
+        key_bit_len,
+        key,
+        value,
+        mode,
+        finalizer
+    )) {
+        Some(root) => Ok(Some(root)),
+        None => Ok(root.clone()),
+    }
+}
+
+/// Removes the value associated with key in dictionary with variable length keys.
+///
+/// Returns the new dictionary root and the removed value (if any).
+pub fn var_dict_remove(
+    root: &Option<Cell>,
+    key: CellSlice<'_>,
+    key_bit_len: u16,
+    finalizer: &mut dyn Finalizer,
+) -> Result<(Option<Cell>, Option<CellSliceParts>), Error> {
+    // Returns the new node (if any) and the removed value,
+    // or `None` if the key was not found
+    #[allow(clippy::type_complexity)]
+    fn remove_impl(
+        node: &Cell,
+        key_bit_len: u16,
+        mut key: CellSlice<'_>,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Option<(Option<Cell>, CellSliceParts)>, Error> {
+        let node_info = ok!(VarNode::load(node.as_ref(), key_bit_len));
+        let label = node_info.label;
+
+        // Remove the node label from the key
+        let lcp = key.longest_common_data_prefix(&label);
+        if lcp.remaining_bits() != label.remaining_bits() {
+            return Ok(None);
+        }
+        key.try_advance(lcp.remaining_bits(), 0);
+
+        // Remove the value of the current node
+        if key.is_data_empty() {
+            let Some(value) = node_info.value else {
+                return Ok(None);
+            };
+            let removed = (node.clone(), value.range());
+
+            let node = match node_info.kind {
+                VarNodeKind::Leaf => None,
+                VarNodeKind::Fork => {
+                    let children = ok!(node_info.children());
+                    Some(ok!(build_node(
+                        &label,
+                        key_bit_len,
+                        children,
+                        None,
+                        finalizer
+                    )))
+                }
+                VarNodeKind::Cont(branch) => Some(ok!(join_child(&node_info, branch, finalizer))),
+            };
+            return Ok(Some((node, removed)));
+        }
+
+        let branch = match ok!(key.load_bit()) {
+            false => Branch::Left,
+            true => Branch::Right,
+        };
+
+        let child = match node_info.child_index(branch) {
+            Some(index) => match node_info.cell.reference_cloned(index) {
+                Some(cell) => cell,
+                None => return Err(Error::CellUnderflow),
+            },
+            None => return Ok(None),
+        };
+
+        let Some((child, removed)) = ok!(remove_impl(
+            &child,
+            node_info.children_key_bit_len(),
+            key,
+            finalizer
+        )) else {
+            return Ok(None);
+        };
+
+        let node = match (child, node_info.kind) {
+            // Replace the child
+            (Some(child), _) => {
+                let children = ok!(node_info.children_with(branch, child));
+                ok!(build_node(
+                    &label,
+                    key_bit_len,
+                    children,
+                    node_info.value.as_ref(),
+                    finalizer
+                ))
+            }
+            // Keep the remaining branch of the fork with the value
+            (None, VarNodeKind::Fork) if node_info.value.is_some() => {
+                let opposite = branch.opposite();
+                let other = match node_info.cell.reference_cloned(opposite as u8) {
+                    Some(cell) => cell,
+                    None => return Err(Error::CellUnderflow),
+                };
+                ok!(build_node(
+                    &label,
+                    key_bit_len,
+                    VarChildren::Cont(opposite, other),
+                    node_info.value.as_ref(),
+                    finalizer
+                ))
+            }
+            // Join the fork label with the label of the remaining branch
+            (None, VarNodeKind::Fork) => ok!(join_child(&node_info, branch.opposite(), finalizer)),
+            // Node without children becomes a leaf
+            (None, _) => ok!(build_node(
+                &label,
+                key_bit_len,
+                VarChildren::None,
+                node_info.value.as_ref(),
+                finalizer
+            )),
+        };
+        Ok(Some((Some(node), removed)))
+    }
+
+    // Replaces the node with its child, joining their labels
+    fn join_child(
+        node: &VarNode<'_>,
+        branch: Branch,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<Cell, Error> {
+        let Some(child) = node.child(branch) else {
+            return Err(Error::CellUnderflow);
+        };
+
+        let mut prefix = CellBuilder::new();
+        ok!(prefix.store_slice_data(node.label));
+        ok!(prefix.store_bit(branch == Branch::Right));
+
+        replace_label(
+            child,
+            node.children_key_bit_len(),
+            &prefix,
+            0,
+            node.key_bit_len,
+            finalizer,
+        )
+    }
+
+    if key.remaining_bits() > key_bit_len {
+        return Ok((root.clone(), None));
+    }
+
+    let Some(root_cell) = root.as_ref() else {
+        return Ok((None, None));
+    };
+
+    match ok!(remove_impl(root_cell, key_bit_len, key, finalizer)) {
+        Some((root, removed)) => Ok((root, Some(removed))),
+        None => Ok((root.clone(), None)),
+    }
+}
+
+/// An iterator over the entries of a [`VarDict`].
+///
+/// This struct is created by the [`iter`] method on [`VarDict`].
+/// See its documentation for more.
+///
+/// [`iter`]: VarDict::iter
+#[derive(Clone)]
+pub struct VarIter<'a> {
+    segments: Vec<VarIterSegment<'a>>,
+    status: IterStatus,
+}
+
+impl<'a> VarIter<'a> {
+    /// Creates an iterator over the entries of a dictionary.
+    pub fn new(root: &'a Option<Cell>, bit_len: u16) -> Self {
+        let mut segments = Vec::new();
+        if let Some(root) = root {
+            segments.push(VarIterSegment {
+                data: root.as_ref(),
+                remaining_bit_len: bit_len,
+                key: CellBuilder::new(),
+            });
+        }
+        Self {
+            segments,
+            status: IterStatus::Valid,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn finish(&mut self, err: Error) -> Error {
+        self.status = IterStatus::Broken;
+        err
+    }
+
+    fn process_segment(
+        &mut self,
+        segment: VarIterSegment<'a>,
+    ) -> Result<Option<(CellBuilder, CellSlice<'a>)>, Error> {
+        let node = ok!(VarNode::load(segment.data, segment.remaining_bit_len));
+
+        let mut key = segment.key;
+        ok!(key.store_slice_data(node.label));
+
+        // Push the right child first to visit the left one earlier
+        for branch in [Branch::Right, Branch::Left] {
+            if let Some(child) = node.child(branch) {
+                let mut key = key.clone();
+                ok!(key.store_bit(branch == Branch::Right));
+                self.segments.push(VarIterSegment {
+                    data: child,
+                    remaining_bit_len: node.children_key_bit_len(),
+                    key,
+                });
+            }
+        }
+
+        Ok(node.value.map(|value| (key, value)))
+    }
+}
+
+impl<'a> Iterator for VarIter<'a> {
+    type Item = Result<(CellBuilder, CellSlice<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if unlikely(!self.status.is_valid()) {
+            return None;
+        }
+
+        while let Some(segment) = self.segments.pop() {
+            match self.process_segment(segment) {
+                Ok(Some(item)) => return Some(Ok(item)),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(self.finish(e))),
+            }
+        }
+
+        None
+    }
+}
+
+#[derive(Clone)]
+struct VarIterSegment<'a> {
+    data: &'a DynCell,
+    remaining_bit_len: u16,
+    key: CellBuilder,
+}
+
+/// Parsed node of a dictionary with variable length keys.
+struct VarNode<'a> {
+    cell: &'a DynCell,
+    /// Remaining key length at the node.
+    key_bit_len: u16,
+    label: CellSlice<'a>,
+    kind: VarNodeKind,
+    value: Option<CellSlice<'a>>,
+}
+
+#[derive(Clone, Copy)]
+enum VarNodeKind {
+    Leaf,
+    Fork,
+    Cont(Branch),
+}
+
+/// Children of a new node.
+enum VarChildren {
+    None,
+    Fork(Cell, Cell),
+    Cont(Branch, Cell),
+}
+
+impl<'a> VarNode<'a> {
+    fn load(cell: &'a DynCell, key_bit_len: u16) -> Result<Self, Error> {
+        let mut data = ok!(cell.as_slice());
+        let label = ok!(read_label(&mut data, key_bit_len));
+        let has_children = label.remaining_bits() < key_bit_len;
+
+        let (kind, value) = if !ok!(data.load_bit()) {
+            if !ok!(data.load_bit()) {
+                (VarNodeKind::Leaf, Some(data))
+            } else {
+                if !has_children || !data.try_advance(0, 2) {
+                    return Err(Error::CellUnderflow);
+                }
+                let value = if ok!(data.load_bit()) {
+                    Some(data)
+                } else {
+                    None
+                };
+                (VarNodeKind::Fork, value)
+            }
+        } else {
+            let branch = match ok!(data.load_bit()) {
+                false => Branch::Left,
+                true => Branch::Right,
+            };
+            if !has_children || !data.try_advance(0, 1) {
+                return Err(Error::CellUnderflow);
+            }
+            (VarNodeKind::Cont(branch), Some(data))
+        };
+
+        Ok(Self {
+            cell,
+            key_bit_len,
+            label,
+            kind,
+            value,
+        })
+    }
+
+    /// Remaining key length of the node children.
+    fn children_key_bit_len(&self) -> u16 {
+        self.key_bit_len - self.label.remaining_bits() - 1
+    }
+
+    /// Returns the reference index of the child in the specified branch.
+    fn child_index(&self, branch: Branch) -> Option<u8> {
+        match self.kind {
+            VarNodeKind::Leaf => None,
+            VarNodeKind::Fork => Some(branch as u8),
+            VarNodeKind::Cont(child_branch) if child_branch == branch => Some(0),
+            VarNodeKind::Cont(_) => None,
+        }
+    }
+
+    fn child(&self, branch: Branch) -> Option<&'a DynCell> {
+        match self.child_index(branch) {
+            Some(index) => self.cell.reference(index),
+            None => None,
+        }
+    }
+
+    fn children(&self) -> Result<VarChildren, Error> {
+        let child = |index: u8| match self.cell.reference_cloned(index) {
+            Some(cell) => Ok(cell),
+            None => Err(Error::CellUnderflow),
+        };
+
+        Ok(match self.kind {
+            VarNodeKind::Leaf => VarChildren::None,
+            VarNodeKind::Fork => VarChildren::Fork(ok!(child(0)), ok!(child(1))),
+            VarNodeKind::Cont(branch) => VarChildren::Cont(branch, ok!(child(0))),
+        })
+    }
+
+    /// Returns the node children with the specified branch replaced or added.
+    fn children_with(&self, branch: Branch, new: Cell) -> Result<VarChildren, Error> {
+        Ok(match (ok!(self.children()), branch) {
+            (VarChildren::None, _) => VarChildren::Cont(branch, new),
+            (VarChildren::Fork(_, right), Branch::Left) => VarChildren::Fork(new, right),
+            (VarChildren::Fork(left, _), Branch::Right) => VarChildren::Fork(left, new),
+            (VarChildren::Cont(child_branch, _), _) if child_branch == branch => {
+                VarChildren::Cont(branch, new)
+            }
+            (VarChildren::Cont(_, child), Branch::Left) => VarChildren::Fork(new, child),
+            (VarChildren::Cont(_, child), Branch::Right) => VarChildren::Fork(child, new),
+        })
+    }
+}
+
+fn build_node(
+    label: &CellSlice<'_>,
+    key_bit_len: u16,
+    children: VarChildren,
+    value: Option<&CellSlice<'_>>,
+    finalizer: &mut dyn Finalizer,
+) -> Result<Cell, Error> {
+    // Leaf must always have a value
+    if matches!(children, VarChildren::None) && value.is_none() {
+        return Err(Error::InvalidData);
+    }
+
+    let mut builder = CellBuilder::new();
+    ok!(write_label(label, key_bit_len, &mut builder));
+
+    match children {
+        VarChildren::None => {
+            ok!(builder.store_small_uint(0b00, 2));
+        }
+        VarChildren::Fork(left, right) => {
+            ok!(builder.store_small_uint(0b01, 2));
+            ok!(builder.store_reference(left));
+            ok!(builder.store_reference(right));
+            ok!(builder.store_bit(value.is_some()));
+        }
+        VarChildren::Cont(branch, child) => {
+            ok!(builder.store_bit_one());
+            ok!(builder.store_bit(branch == Branch::Right));
+            ok!(builder.store_reference(child));
+        }
+    }
+
+    if let Some(value) = value {
+        ok!(builder.store_slice(value));
+    }
+
+    builder.build_ext(finalizer)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+
+    fn make_key(bits: &str) -> anyhow::Result<Cell> {
+        let mut builder = CellBuilder::new();
+        for bit in bits.chars() {
+            builder.store_bit(bit == '1')?;
+        }
+        Ok(builder.build()?)
+    }
+
+    fn key_to_string(key: &CellBuilder) -> anyhow::Result<String> {
+        let cell = key.clone().build()?;
+        let mut slice = cell.as_slice()?;
+        let mut result = String::new();
+        while !slice.is_data_empty() {
+            result.push(if slice.load_bit()? { '1' } else { '0' });
+        }
+        Ok(result)
+    }
+
+    #[test]
+    fn var_dict_set_get_remove() -> anyhow::Result<()> {
+        let keys = [
+            "1010",
+            "",
+            "10",
+            "1",
+            "0",
+            "101011",
+            "1010",
+            "111",
+            "10100000",
+            "0110",
+            "01",
+            "1111111111111111",
+        ];
+
+        let mut dict = VarDict::<16>::new();
+        let mut expected = BTreeMap::new();
+        for (i, key) in keys.into_iter().enumerate() {
+            let key_cell = make_key(key)?;
+            let value = CellBuilder::build_from(i as u32)?;
+            dict.set(key_cell.as_slice()?, value.as_slice()?)?;
+            expected.insert(key, i as u32);
+
+            for (key, value) in &expected {
+                let key_cell = make_key(key)?;
+                let found = dict.get(key_cell.as_slice()?)?;
+                assert_eq!(found.map(|mut s| s.load_u32()).transpose()?, Some(*value));
+            }
+        }
+
+        // Too long key
+        let long_key = make_key("11111111111111111")?;
+        let value = CellBuilder::build_from(0u32)?;
+        assert!(dict.set(long_key.as_slice()?, value.as_slice()?).is_err());
+
+        for key in ["11", "100", "1010000", "101010", "0111", "111111111111111"] {
+            let key_cell = make_key(key)?;
+            assert!(!dict.contains_key(key_cell.as_slice()?)?);
+        }
+
+        // Replace and add
+        let key_cell = make_key("11")?;
+        let value = CellBuilder::build_from(100u32)?;
+        let before = dict.clone();
+        dict.replace(key_cell.as_slice()?, value.as_slice()?)?;
+        assert_eq!(dict, before);
+        dict.add(key_cell.as_slice()?, value.as_slice()?)?;
+        expected.insert("11", 100);
+
+        let key_cell = make_key("10")?;
+        let before = dict.clone();
+        dict.add(key_cell.as_slice()?, value.as_slice()?)?;
+        assert_eq!(dict, before);
+
+        // Iteration
+        let entries = dict
+            .iter()
+            .map(|entry| {
+                let (key, mut value) = entry?;
+                Ok((key_to_string(&key)?, value.load_u32()?))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let expected_entries = expected
+            .iter()
+            .map(|(key, value)| (key.to_string(), *value))
+            .collect::<Vec<_>>();
+        assert_eq!(entries, expected_entries);
+
+        // Removal
+        for key in [
+            "10",
+            "",
+            "1010",
+            "1",
+            "1111111111111111",
+            "0",
+            "11",
+            "01",
+            "0110",
+        ] {
+            let key_cell = make_key(key)?;
+            let (cell, range) = dict.remove(key_cell.as_slice()?)?.unwrap();
+            assert_eq!(
+                range.apply(cell.as_ref())?.load_u32()?,
+                expected.remove(key).unwrap()
+            );
+            assert!(dict.remove(key_cell.as_slice()?)?.is_none());
+
+            let mut rebuilt = VarDict::<16>::new();
+            for (key, value) in &expected {
+                let key_cell = make_key(key)?;
+                let value = CellBuilder::build_from(*value)?;
+                rebuilt.set(key_cell.as_slice()?, value.as_slice()?)?;
+            }
+            assert_eq!(dict, rebuilt);
+        }
+
+        for key in ["111", "101011", "10100000"] {
+            let key_cell = make_key(key)?;
+            assert!(dict.remove(key_cell.as_slice()?)?.is_some());
+        }
+        assert!(dict.is_empty());
+
+        Ok(())
+    }
+}
@@ -30,6 +30,12 @@ pub enum Error {
     /// Signature check failed.
     #[error("invalid signature")]
     InvalidSignature,
+    /// Integer value does not fit into the target type or bit width.
+    #[error("integer overflow")]
+    IntOverflow,
+    /// Gas limit was reached.
+    #[error("out of gas")]
+    OutOfGas,
 }
 
 /// Error type for integer parsing related errors.
@@ -85,3 +91,28 @@ pub enum ParseBlockIdError {
     #[error("unexpected block id part")]
     UnexpectedPart,
 }
+
+#[doc(hidden)]
+mod __checks {
+    use super::*;
+
+    assert_impl_all!(Error: Send, Sync);
+    assert_impl_all!(DictValidationError: Send, Sync);
+}
+
+/// Error type for dictionary validation errors.
+#[derive(Debug, Clone, Eq, PartialEq, thiserror::Error)]
+#[error(
+    "invalid dictionary node at key prefix {}/{key_prefix_bit_len}: {error}",
+    hex::encode(key_prefix)
+)]
+pub struct DictValidationError {
+    /// Key bits on the path from the root to the invalid node
+    /// (padded with zeros to the whole number of bytes).
+    pub key_prefix: Vec<u8>,
+    /// The number of bits in the key prefix.
+    pub key_prefix_bit_len: u16,
+    /// Underlying error.
+    #[source]
+    pub error: Error,
+}
@@ -27,6 +27,18 @@ impl StorageUsed {
         bits: VarUint56::ZERO,
         public_cells: VarUint56::ZERO,
     };
+
+    /// Computes storage usage stats for the cell tree.
+    ///
+    /// See [`DynCell::compute_unique_stats`] for details.
+    pub fn compute(cell: &DynCell, limit: CellTreeStats) -> Option<Self> {
+        let stats = cell.compute_unique_stats(limit)?;
+        Some(Self {
+            cells: VarUint56::new(stats.cell_count),
+            bits: VarUint56::new(stats.bit_count),
+            public_cells: VarUint56::ZERO,
+        })
+    }
 }
 
 /// Amount of unique cells and bits.
@@ -44,6 +56,17 @@ impl StorageUsedShort {
         cells: VarUint56::ZERO,
         bits: VarUint56::ZERO,
     };
+
+    /// Computes storage usage stats for the cell tree.
+    ///
+    /// See [`DynCell::compute_unique_stats`] for details.
+    pub fn compute(cell: &DynCell, limit: CellTreeStats) -> Option<Self> {
+        let stats = cell.compute_unique_stats(limit)?;
+        Some(Self {
+            cells: VarUint56::new(stats.cell_count),
+            bits: VarUint56::new(stats.bit_count),
+        })
+    }
 }
 
 /// Storage profile of an account.
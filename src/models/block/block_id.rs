@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use crate::cell::*;
+use crate::dict::DictKey;
 use crate::error::{Error, ParseBlockIdError};
 
 /// Full block id.
@@ -116,7 +117,9 @@ impl std::fmt::Display for BlockId {
 }
 
 /// Short block id.
-#[derive(Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(
+    Debug, Default, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd, Store, Load, DictKey,
+)]
 pub struct BlockIdShort {
     /// Block shard ident.
     pub shard: ShardIdent,
@@ -426,3 +429,76 @@ impl<'a> Load<'a> for ShardIdent {
         Ok(Self { workchain, prefix })
     }
 }
+
+impl DictKey for ShardIdent {
+    const BITS: u16 = Self::BITS;
+
+    fn from_raw_data(d: &[u8; 128]) -> Option<Self> {
+        let prefix_len = d[0];
+        if prefix_len > Self::MAX_SPLIT_DEPTH {
+            return None;
+        }
+
+        let workchain = i32::from_be_bytes(d[1..5].try_into().unwrap());
+        let prefix_without_tag = u64::from_be_bytes(d[5..13].try_into().unwrap());
+
+        let tag = 1u64 << (63 - prefix_len);
+        let prefix = (prefix_without_tag & (!tag + 1)) | tag;
+
+        Some(Self { workchain, prefix })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dict::Dict;
+
+    #[test]
+    fn block_id_dict_keys() -> anyhow::Result<()> {
+        let (left, right) = ShardIdent::BASECHAIN.split().unwrap();
+        let (left_left, _) = left.split().unwrap();
+        let shards = [
+            ShardIdent::MASTERCHAIN,
+            ShardIdent::BASECHAIN,
+            left,
+            right,
+            left_left,
+        ];
+
+        let mut dict = Dict::<ShardIdent, u32>::new();
+        for (i, shard) in shards.iter().enumerate() {
+            dict.set(shard, i as u32)?;
+        }
+        for (i, shard) in shards.iter().enumerate() {
+            assert_eq!(dict.get(shard)?, Some(i as u32));
+        }
+        let mut keys = dict.keys().collect::<Result<Vec<_>, _>>()?;
+        keys.sort();
+        let mut expected = shards.to_vec();
+        expected.sort();
+        assert_eq!(keys, expected);
+
+        let blocks = [
+            BlockIdShort {
+                shard: ShardIdent::MASTERCHAIN,
+                seqno: 100,
+            },
+            BlockIdShort {
+                shard: right,
+                seqno: 1,
+            },
+        ];
+
+        let mut dict = Dict::<BlockIdShort, u32>::new();
+        for (i, block) in blocks.iter().enumerate() {
+            dict.set(block, i as u32)?;
+        }
+        for (i, block) in blocks.iter().enumerate() {
+            assert_eq!(dict.get(block)?, Some(i as u32));
+        }
+        assert_eq!(dict.keys().count(), blocks.len());
+
+        Ok(())
+    }
+}
@@ -1,7 +1,8 @@
 //! Currency collection stuff.
 
 use crate::cell::*;
-use crate::dict::{AugDictSkipValue, Dict};
+use crate::dict::{AugDictExtra, AugDictSkipValue, Dict};
+use crate::error::Error;
 use crate::num::{Tokens, VarUint248};
 
 /// Amounts collection.
@@ -41,6 +42,33 @@ impl CurrencyCollection {
     pub const fn bit_len(&self) -> u16 {
         self.tokens.unwrap_bit_len() + 1
     }
+
+    /// Checked currency collection addition.
+    /// Computes `self + rhs` for each currency, returning `Err`
+    /// if overflow occurred.
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self, Error> {
+        let tokens = match self.tokens.checked_add(rhs.tokens) {
+            Some(tokens) => tokens,
+            None => return Err(Error::IntOverflow),
+        };
+        Ok(Self {
+            tokens,
+            other: ok!(self.other.checked_add(&rhs.other)),
+        })
+    }
+}
+
+impl AugDictExtra for CurrencyCollection {
+    fn comp_add(
+        left: &mut CellSlice,
+        right: &mut CellSlice,
+        b: &mut CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        let left = ok!(Self::load_from(left));
+        let right = ok!(Self::load_from(right));
+        ok!(left.checked_add(&right)).store_into(b, finalizer)
+    }
 }
 
 impl<'a> AugDictSkipValue<'a> for CurrencyCollection {
@@ -77,6 +105,23 @@ impl ExtraCurrencyCollection {
     pub const fn as_dict(&self) -> &Dict<HashBytes, VarUint248> {
         &self.0
     }
+
+    /// Checked extra currency collection addition.
+    /// Computes `self + rhs` for each currency, returning `Err`
+    /// if overflow occurred.
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self, Error> {
+        let mut result = self.clone();
+        for entry in rhs.0.iter() {
+            let (currency_id, other) = ok!(entry);
+
+            let existing = ok!(result.0.get(currency_id)).unwrap_or_default();
+            match existing.checked_add(other) {
+                Some(value) => ok!(result.0.set(currency_id, value)),
+                None => return Err(Error::IntOverflow),
+            };
+        }
+        Ok(result)
+    }
 }
 
 impl<'a> AugDictSkipValue<'a> for ExtraCurrencyCollection {
@@ -25,49 +25,20 @@ pub struct Message<'a> {
 }
 
 impl<'a> Store for Message<'a> {
+    #[inline]
     fn store_into(
         &self,
         builder: &mut CellBuilder,
         finalizer: &mut dyn Finalizer,
     ) -> Result<(), Error> {
-        let (layout, bits, refs) = match self.layout {
-            Some(layout) => {
-                let (bits, refs) = layout.compute_full_len(&self.info, &self.init, &self.body);
-                (layout, bits, refs)
-            }
-            None => MessageLayout::compute(&self.info, &self.init, &self.body),
-        };
-
-        // Check capacity
-        if !builder.has_capacity(bits, refs) {
-            return Err(Error::CellOverflow);
-        }
-
-        // Try to store info
-        ok!(self.info.store_into(builder, finalizer));
-
-        // Try to store init
-        ok!(match &self.init {
-            Some(value) => {
-                ok!(builder.store_bit_one()); // just$1
-                SliceOrCell {
-                    to_cell: layout.init_to_cell,
-                    value,
-                }
-                .store_into(builder, finalizer)
-            }
-            None => builder.store_bit_zero(), // nothing$0
-        });
-
-        // Try to store body
-        match &self.body {
-            Some(value) => SliceOrCell {
-                to_cell: layout.body_to_cell,
-                value,
-            }
-            .store_into(builder, finalizer),
-            None => builder.store_bit_zero(),
-        }
+        store_message(
+            &self.info,
+            &self.init,
+            &self.body,
+            self.layout,
+            builder,
+            finalizer,
+        )
     }
 }
 
@@ -102,6 +73,126 @@ impl<'a> Load<'a> for Message<'a> {
     }
 }
 
+/// Blockchain message which owns its payload.
+///
+/// Unlike [`Message`] it doesn't borrow the cell it was loaded from,
+/// so it can be stored in long-lived structures or sent across threads.
+#[derive(Debug, Clone)]
+pub struct OwnedMessage {
+    /// Message info.
+    pub info: MsgInfo,
+    /// Optional state init.
+    pub init: Option<StateInit>,
+    /// Optional payload.
+    pub body: Option<OwnedCellSlice>,
+    /// Optional message layout.
+    pub layout: Option<MessageLayout>,
+}
+
+impl Store for OwnedMessage {
+    fn store_into(
+        &self,
+        builder: &mut CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        let body = self.body.as_ref().map(OwnedCellSlice::as_slice);
+        store_message(
+            &self.info,
+            &self.init,
+            &body,
+            self.layout,
+            builder,
+            finalizer,
+        )
+    }
+}
+
+impl<'a> Load<'a> for OwnedMessage {
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        let info = ok!(MsgInfo::load_from(slice));
+        let init = ok!(Option::<SliceOrCell<StateInit>>::load_from(slice));
+
+        let body_to_cell = ok!(slice.load_bit());
+        let body = if body_to_cell {
+            // Reuse the child cell instead of copying it
+            ok!(OwnedCellSlice::new(ok!(slice.load_reference_cloned())))
+        } else {
+            ok!(OwnedCellSlice::load_from(slice))
+        };
+
+        let (init, init_to_cell) = match init {
+            Some(SliceOrCell { to_cell, value }) => (Some(value), to_cell),
+            None => (None, false),
+        };
+
+        let layout = MessageLayout {
+            init_to_cell,
+            body_to_cell,
+        };
+
+        let body = if body.range().remaining_bits() == 0 && body.range().remaining_refs() == 0 {
+            None
+        } else {
+            Some(body)
+        };
+
+        Ok(Self {
+            info,
+            init,
+            body,
+            layout: Some(layout),
+        })
+    }
+}
+
+fn store_message(
+    info: &MsgInfo,
+    init: &Option<StateInit>,
+    body: &Option<CellSlice<'_>>,
+    layout: Option<MessageLayout>,
+    builder: &mut CellBuilder,
+    finalizer: &mut dyn Finalizer,
+) -> Result<(), Error> {
+    let (layout, bits, refs) = match layout {
+        Some(layout) => {
+            let (bits, refs) = layout.compute_full_len(info, init, body);
+            (layout, bits, refs)
+        }
+        None => MessageLayout::compute(info, init, body),
+    };
+
+    // Check capacity
+    if !builder.has_capacity(bits, refs) {
+        return Err(Error::CellOverflow);
+    }
+
+    // Try to store info
+    ok!(info.store_into(builder, finalizer));
+
+    // Try to store init
+    ok!(match init {
+        Some(value) => {
+            ok!(builder.store_bit_one()); // just$1
+            SliceOrCell {
+                to_cell: layout.init_to_cell,
+                value,
+            }
+            .store_into(builder, finalizer)
+        }
+        None => builder.store_bit_zero(), // nothing$0
+    });
+
+    // Try to store body
+    match body {
+        Some(value) => SliceOrCell {
+            to_cell: layout.body_to_cell,
+            value,
+        }
+        .store_into(builder, finalizer),
+        None => builder.store_bit_zero(),
+    }
+}
+
 struct SliceOrCell<T> {
     to_cell: bool,
     value: T,
@@ -630,6 +721,11 @@ mod tests {
         let serialized = serialize_message(message);
         assert_eq!(serialized.as_ref(), boc.as_ref());
 
+        // Check that the owned message is serialized the same way
+        let owned = boc.parse::<OwnedMessage>().unwrap();
+        let serialized = CellBuilder::build_from(owned).unwrap();
+        assert_eq!(serialized.as_ref(), boc.as_ref());
+
         boc
     }
 
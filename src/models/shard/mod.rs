@@ -268,6 +268,34 @@ mod tests {
         let _elector = shard_accounts.get([0x33; 32]).unwrap().unwrap();
         assert!(shard_accounts.contains_account([0x55; 32]).unwrap());
 
+        // Augmentation must be recomputed exactly as in the original state
+        let mut modified = shard_accounts.clone();
+        let (balance, elector) = modified.remove([0x33; 32]).unwrap().unwrap();
+        assert!(!modified.contains_account([0x33; 32]).unwrap());
+        assert_eq!(
+            modified
+                .root_extra()
+                .balance
+                .checked_add(&balance.balance)
+                .unwrap(),
+            shard_accounts.root_extra().balance
+        );
+
+        let diff = shard_accounts
+            .diff(&modified)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            diff,
+            [crate::dict::DictDiffItem::Removed(
+                HashBytes([0x33; 32]),
+                (balance.clone(), elector.clone())
+            )]
+        );
+
+        modified.set([0x33; 32], balance, elector).unwrap();
+        assert_eq!(modified, shard_accounts);
+
         let custom = data.load_custom().unwrap().unwrap();
         println!("custom: {custom:#?}");
     }
@@ -1,7 +1,7 @@
 use std::borrow::Borrow;
 
 use crate::cell::*;
-use crate::dict::{self, AugDict, AugDictSkipValue};
+use crate::dict::{self, AugDict, AugDictExtra, AugDictSkipValue};
 use crate::error::*;
 
 use crate::models::currency::CurrencyCollection;
@@ -11,7 +11,58 @@ use crate::models::ShardAccount;
 #[derive(Debug, Clone, Eq, PartialEq, Store, Load)]
 pub struct ShardAccounts(AugDict<HashBytes, DepthBalanceInfo, ShardAccount>);
 
+impl Default for ShardAccounts {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ShardAccounts {
+    /// Creates an empty shard accounts dictionary.
+    pub fn new() -> Self {
+        Self(AugDict::new())
+    }
+
+    /// Returns the total balance info of all accounts.
+    pub fn root_extra(&self) -> &DepthBalanceInfo {
+        self.0.root_extra()
+    }
+
+    /// Sets the account state and its balance info.
+    ///
+    /// Key is serialized using the default finalizer.
+    pub fn set<Q, E, T>(&mut self, key: Q, aug: E, value: T) -> Result<(), Error>
+    where
+        Q: Borrow<HashBytes>,
+        E: Borrow<DepthBalanceInfo>,
+        T: Borrow<ShardAccount>,
+    {
+        self.0.set(key, aug, value)
+    }
+
+    /// Removes the account state corresponding to the key.
+    /// Returns an optional removed balance info and account state.
+    ///
+    /// Key is serialized using the default finalizer.
+    pub fn remove<Q>(&mut self, key: Q) -> Result<Option<(DepthBalanceInfo, ShardAccount)>, Error>
+    where
+        Q: Borrow<HashBytes>,
+    {
+        self.0.remove(key)
+    }
+
+    /// Gets an iterator over the changed accounts between this state
+    /// and the `other` one, sorted by key. Unchanged subtrees are skipped.
+    /// The iterator element type is
+    /// `Result<DictDiffItem<HashBytes, (DepthBalanceInfo, ShardAccount)>>`.
+    pub fn diff<'a>(
+        &'a self,
+        other: &'a Self,
+    ) -> dict::DictDiff<'a, HashBytes, (DepthBalanceInfo, ShardAccount)> {
+        self.0.diff(&other.0)
+    }
+
     /// Returns the account state corresponding to the key.
     ///
     /// Key is serialized using the default finalizer.
@@ -155,6 +206,34 @@ impl<'a> Load<'a> for DepthBalanceInfo {
     }
 }
 
+impl Default for DepthBalanceInfo {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            split_depth: 0,
+            balance: CurrencyCollection::ZERO,
+        }
+    }
+}
+
+impl AugDictExtra for DepthBalanceInfo {
+    fn comp_add(
+        left: &mut CellSlice,
+        right: &mut CellSlice,
+        b: &mut CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        // Split depth of a subtree is always zero
+        if !left.try_advance(Self::SPLIT_DEPTH_BITS, 0)
+            || !right.try_advance(Self::SPLIT_DEPTH_BITS, 0)
+        {
+            return Err(Error::CellUnderflow);
+        }
+        ok!(b.store_small_uint(0, Self::SPLIT_DEPTH_BITS));
+        CurrencyCollection::comp_add(left, right, b, finalizer)
+    }
+}
+
 impl<'a> AugDictSkipValue<'a> for DepthBalanceInfo {
     fn skip_value(slice: &mut CellSlice<'a>) -> bool {
         slice.try_advance(5, 0) && CurrencyCollection::skip_value(slice)
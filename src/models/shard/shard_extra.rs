@@ -1,5 +1,5 @@
 use crate::cell::*;
-use crate::dict::{AugDict, Dict};
+use crate::dict::{AugDict, AugDictExtra, Dict};
 use crate::error::Error;
 use crate::num::*;
 
@@ -151,7 +151,7 @@ pub struct KeyBlockRef {
 }
 
 /// Value augmentation for the [`OldMcBlocksInfo`] dictionary.
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Store, Load)]
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Store, Load)]
 pub struct KeyMaxLt {
     /// Has key block in a subtree.
     pub has_key_block: bool,
@@ -159,6 +159,23 @@ pub struct KeyMaxLt {
     pub max_end_lt: u64,
 }
 
+impl AugDictExtra for KeyMaxLt {
+    fn comp_add(
+        left: &mut CellSlice,
+        right: &mut CellSlice,
+        b: &mut CellBuilder,
+        finalizer: &mut dyn Finalizer,
+    ) -> Result<(), Error> {
+        let left = ok!(Self::load_from(left));
+        let right = ok!(Self::load_from(right));
+        Self {
+            has_key_block: left.has_key_block || right.has_key_block,
+            max_end_lt: std::cmp::max(left.max_end_lt, right.max_end_lt),
+        }
+        .store_into(b, finalizer)
+    }
+}
+
 /// Block production statistics for the single validator.
 #[derive(Debug, Clone, Eq, PartialEq, Store, Load)]
 #[tlb(tag = "#4")]
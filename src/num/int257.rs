@@ -0,0 +1,959 @@
+use std::cmp::Ordering;
+
+use crate::cell::*;
+use crate::error::{Error, ParseIntError};
+use crate::util::unlikely;
+
+/// Limbs of a 320-bit two's complement integer (least significant first).
+type Limbs = [u64; 5];
+
+/// Signed 257-bit integer (TVM integer).
+///
+/// Stored as a two's complement number with a sign-extended highest limb.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Int257(Limbs);
+
+impl Int257 {
+    /// The additive identity for this integer type, i.e. `0`.
+    pub const ZERO: Self = Self([0; 5]);
+
+    /// The multiplicative identity for this integer type, i.e. `1`.
+    pub const ONE: Self = Self([1, 0, 0, 0, 0]);
+
+    /// The smallest value that can be represented by this integer type (`-2^256`).
+    pub const MIN: Self = Self([0, 0, 0, 0, u64::MAX]);
+
+    /// The largest value that can be represented by this integer type (`2^256 - 1`).
+    pub const MAX: Self = Self([u64::MAX, u64::MAX, u64::MAX, u64::MAX, 0]);
+
+    /// The number of data bits that this struct occupies.
+    pub const BITS: u16 = 257;
+
+    /// Creates a new integer value from a signed primitive integer.
+    pub const fn new(value: i128) -> Self {
+        let ext = if value < 0 { u64::MAX } else { 0 };
+        Self([value as u64, (value >> 64) as u64, ext, ext, ext])
+    }
+
+    /// Creates a new integer value from an unsigned primitive integer.
+    pub const fn from_u128(value: u128) -> Self {
+        Self([value as u64, (value >> 64) as u64, 0, 0, 0])
+    }
+
+    /// Returns `true` if the value is zero.
+    #[inline]
+    pub const fn is_zero(&self) -> bool {
+        let [a, b, c, d, e] = self.0;
+        a == 0 && b == 0 && c == 0 && d == 0 && e == 0
+    }
+
+    /// Returns `true` if the value is negative.
+    #[inline]
+    pub const fn is_negative(&self) -> bool {
+        self.0[4] != 0
+    }
+
+    /// Returns the minimal number of bits required to store this value
+    /// as a signed integer (`0` for zero).
+    pub fn bit_len(&self) -> u16 {
+        if self.is_zero() {
+            0
+        } else if self.is_negative() {
+            (320 - leading_ones(&self.0) + 1) as u16
+        } else {
+            (320 - leading_zeros(&self.0) + 1) as u16
+        }
+    }
+
+    /// Returns the minimal number of bits required to store this value
+    /// as an unsigned integer, or `None` if the value is negative.
+    pub fn unsigned_bit_len(&self) -> Option<u16> {
+        if self.is_negative() {
+            None
+        } else {
+            Some((320 - leading_zeros(&self.0)) as u16)
+        }
+    }
+
+    /// Tries to convert the value into `i128`.
+    pub const fn to_i128(&self) -> Option<i128> {
+        let [lo, hi, c, d, e] = self.0;
+        let ext = if (hi as i64) < 0 { u64::MAX } else { 0 };
+        if c == ext && d == ext && e == ext {
+            Some(((hi as u128) << 64 | lo as u128) as i128)
+        } else {
+            None
+        }
+    }
+
+    /// Tries to convert the value into `u128`.
+    pub const fn to_u128(&self) -> Option<u128> {
+        let [lo, hi, c, d, e] = self.0;
+        if c == 0 && d == 0 && e == 0 {
+            Some((hi as u128) << 64 | lo as u128)
+        } else {
+            None
+        }
+    }
+
+    /// Checked negation. Computes `-self`, returning `None` if `self == MIN`.
+    pub fn checked_neg(self) -> Option<Self> {
+        Self::from_limbs(wrapping_neg(&self.0))
+    }
+
+    /// Computes the absolute value of `self`, returning `None` if `self == MIN`.
+    pub fn checked_abs(self) -> Option<Self> {
+        if self.is_negative() {
+            self.checked_neg()
+        } else {
+            Some(self)
+        }
+    }
+
+    /// Checked integer addition. Computes `self + rhs`, returning `None` if overflow occurred.
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        Self::from_limbs(wrapping_add(&self.0, &rhs.0))
+    }
+
+    /// Checked integer subtraction. Computes `self - rhs`, returning `None` if overflow occurred.
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        Self::from_limbs(wrapping_add(&self.0, &wrapping_neg(&rhs.0)))
+    }
+
+    /// Checked integer multiplication. Computes `self * rhs`, returning `None` if overflow occurred.
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let mut result = [0u64; 10];
+        let lhs = self.magnitude();
+        let rhs_abs = rhs.magnitude();
+        for (i, a) in lhs.iter().enumerate() {
+            let mut carry = 0u128;
+            for (j, b) in rhs_abs.iter().enumerate() {
+                let t = (*a as u128) * (*b as u128) + result[i + j] as u128 + carry;
+                result[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            result[i + 5] = carry as u64;
+        }
+
+        if result[5..].iter().any(|limb| *limb != 0) {
+            return None;
+        }
+
+        let mut magnitude = [0u64; 5];
+        magnitude.copy_from_slice(&result[..5]);
+        Self::from_magnitude(self.is_negative() != rhs.is_negative(), magnitude)
+    }
+
+    /// Checked integer division. Computes `self / rhs` rounding towards zero,
+    /// returning `None` if `rhs == 0` or overflow occurred.
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        let (quotient, _) = div_rem_magnitude(&self, &rhs)?;
+        Self::from_magnitude(self.is_negative() != rhs.is_negative(), quotient)
+    }
+
+    /// Checked integer remainder. Computes `self % rhs` (with the sign of `self`),
+    /// returning `None` if `rhs == 0`.
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        let (_, remainder) = div_rem_magnitude(&self, &rhs)?;
+        Self::from_magnitude(self.is_negative(), remainder)
+    }
+
+    /// Parses an integer from a string in the given base.
+    ///
+    /// The string may start with an optional `+` or `-` sign.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `radix` is not in the range from 2 to 36.
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseIntError> {
+        assert!(
+            (2..=36).contains(&radix),
+            "from_str_radix: radix must lie in the range `[2, 36]`"
+        );
+
+        let (neg, digits) = split_sign(s);
+        let magnitude = ok!(parse_magnitude(digits, radix));
+        match Self::from_magnitude(neg, magnitude) {
+            Some(value) => Ok(value),
+            None => Err(ParseIntError::Overflow),
+        }
+    }
+
+    /// Tries to read a signed integer of the specified number of bits (`0..=257`).
+    pub fn load_int(slice: &mut CellSlice<'_>, bits: u16) -> Result<Self, Error> {
+        if unlikely(bits > Self::BITS) {
+            return Err(Error::IntOverflow);
+        }
+
+        let mut limbs = ok!(load_limbs(slice, bits));
+        if bits > 0 {
+            let sign_bit = bits - 1;
+            if limbs[(sign_bit / 64) as usize] >> (sign_bit % 64) & 1 != 0 {
+                sign_extend(&mut limbs, bits);
+            }
+        }
+        Ok(Self(limbs))
+    }
+
+    /// Tries to read an unsigned integer of the specified number of bits (`0..=256`).
+    pub fn load_uint(slice: &mut CellSlice<'_>, bits: u16) -> Result<Self, Error> {
+        if unlikely(bits > Self::BITS - 1) {
+            return Err(Error::IntOverflow);
+        }
+
+        match load_limbs(slice, bits) {
+            Ok(limbs) => Ok(Self(limbs)),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Tries to store the value as a signed integer of the specified number of bits.
+    ///
+    /// Returns [`Error::IntOverflow`] if the value does not fit into `bits`.
+    pub fn store_int(&self, builder: &mut CellBuilder, bits: u16) -> Result<(), Error> {
+        if unlikely(self.bit_len() > bits || bits > Self::BITS) {
+            return Err(Error::IntOverflow);
+        }
+        store_limbs(&self.0, builder, bits)
+    }
+
+    /// Tries to store the value as an unsigned integer of the specified number of bits.
+    ///
+    /// Returns [`Error::IntOverflow`] if the value is negative or does not fit into `bits`.
+    pub fn store_uint(&self, builder: &mut CellBuilder, bits: u16) -> Result<(), Error> {
+        match self.unsigned_bit_len() {
+            Some(len) if len <= bits && bits < Self::BITS => store_limbs(&self.0, builder, bits),
+            _ => Err(Error::IntOverflow),
+        }
+    }
+
+    fn from_limbs(limbs: Limbs) -> Option<Self> {
+        if limbs[4] == 0 || limbs[4] == u64::MAX {
+            Some(Self(limbs))
+        } else {
+            None
+        }
+    }
+
+    fn from_magnitude(neg: bool, magnitude: Limbs) -> Option<Self> {
+        if !fits_magnitude(&magnitude) {
+            None
+        } else if neg {
+            Some(Self(wrapping_neg(&magnitude)))
+        } else {
+            Self::from_limbs(magnitude)
+        }
+    }
+
+    fn magnitude(&self) -> Limbs {
+        if self.is_negative() {
+            wrapping_neg(&self.0)
+        } else {
+            self.0
+        }
+    }
+}
+
+impl Ord for Int257 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.is_negative(), other.is_negative()) {
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            _ => cmp_limbs(&self.0, &other.0),
+        }
+    }
+}
+
+impl PartialOrd for Int257 {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Store for Int257 {
+    #[inline]
+    fn store_into(&self, builder: &mut CellBuilder, _: &mut dyn Finalizer) -> Result<(), Error> {
+        self.store_int(builder, Self::BITS)
+    }
+}
+
+impl<'a> Load<'a> for Int257 {
+    #[inline]
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        Self::load_int(slice, Self::BITS)
+    }
+}
+
+impl crate::dict::DictKey for Int257 {
+    const BITS: u16 = Self::BITS;
+
+    fn from_raw_data(d: &[u8; 128]) -> Option<Self> {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = (d[i] << 1) | (d[i + 1] >> 7);
+        }
+
+        let mut result = Self::from(HashBytes(bytes));
+        if d[0] & 0x80 != 0 {
+            result.0[4] = u64::MAX;
+        }
+        Some(result)
+    }
+}
+
+/// Signed 256-bit integer (`int256` in TL-B).
+///
+/// A thin wrapper around [`Int257`] which is always in the `-2^255..2^255` range.
+/// Can be used as a 256-bit signed dictionary key.
+#[derive(Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct Int256(Int257);
+
+impl Int256 {
+    /// The additive identity for this integer type, i.e. `0`.
+    pub const ZERO: Self = Self(Int257::ZERO);
+
+    /// The smallest value that can be represented by this integer type (`-2^255`).
+    pub const MIN: Self = Self(Int257([0, 0, 0, 1 << 63, u64::MAX]));
+
+    /// The largest value that can be represented by this integer type (`2^255 - 1`).
+    pub const MAX: Self = Self(Int257([u64::MAX, u64::MAX, u64::MAX, u64::MAX >> 1, 0]));
+
+    /// The number of data bits that this struct occupies.
+    pub const BITS: u16 = 256;
+
+    /// Creates a new integer value, returning `None` if it does not fit into 256 bits.
+    pub fn new(value: Int257) -> Option<Self> {
+        if value.bit_len() <= Self::BITS {
+            Some(Self(value))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the underlying 257-bit integer.
+    #[inline]
+    pub const fn into_inner(self) -> Int257 {
+        self.0
+    }
+}
+
+impl From<Int256> for Int257 {
+    #[inline]
+    fn from(value: Int256) -> Self {
+        value.0
+    }
+}
+
+impl TryFrom<Int257> for Int256 {
+    type Error = Error;
+
+    #[inline]
+    fn try_from(value: Int257) -> Result<Self, Self::Error> {
+        match Self::new(value) {
+            Some(value) => Ok(value),
+            None => Err(Error::IntOverflow),
+        }
+    }
+}
+
+impl Store for Int256 {
+    #[inline]
+    fn store_into(&self, builder: &mut CellBuilder, _: &mut dyn Finalizer) -> Result<(), Error> {
+        self.0.store_int(builder, Self::BITS)
+    }
+}
+
+impl<'a> Load<'a> for Int256 {
+    #[inline]
+    fn load_from(slice: &mut CellSlice<'a>) -> Result<Self, Error> {
+        match Int257::load_int(slice, Self::BITS) {
+            Ok(value) => Ok(Self(value)),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl crate::dict::DictKey for Int256 {
+    const BITS: u16 = Self::BITS;
+
+    fn from_raw_data(d: &[u8; 128]) -> Option<Self> {
+        let mut result = Int257::from(HashBytes(d[..32].try_into().unwrap()));
+        if d[0] & 0x80 != 0 {
+            result.0[4] = u64::MAX;
+        }
+        Some(Self(result))
+    }
+}
+
+impl std::fmt::Display for Int256 {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::fmt::Debug for Int256 {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::str::FromStr for Int257 {
+    type Err = ParseIntError;
+
+    /// Parses a decimal integer, or a hexadecimal one if it is prefixed with `0x`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (neg, digits) = split_sign(s);
+        let Some(hex) = digits.strip_prefix("0x") else {
+            return Self::from_str_radix(s, 10);
+        };
+
+        let magnitude = ok!(parse_magnitude(hex, 16));
+        match Self::from_magnitude(neg, magnitude) {
+            Some(value) => Ok(value),
+            None => Err(ParseIntError::Overflow),
+        }
+    }
+}
+
+impl std::fmt::Display for Int257 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const CHUNK: u64 = 10_000_000_000_000_000_000;
+
+        let mut magnitude = self.magnitude();
+        let mut chunks = Vec::with_capacity(5);
+        loop {
+            chunks.push(div_small(&mut magnitude, CHUNK));
+            if magnitude == [0; 5] {
+                break;
+            }
+        }
+
+        let mut result = String::with_capacity(chunks.len() * 19);
+        let mut chunks = chunks.into_iter().rev();
+        if let Some(first) = chunks.next() {
+            result.push_str(&first.to_string());
+        }
+        for chunk in chunks {
+            result.push_str(&format!("{chunk:019}"));
+        }
+
+        f.pad_integral(!self.is_negative(), "", &result)
+    }
+}
+
+impl std::fmt::Debug for Int257 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self, f)
+    }
+}
+
+impl From<HashBytes> for Int257 {
+    fn from(value: HashBytes) -> Self {
+        let mut limbs = [0u64; 5];
+        for (i, chunk) in value.0.chunks_exact(8).enumerate() {
+            limbs[3 - i] = u64::from_be_bytes(chunk.try_into().unwrap());
+        }
+        Self(limbs)
+    }
+}
+
+impl TryFrom<Int257> for HashBytes {
+    type Error = Error;
+
+    fn try_from(value: Int257) -> Result<Self, Self::Error> {
+        if value.is_negative() {
+            return Err(Error::IntOverflow);
+        }
+
+        let mut result = HashBytes::ZERO;
+        for (i, chunk) in result.0.chunks_exact_mut(8).enumerate() {
+            chunk.copy_from_slice(&value.0[3 - i].to_be_bytes());
+        }
+        Ok(result)
+    }
+}
+
+macro_rules! impl_int257_conversions {
+    ($($via:ident => [$($ty:ty),*]),*$(,)?) => {$($(
+        impl From<$ty> for Int257 {
+            #[inline]
+            fn from(value: $ty) -> Self {
+                impl_int257_conversions!(@from $via value)
+            }
+        }
+
+        impl TryFrom<Int257> for $ty {
+            type Error = Error;
+
+            #[inline]
+            fn try_from(value: Int257) -> Result<Self, Self::Error> {
+                match impl_int257_conversions!(@into $via value) {
+                    Some(value) => match <$ty>::try_from(value) {
+                        Ok(value) => Ok(value),
+                        Err(_) => Err(Error::IntOverflow),
+                    },
+                    None => Err(Error::IntOverflow),
+                }
+            }
+        }
+    )*)*};
+    (@from signed $value:ident) => { Self::new($value as i128) };
+    (@from unsigned $value:ident) => { Self::from_u128($value as u128) };
+    (@into signed $value:ident) => { $value.to_i128() };
+    (@into unsigned $value:ident) => { $value.to_u128() };
+}
+
+impl_int257_conversions! {
+    signed => [i8, i16, i32, i64, i128],
+    unsigned => [u8, u16, u32, u64, u128],
+}
+
+fn div_rem_magnitude(lhs: &Int257, rhs: &Int257) -> Option<(Limbs, Limbs)> {
+    if rhs.is_zero() {
+        return None;
+    }
+
+    let dividend = lhs.magnitude();
+    let divisor = rhs.magnitude();
+
+    let mut quotient = [0u64; 5];
+    let mut remainder = [0u64; 5];
+    for bit in (0..320).rev() {
+        remainder = shl1(&remainder);
+        remainder[0] |= dividend[bit / 64] >> (bit % 64) & 1;
+        if cmp_limbs(&remainder, &divisor) != Ordering::Less {
+            remainder = wrapping_add(&remainder, &wrapping_neg(&divisor));
+            quotient[bit / 64] |= 1 << (bit % 64);
+        }
+    }
+
+    Some((quotient, remainder))
+}
+
+fn split_sign(s: &str) -> (bool, &str) {
+    match s.as_bytes().first() {
+        Some(b'-') => (true, &s[1..]),
+        Some(b'+') => (false, &s[1..]),
+        _ => (false, s),
+    }
+}
+
+fn parse_magnitude(digits: &str, radix: u32) -> Result<Limbs, ParseIntError> {
+    const CHUNK_LEN: usize = 8;
+
+    // Get an appropriate error for an empty string
+    if digits.is_empty() {
+        return Err(parse_chunk(digits, radix).unwrap_err());
+    }
+
+    let mut magnitude = [0u64; 5];
+    for chunk in digits.as_bytes().chunks(CHUNK_LEN) {
+        // NOTE: chunks with a split non-ascii char or with a sign
+        // (which is allowed by `from_str_radix`) are invalid anyway
+        let chunk = match std::str::from_utf8(chunk) {
+            Ok(chunk) if !chunk.starts_with('+') => chunk,
+            _ => return Err(parse_chunk("+", radix).unwrap_err()),
+        };
+        let value = ok!(parse_chunk(chunk, radix));
+
+        mul_small(&mut magnitude, (radix as u64).pow(chunk.len() as u32));
+        add_small(&mut magnitude, value);
+        if !fits_magnitude(&magnitude) {
+            return Err(ParseIntError::Overflow);
+        }
+    }
+    Ok(magnitude)
+}
+
+fn parse_chunk(s: &str, radix: u32) -> Result<u64, ParseIntError> {
+    match u64::from_str_radix(s, radix) {
+        Ok(value) => Ok(value),
+        Err(e) => Err(ParseIntError::InvalidString(e)),
+    }
+}
+
+fn load_limbs(slice: &mut CellSlice<'_>, bits: u16) -> Result<Limbs, Error> {
+    if !slice.has_remaining(bits, 0) {
+        return Err(Error::CellUnderflow);
+    }
+
+    let mut limbs = [0u64; 5];
+    let mut remaining = bits;
+    let mut i = ((bits + 63) / 64) as usize;
+    while i > 0 {
+        i -= 1;
+        let len = remaining - 64 * i as u16;
+        limbs[i] = ok!(slice.load_uint(len));
+        remaining -= len;
+    }
+    Ok(limbs)
+}
+
+fn store_limbs(limbs: &Limbs, builder: &mut CellBuilder, bits: u16) -> Result<(), Error> {
+    if !builder.has_capacity(bits, 0) {
+        return Err(Error::CellOverflow);
+    }
+
+    let mut remaining = bits;
+    let mut i = ((bits + 63) / 64) as usize;
+    while i > 0 {
+        i -= 1;
+        let len = remaining - 64 * i as u16;
+        ok!(builder.store_uint(limbs[i], len));
+        remaining -= len;
+    }
+    Ok(())
+}
+
+fn sign_extend(limbs: &mut Limbs, bits: u16) {
+    let q = (bits / 64) as usize;
+    let r = bits % 64;
+    if q < 5 {
+        if r > 0 {
+            limbs[q] |= u64::MAX << r;
+        } else {
+            limbs[q] = u64::MAX;
+        }
+        for limb in &mut limbs[q + 1..] {
+            *limb = u64::MAX;
+        }
+    }
+}
+
+/// Returns `true` if the magnitude is in range `0..=2^256`.
+fn fits_magnitude(magnitude: &Limbs) -> bool {
+    match magnitude[4] {
+        0 => true,
+        1 => magnitude[..4] == [0; 4],
+        _ => false,
+    }
+}
+
+fn leading_zeros(limbs: &Limbs) -> u32 {
+    let mut result = 0;
+    for limb in limbs.iter().rev() {
+        result += limb.leading_zeros();
+        if *limb != 0 {
+            break;
+        }
+    }
+    result
+}
+
+fn leading_ones(limbs: &Limbs) -> u32 {
+    let mut result = 0;
+    for limb in limbs.iter().rev() {
+        result += limb.leading_ones();
+        if *limb != u64::MAX {
+            break;
+        }
+    }
+    result
+}
+
+fn cmp_limbs(lhs: &Limbs, rhs: &Limbs) -> Ordering {
+    lhs.iter().rev().cmp(rhs.iter().rev())
+}
+
+fn wrapping_add(lhs: &Limbs, rhs: &Limbs) -> Limbs {
+    let mut result = [0u64; 5];
+    let mut carry = false;
+    for i in 0..5 {
+        let (value, c1) = lhs[i].overflowing_add(rhs[i]);
+        let (value, c2) = value.overflowing_add(carry as u64);
+        result[i] = value;
+        carry = c1 || c2;
+    }
+    result
+}
+
+fn wrapping_neg(limbs: &Limbs) -> Limbs {
+    let inverted = limbs.map(|limb| !limb);
+    wrapping_add(&inverted, &[1, 0, 0, 0, 0])
+}
+
+fn shl1(limbs: &Limbs) -> Limbs {
+    let mut result = [0u64; 5];
+    let mut carry = 0;
+    for i in 0..5 {
+        result[i] = (limbs[i] << 1) | carry;
+        carry = limbs[i] >> 63;
+    }
+    result
+}
+
+fn mul_small(limbs: &mut Limbs, rhs: u64) {
+    let mut carry = 0u128;
+    for limb in limbs.iter_mut() {
+        let t = (*limb as u128) * (rhs as u128) + carry;
+        *limb = t as u64;
+        carry = t >> 64;
+    }
+}
+
+fn add_small(limbs: &mut Limbs, rhs: u64) {
+    let mut carry = rhs;
+    for limb in limbs.iter_mut() {
+        let (value, overflow) = limb.overflowing_add(carry);
+        *limb = value;
+        if !overflow {
+            break;
+        }
+        carry = 1;
+    }
+}
+
+fn div_small(limbs: &mut Limbs, rhs: u64) -> u64 {
+    let mut remainder = 0u128;
+    for limb in limbs.iter_mut().rev() {
+        let t = (remainder << 64) | *limb as u128;
+        *limb = (t / rhs as u128) as u64;
+        remainder = t % rhs as u128;
+    }
+    remainder as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int257_parse_display() -> anyhow::Result<()> {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        let min = "-115792089237316195423570985008687907853269984665640564039457584007913129639936";
+
+        assert_eq!(max.parse::<Int257>()?, Int257::MAX);
+        assert_eq!(min.parse::<Int257>()?, Int257::MIN);
+        assert_eq!(Int257::MAX.to_string(), max);
+        assert_eq!(Int257::MIN.to_string(), min);
+
+        for value in [
+            "0",
+            "1",
+            "-1",
+            "12345678901234567890123",
+            "-98765432109876543210",
+        ] {
+            assert_eq!(value.parse::<Int257>()?.to_string(), value);
+        }
+
+        assert_eq!("-0x10".parse::<Int257>()?, Int257::new(-16));
+        assert_eq!("0xff".parse::<Int257>()?, Int257::new(255));
+        assert_eq!(Int257::from_str_radix("-777", 8)?, Int257::new(-511));
+        assert_eq!(format!("{:>5}", Int257::new(-12)), "  -12");
+
+        // Overflow
+        assert!(matches!(
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936"
+                .parse::<Int257>(),
+            Err(ParseIntError::Overflow)
+        ));
+
+        // Invalid strings
+        for value in ["", "-", "+", "1-2", "--1", "-0x-1", "12345678+1", "0x"] {
+            assert!(
+                matches!(
+                    value.parse::<Int257>(),
+                    Err(ParseIntError::InvalidString(_))
+                ),
+                "{value}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn int257_arithmetic() {
+        let big = Int257::from(HashBytes([0xaa; 32]));
+        let neg_big = big.checked_neg().unwrap();
+
+        assert_eq!(big.checked_add(neg_big), Some(Int257::ZERO));
+        assert_eq!(Int257::MAX.checked_add(Int257::ONE), None);
+        assert_eq!(Int257::MIN.checked_sub(Int257::ONE), None);
+        assert_eq!(Int257::MIN.checked_neg(), None);
+        assert_eq!(
+            Int257::MAX.checked_neg().unwrap().checked_sub(Int257::ONE),
+            Some(Int257::MIN)
+        );
+
+        let a = Int257::new(-123456789012345678901234567);
+        let b = Int257::new(98765432109876543210);
+        let product = a.checked_mul(b).unwrap();
+        assert_eq!(
+            product.to_string(),
+            "-12193263113702179522496570554336229223321140070"
+        );
+        assert_eq!(product.checked_div(b), Some(a));
+        assert_eq!(product.checked_div(a), Some(b));
+        assert_eq!(product.checked_rem(a), Some(Int257::ZERO));
+
+        assert_eq!(
+            Int257::new(-7).checked_div(Int257::new(2)),
+            Some(Int257::new(-3))
+        );
+        assert_eq!(
+            Int257::new(-7).checked_rem(Int257::new(2)),
+            Some(Int257::new(-1))
+        );
+        assert_eq!(
+            Int257::new(7).checked_rem(Int257::new(-2)),
+            Some(Int257::new(1))
+        );
+        assert_eq!(Int257::ONE.checked_div(Int257::ZERO), None);
+
+        assert_eq!(Int257::MIN.checked_div(Int257::new(-1)), None);
+        assert_eq!(Int257::MIN.checked_div(Int257::ONE), Some(Int257::MIN));
+        assert_eq!(Int257::MIN.checked_div(Int257::MIN), Some(Int257::ONE));
+        assert_eq!(Int257::MIN.checked_mul(Int257::ONE), Some(Int257::MIN));
+        assert_eq!(Int257::MIN.checked_mul(Int257::new(-1)), None);
+        assert_eq!(Int257::MAX.checked_mul(Int257::new(2)), None);
+
+        assert!(Int257::MIN < neg_big);
+        assert!(neg_big < Int257::ZERO);
+        assert!(Int257::ZERO < big);
+        assert!(big < Int257::MAX);
+    }
+
+    #[test]
+    fn int257_conversions() {
+        assert_eq!(i128::try_from(Int257::new(i128::MIN)), Ok(i128::MIN));
+        assert_eq!(u128::try_from(Int257::from(u128::MAX)), Ok(u128::MAX));
+        assert_eq!(u8::try_from(Int257::new(-1)), Err(Error::IntOverflow));
+        assert_eq!(i8::try_from(Int257::new(-128)), Ok(-128));
+        assert_eq!(i8::try_from(Int257::new(128)), Err(Error::IntOverflow));
+        assert_eq!(i128::try_from(Int257::MAX), Err(Error::IntOverflow));
+
+        let hash = HashBytes([0xfe; 32]);
+        assert_eq!(HashBytes::try_from(Int257::from(hash)), Ok(hash));
+        assert_eq!(
+            HashBytes::try_from(Int257::new(-1)),
+            Err(Error::IntOverflow)
+        );
+
+        assert_eq!(Int257::ZERO.bit_len(), 0);
+        assert_eq!(Int257::new(-1).bit_len(), 1);
+        assert_eq!(Int257::new(1).bit_len(), 2);
+        assert_eq!(Int257::new(-128).bit_len(), 8);
+        assert_eq!(Int257::new(127).bit_len(), 8);
+        assert_eq!(Int257::MIN.bit_len(), 257);
+        assert_eq!(Int257::MAX.bit_len(), 257);
+        assert_eq!(Int257::MAX.unsigned_bit_len(), Some(256));
+    }
+
+    #[test]
+    fn int257_load_store() -> anyhow::Result<()> {
+        let values = [
+            Int257::ZERO,
+            Int257::ONE,
+            Int257::new(-1),
+            Int257::MIN,
+            Int257::MAX,
+            Int257::new(-123456789012345678901234567),
+        ];
+
+        for value in values {
+            let cell = CellBuilder::build_from(value)?;
+            assert_eq!(cell.bit_len(), 257);
+            assert_eq!(cell.parse::<Int257>()?, value);
+
+            let bits = value.bit_len();
+            let mut builder = CellBuilder::new();
+            value.store_int(&mut builder, bits)?;
+            if bits > 0 {
+                assert_eq!(
+                    value.store_int(&mut builder, bits - 1),
+                    Err(Error::IntOverflow)
+                );
+            }
+            let cell = builder.build()?;
+            assert_eq!(Int257::load_int(&mut cell.as_slice()?, bits)?, value);
+        }
+
+        let value = Int257::from(0xabcdefu32);
+        let mut builder = CellBuilder::new();
+        value.store_uint(&mut builder, 24)?;
+        assert_eq!(value.store_uint(&mut builder, 23), Err(Error::IntOverflow));
+        assert_eq!(
+            Int257::new(-1).store_uint(&mut builder, 256),
+            Err(Error::IntOverflow)
+        );
+        let cell = builder.build()?;
+        let mut slice = cell.as_slice()?;
+        assert_eq!(Int257::load_uint(&mut slice, 24)?, value);
+
+        let mut slice = cell.as_slice()?;
+        assert_eq!(
+            Int257::load_int(&mut slice, 24)?,
+            Int257::new(0xabcdef - (1 << 24))
+        );
+        assert_eq!(Int257::load_int(&mut slice, 1), Err(Error::CellUnderflow));
+
+        Ok(())
+    }
+
+    #[test]
+    fn int257_dict_key() -> anyhow::Result<()> {
+        use crate::dict::Dict;
+
+        let keys = [Int257::MIN, Int257::new(-5), Int257::ZERO, Int257::MAX];
+
+        let mut dict = Dict::<Int257, u32>::new();
+        for (i, key) in keys.iter().enumerate() {
+            dict.set(key, i as u32)?;
+        }
+
+        let mut parsed = dict.keys().collect::<Result<Vec<_>, _>>()?;
+        parsed.sort();
+        assert_eq!(parsed, keys);
+
+        Ok(())
+    }
+
+    #[test]
+    fn int256_dict_key() -> anyhow::Result<()> {
+        use crate::dict::Dict;
+
+        assert_eq!(Int256::MIN.into_inner().bit_len(), 256);
+        assert_eq!(Int256::MAX.into_inner().bit_len(), 256);
+        assert_eq!(Int256::new(Int257::MAX), None);
+        assert_eq!(Int256::new(Int257::MIN), None);
+
+        let keys = [
+            Int256::MIN,
+            Int256::new(Int257::new(-5)).unwrap(),
+            Int256::new(Int257::new(-1)).unwrap(),
+            Int256::ZERO,
+            Int256::new(Int257::new(123)).unwrap(),
+            Int256::MAX,
+        ];
+
+        // Round-trip
+        for key in keys {
+            let cell = CellBuilder::build_from(key)?;
+            assert_eq!(cell.bit_len(), 256);
+            assert_eq!(cell.parse::<Int256>()?, key);
+        }
+
+        // Signed ordering
+        let mut dict = Dict::<Int256, u32>::new();
+        for (i, key) in keys.iter().enumerate().rev() {
+            dict.set(key, i as u32)?;
+        }
+
+        let mut parsed = dict.keys().collect::<Result<Vec<_>, _>>()?;
+        parsed.sort();
+        assert_eq!(parsed, keys);
+
+        assert_eq!(dict.get_min(true)?, Some((Int256::MIN, 0)));
+        assert_eq!(dict.get_max(true)?, Some((Int256::MAX, 5)));
+        assert_eq!(dict.get_next(Int256::ZERO, true)?, Some((keys[4], 4)));
+        assert_eq!(dict.get_prev(Int256::ZERO, true)?, Some((keys[2], 2)));
+
+        Ok(())
+    }
+}
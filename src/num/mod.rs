@@ -6,6 +6,10 @@ use crate::cell::*;
 use crate::error::{Error, ParseIntError};
 use crate::util::unlikely;
 
+pub use self::int257::{Int256, Int257};
+
+mod int257;
+
 macro_rules! impl_ops {
     ($ident:ident, $inner:ty) => {
         impl From<$ident> for $inner {
@@ -540,6 +544,23 @@ impl VarUint248 {
             hi.leading_zeros()
         }
     }
+
+    /// Checked integer addition. Computes `self + rhs`, returning `None` if overflow occurred.
+    pub const fn checked_add(self, rhs: Self) -> Option<Self> {
+        let (lhs_hi, lhs_lo) = self.into_words();
+        let (rhs_hi, rhs_lo) = rhs.into_words();
+
+        let (lo, carry) = lhs_lo.overflowing_add(rhs_lo);
+        let hi = match lhs_hi.checked_add(rhs_hi) {
+            Some(hi) => hi.checked_add(carry as u128),
+            None => None,
+        };
+
+        match hi {
+            Some(hi) if hi <= u128::MAX >> 8 => Some(Self::from_words(hi, lo)),
+            _ => None,
+        }
+    }
 }
 
 impl Ord for VarUint248 {
@@ -1029,6 +1050,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn var_uint248_operations() {
+        let value = VarUint248::new(u128::MAX);
+        assert_eq!(
+            value.checked_add(VarUint248::new(1)),
+            Some(VarUint248::from_words(1, 0))
+        );
+        assert_eq!(VarUint248::MAX.checked_add(VarUint248::new(1)), None);
+        assert_eq!(
+            VarUint248::from_words(u128::MAX >> 8, 0).checked_add(VarUint248::new(123)),
+            Some(VarUint248::from_words(u128::MAX >> 8, 123))
+        );
+    }
+
     #[test]
     fn var_uint248_deserialization() {
         let mut lo: u128 = 0xababcdef89abcdefdeadbeeffafacafe;